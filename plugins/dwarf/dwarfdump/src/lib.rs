@@ -106,7 +106,7 @@ fn get_info_string<R: Reader>(
                 // TODO: Using field name for this is weird.
                 InstructionTextTokenKind::FieldName {
                     offset: 0,
-                    type_names: vec![],
+                    type_names: Default::default(),
                 },
             ));
         } else {
@@ -117,7 +117,7 @@ fn get_info_string<R: Reader>(
                 // TODO: Using field name for this is weird.
                 InstructionTextTokenKind::FieldName {
                     offset: 0,
-                    type_names: vec![],
+                    type_names: Default::default(),
                 },
             ));
         }
@@ -97,6 +97,7 @@ fn active_local_cache(view: Option<&BinaryView>) -> Result<String> {
             .get_string_with_opts("pdb.files.localStoreRelative", &mut settings_query_options)
             .to_string();
         local_store_path = user_directory()
+            .map_err(|_| anyhow!("No user directory"))?
             .join(relative_local_store)
             .to_string_lossy()
             .to_string();
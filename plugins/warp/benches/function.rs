@@ -15,14 +15,18 @@ pub fn function_benchmark(c: &mut Criterion) {
 
     c.bench_function("signature first function", |b| {
         b.iter(|| {
-            let _ = build_function(&first_function, &first_function.low_level_il().unwrap());
+            let _ = build_function(
+                &first_function,
+                &first_function.low_level_il().unwrap(),
+                true,
+            );
         })
     });
 
     c.bench_function("signature all functions", |b| {
         b.iter(|| {
             for func in &functions {
-                let _ = build_function(&func, &func.low_level_il().unwrap());
+                let _ = build_function(&func, &func.low_level_il().unwrap(), true);
             }
         })
     });
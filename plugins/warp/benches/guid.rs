@@ -1,7 +1,7 @@
 use binaryninja::binary_view::BinaryViewExt;
 use binaryninja::headless::Session;
 use criterion::{criterion_group, criterion_main, Criterion};
-use warp_ninja::function_guid;
+use warp_ninja::{basic_block_guid, function_guid, sorted_basic_blocks};
 
 pub fn guid_benchmark(c: &mut Criterion) {
     let session = Session::new().expect("Failed to initialize session");
@@ -17,6 +17,28 @@ pub fn guid_benchmark(c: &mut Criterion) {
             function_guid(&first_function, &llil);
         })
     });
+
+    // The largest function exercises reading many instructions from a single basic block buffer.
+    let largest_function = functions
+        .iter()
+        .max_by_key(|f| f.highest_address() - f.lowest_address())
+        .unwrap();
+    let largest_llil = largest_function.low_level_il().unwrap();
+    let largest_basic_blocks = sorted_basic_blocks(&largest_function);
+    c.bench_function("basic block guids largest function", |b| {
+        b.iter(|| {
+            for basic_block in &largest_basic_blocks {
+                basic_block_guid(basic_block, &largest_llil);
+            }
+        })
+    });
+
+    // Reads the function once, compare against the per basic block reads above.
+    c.bench_function("function guid largest function", |b| {
+        b.iter(|| {
+            function_guid(&largest_function, &largest_llil);
+        })
+    });
 }
 
 criterion_group!(benches, guid_benchmark);
@@ -31,4 +31,19 @@ fn main() {
             }
         }
     }
+
+    // Copy the fixtures of individual tests to their own directory, they are not snapshot inputs.
+    for fixture_dir in ["hlil", "thunk"] {
+        let fixture_out_dir = out_dir_path.join(fixture_dir);
+        std::fs::create_dir_all(&fixture_out_dir).expect("failed to create fixture directory");
+        let entries = std::fs::read_dir(PathBuf::from("fixtures").join(fixture_dir));
+        for entry in entries.into_iter().flatten() {
+            let path = entry.unwrap().path();
+            // Only the binaries, the sources are kept next to them to document how they were built.
+            if path.is_file() && !path.extension().is_some_and(|ext| ext == "c") {
+                let dest_path = fixture_out_dir.join(path.file_name().unwrap());
+                std::fs::copy(&path, &dest_path).expect("failed to copy binary to OUT_DIR");
+            }
+        }
+    }
 }
@@ -0,0 +1,11 @@
+/*
+ * The same functions built with different instruction selection, used to test that HLIL function
+ * GUIDs are stable across compilers. Built with gcc 12.2:
+ *
+ *   gcc -O0 -c -fno-asynchronous-unwind-tables -fcf-protection=none -fno-pic instr_select.c -o instr_select_O0.o
+ *   gcc -O2 -c -fno-asynchronous-unwind-tables -fcf-protection=none -fno-pic instr_select.c -o instr_select_O2.o
+ */
+
+int return_zero(void) { return 0; }
+
+int add_one(int value) { return value + 1; }
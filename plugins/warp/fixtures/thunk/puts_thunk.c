@@ -0,0 +1,12 @@
+/*
+ * An executable calling an import through its PLT thunk, used to test the byte-only GUID of
+ * functions without LLIL. Built with gcc 12.2:
+ *
+ *   gcc -O2 -no-pie -fno-pie -fcf-protection=none -s puts_thunk.c -o puts_thunk
+ */
+#include <stdio.h>
+
+int main(void) {
+    puts("hello");
+    return 0;
+}
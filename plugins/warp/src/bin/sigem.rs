@@ -1,43 +1,67 @@
 use std::collections::HashSet;
 use std::fs::File;
-use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
 
 use ar::Archive;
-use clap::{arg, Parser};
+use clap::{arg, Parser, Subcommand};
 use rayon::prelude::*;
 
 use binaryninja::binary_view::{BinaryView, BinaryViewExt};
-use binaryninja::function::Function as BNFunction;
-use binaryninja::rc::Guard as BNGuard;
+use binaryninja::headless::Session;
+use binaryninja::rc::Ref;
 use binaryninja::settings::Settings;
 use serde_json::{json, Value};
+use thiserror::Error;
 use walkdir::WalkDir;
 use warp::signature::Data;
 use warp_ninja::cache::{cached_type_references, register_cache_destructor};
+use warp_ninja::data::DataExt;
+use warp_ninja::match_result::{GuidLevel, MatchKind, MatchResult};
+use warp_ninja::{
+    build_byte_function, build_hlil_function, check_named_functions, match_result_for,
+    match_view_with_files, read_signature_file, should_signature_function, GenerationSettings,
+    GuidOptions, GuidString, MissingNamedFunctions,
+};
 
 #[derive(Parser, Debug)]
-#[command(about, long_about)]
+#[command(
+    about,
+    long_about,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 /// A simple CLI utility to generate WARP signature files headlessly using Binary Ninja.
 ///
 /// NOTE: This requires a headless compatible Binary Ninja, make sure it's in your path.
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to create signatures from, this can be:
     /// - A binary (that can be opened with Binary Ninja)
     /// - A directory (all files will be merged)
     /// - An archive (with ext: a, lib, rlib)
-    /// - A BNDB
+    /// - A BNDB (the analysis stored in the database is kept)
     /// - A Signature file (sbin)
-    #[arg(index = 1, verbatim_doc_comment)]
-    path: PathBuf,
+    #[arg(index = 1, required = true, verbatim_doc_comment)]
+    path: Option<PathBuf>,
 
-    /// The signature output file
+    /// The signature output file, or the output directory when using `--split`
     ///
     /// NOTE: If not specified the output will be the input path with the sbin extension
-    /// as an example `mylib.a` will output `mylib.sbin`.
+    /// as an example `mylib.a` will output `mylib.sbin`, with `--split` the output directory
+    /// will be `mylib_sbin`.
     #[arg(index = 2)]
     output: Option<PathBuf>,
 
+    /// Write one signature file per input file instead of merging them into a single file
+    ///
+    /// NOTE: Archive entries and files in a directory will keep their relative path in the output directory.
+    #[arg(long)]
+    split: bool,
+
     /// Should we overwrite output file
     ///
     /// NOTE: If the file exists we will exit early to prevent wasted effort.
@@ -47,11 +71,166 @@ struct Args {
     /// The external debug information file to use
     #[arg(short, long)]
     debug_info: Option<PathBuf>,
+
+    /// Analysis settings to merge over the defaults, either a path to a JSON file or inline JSON
+    ///
+    /// Example: `--analysis-config '{"analysis.mode": "intermediate"}'`
+    #[arg(long)]
+    analysis_config: Option<String>,
+
+    /// Include functions with auto-generated names (e.g. `sub_401000`)
+    ///
+    /// NOTE: Useful when building signatures to match against the same stripped binary.
+    #[arg(long)]
+    include_unnamed: bool,
+
+    /// Use the experimental HLIL GUIDs instead of the LLIL GUIDs
+    ///
+    /// NOTE: HLIL GUIDs are less sensitive to the compiler version, but they will only match against
+    /// functions which also have HLIL GUIDs.
+    #[arg(long)]
+    hlil: bool,
+
+    /// Keep NOPs and redundant moves in the function GUIDs
+    ///
+    /// NOTE: This makes GUIDs more specific, but hot-patched functions will no longer match. The
+    /// signatures will only match views with `analysis.warp.blacklistNops` disabled.
+    #[arg(long)]
+    no_nop_blacklist: bool,
+
+    /// Skip functions larger than this many bytes
+    ///
+    /// NOTE: Giant functions (e.g. obfuscated dispatchers) dominate generation time and match
+    /// unreliably. By default there is no limit.
+    #[arg(long)]
+    max_function_size: Option<u64>,
+
+    /// Reference named types nested deeper than this by name only
+    ///
+    /// NOTE: Lowers the time spent converting deeply nested types (e.g. template heavy C++), the
+    /// types past the depth have no type GUID. By default there is no limit.
+    #[arg(long)]
+    max_type_depth: Option<u64>,
+
+    /// Compare the signature file at the input path with this signature file and print the differences
+    ///
+    /// NOTE: No signatures are generated, both files must be signature files (sbin).
+    #[arg(long)]
+    diff: Option<PathBuf>,
     // TODO: Add a file filter and default to filter out files starting with "."
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Match a signature file against a binary and report how many functions matched
+    Match(MatchArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct MatchArgs {
+    /// The signature file (sbin) to match with
+    signatures: PathBuf,
+
+    /// The binary (or BNDB) to match against
+    target: PathBuf,
+
+    /// Print the report as JSON instead of text
+    #[arg(long)]
+    json: bool,
+
+    /// Fall back to the experimental HLIL GUIDs for functions which did not match otherwise
+    ///
+    /// NOTE: This requires HLIL for every unmatched function, which is slow on large binaries.
+    #[arg(long)]
+    hlil: bool,
+}
+
+/// Why no signatures could be created from an input path.
+#[derive(Error, Debug)]
+enum DataError {
+    /// Binary Ninja could not open the file as a binary.
+    #[error("Binary Ninja could not open {0:?}, is it a supported format? (check your license and Binary Ninja version)")]
+    UnsupportedFormat(PathBuf),
+    /// The file could be opened as the expected format, but reading it failed.
+    #[error("Failed to load {path:?}: {reason}")]
+    LoadFailed { path: PathBuf, reason: String },
+    /// No signatures could be created from any entry of the archive or directory.
+    #[error("No signatures could be created from any entry of {0:?}")]
+    EmptyArchive(PathBuf),
+    /// The signature file is not a valid signature file.
+    #[error("Failed to parse signature file {0:?}, is it corrupt?")]
+    ParseError(PathBuf),
+    /// The user cancelled with Ctrl-C, see [`is_cancelled`].
+    #[error("Cancelled creating signatures for {0:?}")]
+    Cancelled(PathBuf),
+}
+
+impl DataError {
+    fn load_failed(path: &Path, reason: impl ToString) -> Self {
+        DataError::LoadFailed {
+            path: path.to_owned(),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// The process exit code for the error, so that scripts can tell failures apart.
+    fn exit_code(&self) -> i32 {
+        match self {
+            DataError::UnsupportedFormat(_) => 2,
+            DataError::LoadFailed { .. } => 3,
+            DataError::EmptyArchive(_) => 4,
+            DataError::ParseError(_) => 5,
+            // The conventional exit code for SIGINT.
+            DataError::Cancelled(_) => 130,
+        }
+    }
+}
+
+/// Set by the Ctrl-C handler, no new inputs are started and merges are cancelled once set.
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+fn is_cancelled() -> bool {
+    CANCELLED.load(Relaxed)
+}
+
+/// Cancel on the first Ctrl-C so that the inputs in progress finish, exit on the second.
+fn register_cancel_handler() {
+    let result = ctrlc::set_handler(|| {
+        if CANCELLED.swap(true, Relaxed) {
+            std::process::exit(130);
+        }
+        log::warn!(
+            "Cancelling, waiting for the inputs in progress... (press Ctrl-C again to exit now)"
+        );
+    });
+    if let Err(e) = result {
+        log::warn!("Failed to register Ctrl-C handler: {}", e);
+    }
+}
+
+/// Merge the data of `path`, logging the progress and stopping if cancelled.
+fn merge_data(path: &Path, data: Vec<Data>) -> Result<Data, DataError> {
+    log::info!("Merging {} entries of {:?}...", data.len(), path);
+    Data::merge_with_progress(data, &mut |done, total| {
+        log::debug!("Merging {:?} ({}/{})", path, done, total);
+        !is_cancelled()
+    })
+    .ok_or_else(|| DataError::Cancelled(path.to_owned()))
+}
+
+/// Options shared by every input visited while generating signatures.
+#[derive(Debug, Clone)]
+struct GenerationOptions {
+    /// The analysis settings passed to [`binaryninja::load_with_options`].
+    settings: Value,
+    /// Whether functions with auto-generated names are kept.
+    include_unnamed: bool,
+    /// Whether the experimental HLIL GUIDs are used, see [`warp_ninja::function_guid_hlil`].
+    hlil: bool,
+}
+
 fn default_settings(bn_settings: &Settings) -> Value {
-    // TODO: Make these settings configurable through the CLI
+    // NOTE: These can be overridden with `--analysis-config`.
     let mut settings = json!({
         "analysis.linearSweep.autorun": false,
         "analysis.signatureMatcher.autorun": false,
@@ -72,10 +251,68 @@ fn default_settings(bn_settings: &Settings) -> Value {
     settings
 }
 
+/// The settings used when loading a database, so that the existing analysis is preserved.
+///
+/// Only the WARP settings are kept, the remaining settings would override the settings stored in
+/// the database and change the analysis of a binary the user already spent time on.
+fn database_settings(settings: &Value) -> Value {
+    let warp_settings = settings
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| key.starts_with("analysis.warp."))
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .collect::<serde_json::Map<_, _>>();
+    Value::Object(warp_settings)
+}
+
+/// Parse the analysis settings given by the user, `config` is either a path to a JSON file or inline JSON.
+fn parse_analysis_config(config: &str) -> Result<Value, String> {
+    let config_path = Path::new(config);
+    let config_str = if config_path.is_file() {
+        std::fs::read_to_string(config_path)
+            .map_err(|e| format!("Failed to read analysis config {:?}: {}", config_path, e))?
+    } else {
+        config.to_string()
+    };
+    match serde_json::from_str::<Value>(&config_str) {
+        Ok(value) if value.is_object() => Ok(value),
+        Ok(_) => Err("Analysis config must be a JSON object of setting names to values".into()),
+        Err(e) => Err(format!("Analysis config is not valid JSON: {}", e)),
+    }
+}
+
+/// Merge the user supplied settings over the default settings, user settings take priority.
+fn merge_settings(settings: &mut Value, overrides: Value) {
+    if let (Some(settings), Value::Object(overrides)) = (settings.as_object_mut(), overrides) {
+        settings.extend(overrides);
+    }
+}
+
 fn main() {
     let args = Args::parse();
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
+    if let Some(Command::Match(match_args)) = &args.command {
+        if let Err(e) = run_match(match_args) {
+            log::error!("{}", e);
+            std::process::exit(e.exit_code());
+        }
+        return;
+    }
+    let path = args
+        .path
+        .expect("The path is required without a subcommand");
+
+    // Comparing signature files requires no analysis, so there is no need for a session.
+    if let Some(other_path) = &args.diff {
+        if let Err(e) = print_diff(&path, other_path) {
+            log::error!("{}", e);
+            std::process::exit(e.exit_code());
+        }
+        return;
+    }
+
     // TODO: After analysis finishes for a file we should save off the bndb to another directory called the bndb cache
     // TODO: This cache should be used before opening a file for first analysis.
 
@@ -83,17 +320,104 @@ fn main() {
     // If no output file was given, just prepend binary with extension sbin
     let output_file = args
         .output
-        .unwrap_or(args.path.to_owned())
+        .clone()
+        .unwrap_or(path.to_owned())
         .with_extension("sbin");
 
-    if output_file.exists() && !args.overwrite.unwrap_or(false) {
+    let overwrite = args.overwrite.unwrap_or(false);
+    if !args.split && output_file.exists() && !overwrite {
         log::info!("Output file already exists, skipping... {:?}", output_file);
         return;
     }
+    let output_dir = args.output.unwrap_or_else(|| split_output_dir(&path));
+    if args.split && !overwrite {
+        // Check before generating, otherwise a collision is only reported at the end of a long run.
+        let existing_files = split_output_names(&path)
+            .into_iter()
+            .map(|name| output_dir.join(name).with_extension("sbin"))
+            .filter(|output_file| output_file.exists())
+            .collect::<Vec<_>>();
+        if !existing_files.is_empty() {
+            log::info!(
+                "Output files already exist, skipping (use --overwrite to replace them)... {:?}",
+                existing_files
+            );
+            return;
+        }
+    }
+
+    // Validate the analysis config before we spend time starting the session.
+    let analysis_config = match args.analysis_config.as_deref().map(parse_analysis_config) {
+        Some(Ok(overrides)) => Some(overrides),
+        Some(Err(e)) => {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+        None => None,
+    };
+
+    let _headless_session = start_session();
+    register_cancel_handler();
+
+    let bn_settings = Settings::new();
+    let mut settings = default_settings(&bn_settings);
+    if let Some(overrides) = analysis_config {
+        merge_settings(&mut settings, overrides);
+    }
+    if args.no_nop_blacklist {
+        settings[GuidOptions::BLACKLIST_NOPS_SETTING] = json!(false);
+    }
+    if let Some(max_function_size) = args.max_function_size {
+        settings[GenerationSettings::MAX_FUNCTION_SIZE_SETTING] = json!(max_function_size);
+    }
+    if let Some(max_type_depth) = args.max_type_depth {
+        settings[GenerationSettings::MAX_TYPE_DEPTH_SETTING] = json!(max_type_depth);
+    }
+    log::debug!("Using analysis settings: {}", settings);
+
+    let options = GenerationOptions {
+        settings,
+        include_unnamed: args.include_unnamed,
+        hlil: args.hlil,
+    };
+
+    // TODO: Add a way to override the symbol type to make it a different function symbol.
+    // TODO: Right now the consumers must dictate that.
+    // TODO: The binja_warp consumer sets this to library function fwiw
+
+    log::info!("Creating functions for {:?}...", path);
+    let start = std::time::Instant::now();
+    if args.split {
+        let split_data = split_data_from_file(&options, &path);
+        log::info!("Functions created in {:?}", start.elapsed());
+        for (name, data) in split_data {
+            let output_file = output_dir.join(&name).with_extension("sbin");
+            if output_file.exists() && !overwrite {
+                log::info!("Output file already exists, skipping... {:?}", output_file);
+                continue;
+            }
+            if let Some(parent) = output_file.parent() {
+                std::fs::create_dir_all(parent).expect("Failed to create output directory");
+            }
+            write_data(&output_file, &data, &name);
+        }
+    } else {
+        let data = match data_from_file(&options, &path) {
+            Ok(data) => data,
+            Err(e) => {
+                log::error!("{}", e);
+                std::process::exit(e.exit_code());
+            }
+        };
+        log::info!("Functions created in {:?}", start.elapsed());
+        write_data(&output_file, &data, &path);
+    }
+}
 
+/// Start the headless session and register what is needed to load views with the WARP settings.
+fn start_session() -> Session {
     log::debug!("Starting Binary Ninja session...");
-    let _headless_session =
-        binaryninja::headless::Session::new().expect("Failed to initialize session");
+    let headless_session = Session::new().expect("Failed to initialize session");
 
     // Adjust the amount of worker threads so that we can actually free BinaryViews.
     let worker_count = rayon::current_num_threads() * 4;
@@ -103,63 +427,130 @@ fn main() {
     // Make sure caches are flushed when the views get destructed.
     register_cache_destructor();
 
-    let bn_settings = Settings::new();
-    let settings = default_settings(&bn_settings);
-
-    log::info!("Creating functions for {:?}...", args.path);
-    let start = std::time::Instant::now();
-    let data = data_from_file(&settings, &args.path)
-        .expect("Failed to read data, check your license and Binary Ninja version!");
-    log::info!("Functions created in {:?}", start.elapsed());
+    // The GUID and generation settings must exist to be set when loading the views.
+    GuidOptions::register();
+    GenerationSettings::register();
+    headless_session
+}
 
-    // TODO: Add a way to override the symbol type to make it a different function symbol.
-    // TODO: Right now the consumers must dictate that.
-    // TODO: The binja_warp consumer sets this to library function fwiw
+/// The default output directory for `--split`, as an example `mylib.a` will output to `mylib_sbin`.
+fn split_output_dir(path: &Path) -> PathBuf {
+    let file_stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}_sbin", file_stem))
+}
 
-    if !data.functions.is_empty() {
-        std::fs::write(&output_file, data.to_bytes()).expect("Failed to write functions to file");
+fn write_data(output_file: &Path, data: &Data, input_path: &Path) {
+    if !data.is_empty() {
+        std::fs::write(output_file, data.to_bytes()).expect("Failed to write functions to file");
         log::info!(
-            "{} functions written to {:?}...",
-            data.functions.len(),
+            "{} functions and {} types written to {:?}...",
+            data.function_count(),
+            data.type_count(),
             output_file
         );
+        log::info!("GUID stats: {}", data.guid_stats());
     } else {
-        log::warn!("No functions found for binary {:?}...", args.path);
+        log::warn!("No functions or types found for binary {:?}...", input_path);
     }
 }
 
-fn data_from_view(view: &BinaryView) -> Data {
-    let mut data = Data::default();
-    let is_function_named = |f: &BNGuard<BNFunction>| {
-        !f.symbol().short_name().as_str().contains("sub_") || f.has_user_annotations()
-    };
+fn data_from_view(options: &GenerationOptions, view: &BinaryView) -> Data {
+    match check_named_functions(view) {
+        Err(MissingNamedFunctions::NoFunctions) => log::warn!(
+            "No functions found in {}, analysis might be incomplete...",
+            view.file().filename()
+        ),
+        Err(MissingNamedFunctions::NoNamedFunctions(count)) if !options.include_unnamed => {
+            log::warn!(
+                "None of the {} functions in {} are named (stripped binary?), use `--include-unnamed` to include them...",
+                count,
+                view.file().filename()
+            )
+        }
+        _ => {}
+    }
 
+    let generation_settings = GenerationSettings::from_view(view);
+    let too_large_functions = AtomicUsize::default();
+
+    let start = std::time::Instant::now();
+    let mut data = Data::default();
+    // Referenced types are converted while building the functions, the type reference cache is
+    // shared between threads, so the types are converted in parallel as well. Types which no
+    // function references are not part of the signature data, so no separate pass is needed.
     data.functions = view
         .functions()
-        .iter()
-        .filter(is_function_named)
+        .par_iter()
+        // Cheap predicates first so that LLIL is only retrieved for functions we keep.
+        .filter(|f| should_signature_function(f, options.include_unnamed, &generation_settings))
+        .filter(|f| {
+            let too_large = generation_settings.is_function_too_large(f);
+            if too_large {
+                too_large_functions.fetch_add(1, Relaxed);
+            }
+            !too_large
+        })
         .filter_map(|f| {
-            let llil = f.low_level_il().ok()?;
-            Some(warp_ninja::cache::cached_function(&f, &llil))
+            if options.hlil {
+                return build_hlil_function(&f);
+            }
+            match f.low_level_il() {
+                Ok(llil) => Some(warp_ninja::cache::cached_function(&f, &llil)),
+                // No LLIL (e.g. import thunks or user annotated functions which failed to lift),
+                // fallback to the byte-only GUID so the function is not dropped.
+                Err(_) => build_byte_function(&f),
+            }
         })
         .collect::<Vec<_>>();
 
-    if let Some(ref_ty_cache) = cached_type_references(view) {
-        let referenced_types = ref_ty_cache
-            .cache
-            .iter()
-            .filter_map(|t| t.to_owned())
-            .collect::<Vec<_>>();
+    let too_large_functions = too_large_functions.into_inner();
+    if too_large_functions > 0 {
+        log::info!(
+            "Skipped {} functions in {} larger than {} bytes",
+            too_large_functions,
+            view.file().filename(),
+            generation_settings.max_function_size.unwrap_or_default()
+        );
+    }
 
-        data.types.extend(referenced_types);
+    // The types were already converted above, this only collects them.
+    if let Some(ref_ty_cache) = cached_type_references(view) {
+        data.types.extend(ref_ty_cache.computed_types());
     }
+    log::debug!(
+        "Created {} functions and {} types for {} in {:?}",
+        data.functions.len(),
+        data.types.len(),
+        view.file().filename(),
+        start.elapsed()
+    );
 
     data
 }
 
-fn data_from_archive<R: Read>(settings: &Value, mut archive: Archive<R>) -> Option<Data> {
+fn data_from_archive(options: &GenerationOptions, path: &Path) -> Result<Data, DataError> {
+    let entry_data = archive_entry_data(options, path)?
+        .into_iter()
+        .map(|(_, data)| data)
+        .collect::<Vec<_>>();
+    if entry_data.is_empty() {
+        return Err(DataError::EmptyArchive(path.to_owned()));
+    }
+    merge_data(path, entry_data)
+}
+
+/// Create the data for each entry in the archive, returned alongside the entry name.
+///
+/// Entries which fail are logged and skipped.
+fn archive_entry_data(
+    options: &GenerationOptions,
+    path: &Path,
+) -> Result<Vec<(PathBuf, Data)>, DataError> {
+    let archive_file = File::open(path).map_err(|e| DataError::load_failed(path, e))?;
+    let mut archive = Archive::new(archive_file);
     // TODO: I feel like this is a hack...
-    let temp_dir = tempdir::TempDir::new("tmp_archive").ok()?;
+    let temp_dir =
+        tempdir::TempDir::new("tmp_archive").map_err(|e| DataError::load_failed(path, e))?;
     // Iterate through the entries in the ar file and make a temp dir with them
     let mut entry_files: HashSet<PathBuf> = HashSet::new();
     while let Some(entry) = archive.next_entry() {
@@ -184,18 +575,37 @@ fn data_from_archive<R: Read>(settings: &Value, mut archive: Archive<R>) -> Opti
     }
 
     // Create the data.
+    let total_entries = entry_files.len();
+    let done_entries = AtomicUsize::default();
     let entry_data = entry_files
         .into_par_iter()
         .filter_map(|path| {
+            if is_cancelled() {
+                return None;
+            }
             log::debug!("Creating data for ENTRY {:?}...", path);
-            data_from_file(settings, &path)
+            let data = data_from_file(options, &path)
+                .inspect_err(|e| log::warn!("Skipping archive entry: {}", e))
+                .ok();
+            log::info!(
+                "Created data for ENTRY {:?} ({}/{})",
+                path.file_name().unwrap_or_default(),
+                done_entries.fetch_add(1, Relaxed) + 1,
+                total_entries
+            );
+            let data = data?;
+            let entry_name = path.strip_prefix(temp_dir.path()).ok()?.to_owned();
+            Some((entry_name, data))
         })
         .collect::<Vec<_>>();
 
-    Some(Data::merge(entry_data))
+    if is_cancelled() {
+        return Err(DataError::Cancelled(path.to_owned()));
+    }
+    Ok(entry_data)
 }
 
-fn data_from_directory(settings: &Value, dir: PathBuf) -> Option<Data> {
+fn data_from_directory(options: &GenerationOptions, dir: &Path) -> Result<Data, DataError> {
     let files = WalkDir::new(dir)
         .into_iter()
         .filter_map(|e| {
@@ -208,39 +618,292 @@ fn data_from_directory(settings: &Value, dir: PathBuf) -> Option<Data> {
         })
         .collect::<Vec<_>>();
 
+    let total_files = files.len();
+    let done_files = AtomicUsize::default();
     let unmerged_data = files
         .into_par_iter()
         .filter_map(|path| {
+            if is_cancelled() {
+                return None;
+            }
             log::info!("Creating data for FILE {:?}...", path);
-            data_from_file(settings, &path)
+            let data = data_from_file(options, &path)
+                .inspect_err(|e| log::warn!("Skipping file: {}", e))
+                .ok();
+            log::info!(
+                "Created data for FILE {:?} ({}/{})",
+                path,
+                done_files.fetch_add(1, Relaxed) + 1,
+                total_files
+            );
+            data
         })
         .collect::<Vec<_>>();
 
-    if !unmerged_data.is_empty() {
-        Some(Data::merge(unmerged_data))
+    if is_cancelled() {
+        Err(DataError::Cancelled(dir.to_owned()))
+    } else if !unmerged_data.is_empty() {
+        merge_data(dir, unmerged_data)
     } else {
-        None
+        Err(DataError::EmptyArchive(dir.to_owned()))
+    }
+}
+
+/// Create the data for each input file separately, returned alongside the path relative to the input.
+///
+/// Each data is merged on its own so that GUIDs are still resolved within the scope of that file.
+fn split_data_from_file(options: &GenerationOptions, path: &Path) -> Vec<(PathBuf, Data)> {
+    match path.extension() {
+        Some(ext) if ext == "a" || ext == "lib" || ext == "rlib" => {
+            // Entries are placed in a directory named after the archive.
+            let archive_dir = PathBuf::from(path.file_stem().unwrap_or_default());
+            archive_entry_data(options, path)
+                .inspect_err(|e| log::error!("{}", e))
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(name, data)| (archive_dir.join(name), Data::merge(vec![data])))
+                .collect()
+        }
+        _ if path.is_dir() => WalkDir::new(path)
+            .into_iter()
+            .filter_map(|e| {
+                let entry_path = e.ok()?.into_path();
+                entry_path.is_file().then_some(entry_path)
+            })
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .flat_map(|entry_path| {
+                let relative_path = entry_path.strip_prefix(path).unwrap_or(&entry_path);
+                let relative_dir = relative_path.parent().unwrap_or(Path::new("")).to_owned();
+                split_data_from_file(options, &entry_path)
+                    .into_iter()
+                    .map(|(name, data)| (relative_dir.join(name), data))
+                    .collect::<Vec<_>>()
+            })
+            .collect(),
+        _ => {
+            let file_name = PathBuf::from(path.file_name().unwrap_or_default());
+            data_from_file(options, path)
+                .inspect_err(|e| log::error!("{}", e))
+                .map(|data| vec![(file_name, Data::merge(vec![data]))])
+                .unwrap_or_default()
+        }
     }
 }
 
-fn data_from_file(settings: &Value, path: &Path) -> Option<Data> {
+/// The names [`split_data_from_file`] returns for the input, without creating any data.
+///
+/// Archive entries are only listed, not extracted.
+fn split_output_names(path: &Path) -> Vec<PathBuf> {
     match path.extension() {
         Some(ext) if ext == "a" || ext == "lib" || ext == "rlib" => {
-            let archive_file = File::open(path).expect("Failed to open archive file");
-            let archive = Archive::new(archive_file);
-            data_from_archive(settings, archive)
+            let archive_dir = PathBuf::from(path.file_stem().unwrap_or_default());
+            let Ok(archive_file) = File::open(path) else {
+                return Vec::new();
+            };
+            let mut archive = Archive::new(archive_file);
+            let mut entry_names = Vec::new();
+            while let Some(entry) = archive.next_entry() {
+                if let Ok(entry) = entry {
+                    let name = String::from_utf8_lossy(entry.header().identifier()).to_string();
+                    let entry_name = archive_dir.join(name);
+                    if !entry_names.contains(&entry_name) {
+                        entry_names.push(entry_name);
+                    }
+                }
+            }
+            entry_names
+        }
+        _ if path.is_dir() => WalkDir::new(path)
+            .into_iter()
+            .filter_map(|e| {
+                let entry_path = e.ok()?.into_path();
+                entry_path.is_file().then_some(entry_path)
+            })
+            .flat_map(|entry_path| {
+                let relative_path = entry_path.strip_prefix(path).unwrap_or(&entry_path);
+                let relative_dir = relative_path.parent().unwrap_or(Path::new("")).to_owned();
+                split_output_names(&entry_path)
+                    .into_iter()
+                    .map(move |name| relative_dir.join(name))
+            })
+            .collect(),
+        _ => vec![PathBuf::from(path.file_name().unwrap_or_default())],
+    }
+}
+
+fn data_from_signature_file(path: &Path) -> Result<Data, DataError> {
+    read_signature_file(path)
+        .map_err(|e| DataError::load_failed(path, e))?
+        .ok_or_else(|| DataError::ParseError(path.to_owned()))
+}
+
+/// Print what changed going from the signature file at `path` to the one at `other_path`.
+fn print_diff(path: &Path, other_path: &Path) -> Result<(), DataError> {
+    let data = data_from_signature_file(path)?;
+    let other_data = data_from_signature_file(other_path)?;
+    let diff = data.diff(&other_data);
+    print!("{}", diff);
+    log::info!(
+        "{} functions added, {} removed, {} changed, {} types added, {} removed",
+        diff.added_functions.len(),
+        diff.removed_functions.len(),
+        diff.changed_functions.len(),
+        diff.added_types.len(),
+        diff.removed_types.len()
+    );
+    Ok(())
+}
+
+/// The GUID levels tried when matching with `sigem match`, the same GUIDs signatures are generated with.
+const MATCH_LEVELS: [GuidLevel; 2] = [GuidLevel::Llil, GuidLevel::Bytes];
+/// The GUID levels tried when matching with `sigem match --hlil`, see [`MATCH_LEVELS`].
+const HLIL_MATCH_LEVELS: [GuidLevel; 3] = [GuidLevel::Llil, GuidLevel::Bytes, GuidLevel::Hlil];
+
+/// How well a signature file matched a binary, see [`run_match`].
+#[derive(Debug, Default)]
+struct MatchReport {
+    total_functions: usize,
+    /// The address and match result of every function matched by the signature file, sorted by address.
+    matched: Vec<(u64, MatchResult)>,
+}
+
+impl MatchReport {
+    /// Collect the matches of the functions matched by the signature file at `signatures`.
+    ///
+    /// Matches from other signature files (e.g. stored in a database) are not included.
+    fn from_view(view: &BinaryView, signatures: &Path) -> Self {
+        let functions = view.functions();
+        let mut matched = functions
+            .iter()
+            .filter_map(|function| {
+                let result = match_result_for(view, function.start())?;
+                let from_signatures = result.source.as_deref() == Some(signatures);
+                from_signatures.then(|| (function.start(), result))
+            })
+            .collect::<Vec<_>>();
+        matched.sort_by_key(|(address, _)| *address);
+        Self {
+            total_functions: functions.len(),
+            matched,
+        }
+    }
+
+    fn kind_count(&self, kind: MatchKind) -> usize {
+        self.matched
+            .iter()
+            .filter(|(_, result)| result.kind == kind)
+            .count()
+    }
+
+    fn unmatched_count(&self) -> usize {
+        self.total_functions.saturating_sub(self.matched.len())
+    }
+
+    fn to_json(&self) -> Value {
+        let functions = self
+            .matched
+            .iter()
+            .map(|(address, result)| {
+                json!({
+                    "address": address,
+                    "name": result.name,
+                    "guid": GuidString(result.guid).to_string(),
+                    "kind": result.kind.to_string(),
+                    "confidence": result.confidence,
+                    "level": result.level.map(|level| level.to_string()),
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "total_functions": self.total_functions,
+            "matched": self.matched.len(),
+            "matched_by_guid": self.kind_count(MatchKind::Guid),
+            "matched_by_constraints": self.kind_count(MatchKind::Constraints),
+            "unmatched": self.unmatched_count(),
+            "functions": functions,
+        })
+    }
+}
+
+impl std::fmt::Display for MatchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let matched_percent = match self.total_functions {
+            0 => 0.0,
+            total => self.matched.len() as f64 * 100.0 / total as f64,
+        };
+        writeln!(
+            f,
+            "Matched {} of {} functions ({:.1}%)",
+            self.matched.len(),
+            self.total_functions,
+            matched_percent
+        )?;
+        writeln!(f, "  by GUID:        {}", self.kind_count(MatchKind::Guid))?;
+        writeln!(
+            f,
+            "  by constraints: {}",
+            self.kind_count(MatchKind::Constraints)
+        )?;
+        writeln!(f, "Unmatched:        {}", self.unmatched_count())
+    }
+}
+
+/// Match the signature file against the target binary and print the report.
+fn run_match(match_args: &MatchArgs) -> Result<(), DataError> {
+    // The matcher skips signature files which fail to parse, fail before spending time on analysis instead.
+    data_from_signature_file(&match_args.signatures)?;
+
+    let _headless_session = start_session();
+    let bn_settings = Settings::new();
+    let settings = default_settings(&bn_settings);
+    log::info!("Analyzing {:?}...", match_args.target);
+    let view = load_view(&settings, &match_args.target)?;
+    log::info!("Matching {:?}...", match_args.signatures);
+    let levels: &[GuidLevel] = match match_args.hlil {
+        true => &HLIL_MATCH_LEVELS,
+        false => &MATCH_LEVELS,
+    };
+    let start = std::time::Instant::now();
+    match_view_with_files(&view, &[match_args.signatures.to_owned()], levels);
+    log::info!("Matching took {:?}", start.elapsed());
+    let report = MatchReport::from_view(&view, &match_args.signatures);
+    view.file().close();
+
+    match match_args.json {
+        true => println!("{}", report.to_json()),
+        false => print!("{}", report),
+    }
+    Ok(())
+}
+
+/// Load the binary or database at `path` with the analysis `settings`.
+fn load_view(settings: &Value, path: &Path) -> Result<Ref<BinaryView>, DataError> {
+    match path.extension() {
+        Some(ext) if ext == "bndb" => {
+            // The database keeps its own analysis settings, only the WARP activities are disabled.
+            // Analysis is not updated, the saved analysis state is what gets signatured.
+            let settings = database_settings(settings);
+            binaryninja::load_with_options(path, false, Some(settings.to_string()))
+                .ok_or_else(|| DataError::load_failed(path, "could not open the database"))
         }
-        Some(ext) if ext == "sbin" => {
-            let contents = std::fs::read(path).ok()?;
-            Data::from_bytes(&contents)
+        _ => binaryninja::load_with_options(path, true, Some(settings.to_string()))
+            .ok_or_else(|| DataError::UnsupportedFormat(path.to_owned())),
+    }
+}
+
+fn data_from_file(options: &GenerationOptions, path: &Path) -> Result<Data, DataError> {
+    match path.extension() {
+        Some(ext) if ext == "a" || ext == "lib" || ext == "rlib" => {
+            data_from_archive(options, path)
         }
-        _ if path.is_dir() => data_from_directory(settings, path.into()),
+        Some(ext) if ext == "sbin" => data_from_signature_file(path),
+        _ if path.is_dir() => data_from_directory(options, path),
         _ => {
-            let path_str = path.to_str().unwrap();
-            let view = binaryninja::load_with_options(path_str, true, Some(settings.to_string()))?;
-            let data = data_from_view(&view);
+            let view = load_view(&options.settings, path)?;
+            let data = data_from_view(options, &view);
             view.file().close();
-            Some(data)
+            Ok(data)
         }
     }
 }
@@ -256,14 +919,168 @@ mod tests {
         let _headless_session =
             binaryninja::headless::Session::new().expect("Failed to initialize session");
         let bn_settings = Settings::new();
-        let settings = default_settings(&bn_settings);
+        let options = GenerationOptions {
+            settings: default_settings(&bn_settings),
+            include_unnamed: false,
+            hlil: false,
+        };
         for entry in std::fs::read_dir(out_dir).expect("Failed to read OUT_DIR") {
             let entry = entry.expect("Failed to read directory entry");
             let path = entry.path();
             if path.is_file() {
-                let result = data_from_file(&settings, &path);
-                assert!(result.is_some());
+                let result = data_from_file(&options, &path);
+                assert!(result.is_ok(), "{:?}", result.err());
             }
         }
     }
+
+    #[test]
+    fn test_data_from_database() {
+        let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
+        let _headless_session =
+            binaryninja::headless::Session::new().expect("Failed to initialize session");
+        let bn_settings = Settings::new();
+        let options = GenerationOptions {
+            settings: default_settings(&bn_settings),
+            include_unnamed: true,
+            hlil: false,
+        };
+        let Some(path) = std::fs::read_dir(&out_dir)
+            .expect("Failed to read OUT_DIR")
+            .filter_map(|e| Some(e.ok()?.path()))
+            .find(|path| path.is_file() && path.extension().is_some_and(|ext| ext != "sbin"))
+        else {
+            return;
+        };
+
+        let view = binaryninja::load_with_options(&path, true, Some(options.settings.to_string()))
+            .expect("Failed to load binary");
+        let expected = data_from_view(&options, &view);
+        let db_dir = std::env::temp_dir().join(format!("sigem_bndb_{}", std::process::id()));
+        std::fs::create_dir_all(&db_dir).unwrap();
+        let db_path = db_dir.join("analyzed.bndb");
+        assert!(view.file().create_database(&db_path));
+        view.file().close();
+
+        let data = data_from_file(&options, &db_path).expect("Failed to read database");
+        assert_eq!(data.functions.len(), expected.functions.len());
+
+        std::fs::remove_dir_all(&db_dir).unwrap();
+    }
+
+    #[test]
+    fn test_data_errors() {
+        let _headless_session =
+            binaryninja::headless::Session::new().expect("Failed to initialize session");
+        let bn_settings = Settings::new();
+        let options = GenerationOptions {
+            settings: default_settings(&bn_settings),
+            include_unnamed: false,
+            hlil: false,
+        };
+        let dir = std::env::temp_dir().join(format!("sigem_errors_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let corrupt_path = dir.join("corrupt.sbin");
+        std::fs::write(&corrupt_path, b"not a signature file").unwrap();
+
+        assert!(matches!(
+            data_from_file(&options, &corrupt_path),
+            Err(DataError::ParseError(_))
+        ));
+        assert!(matches!(
+            data_from_file(&options, &dir.join("missing.sbin")),
+            Err(DataError::LoadFailed { .. })
+        ));
+        assert!(matches!(
+            data_from_file(&options, &dir.join("missing.a")),
+            Err(DataError::LoadFailed { .. })
+        ));
+        let empty_dir = dir.join("empty");
+        std::fs::create_dir_all(&empty_dir).unwrap();
+        assert!(matches!(
+            data_from_file(&options, &empty_dir),
+            Err(DataError::EmptyArchive(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_database_settings() {
+        let settings = json!({
+            "analysis.mode": "full",
+            "analysis.warp.matcher": false,
+            "analysis.warp.guid": false,
+        });
+        assert_eq!(
+            database_settings(&settings),
+            json!({"analysis.warp.matcher": false, "analysis.warp.guid": false})
+        );
+    }
+
+    #[test]
+    fn test_analysis_config() {
+        let mut settings = json!({
+            "analysis.linearSweep.autorun": false,
+            "analysis.mode": "full",
+        });
+        let overrides = parse_analysis_config(r#"{"analysis.mode": "intermediate"}"#).unwrap();
+        merge_settings(&mut settings, overrides);
+        assert_eq!(settings["analysis.mode"], json!("intermediate"));
+        assert_eq!(settings["analysis.linearSweep.autorun"], json!(false));
+        assert!(parse_analysis_config("{ not json").is_err());
+        assert!(parse_analysis_config("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn test_split_output_names() {
+        let dir = tempdir::TempDir::new("sigem_split").expect("Failed to create temp dir");
+        std::fs::create_dir_all(dir.path().join("nested")).unwrap();
+        std::fs::write(dir.path().join("a.o"), b"").unwrap();
+        std::fs::write(dir.path().join("nested/b.o"), b"").unwrap();
+        let mut names = split_output_names(dir.path());
+        names.sort();
+        assert_eq!(
+            names,
+            vec![PathBuf::from("a.o"), PathBuf::from("nested/b.o")]
+        );
+        assert_eq!(
+            split_output_names(&dir.path().join("a.o")),
+            vec![PathBuf::from("a.o")]
+        );
+    }
+
+    #[test]
+    fn test_match_report() {
+        let guid = warp::signature::function::FunctionGUID::from_basic_blocks(&[
+            warp::signature::basic_block::BasicBlockGUID::from([0xc3].as_slice()),
+        ]);
+        let result = |name: &str, kind: MatchKind| MatchResult {
+            name: name.to_string(),
+            guid,
+            source: Some(PathBuf::from("libc.sbin")),
+            kind,
+            confidence: kind.confidence(),
+            level: Some(GuidLevel::Llil),
+        };
+        let report = MatchReport {
+            total_functions: 4,
+            matched: vec![
+                (0x1000, result("memcpy", MatchKind::Guid)),
+                (0x2000, result("memset", MatchKind::Constraints)),
+                (0x3000, result("strlen", MatchKind::Guid)),
+            ],
+        };
+        assert_eq!(report.kind_count(MatchKind::Guid), 2);
+        assert_eq!(report.unmatched_count(), 1);
+        assert_eq!(
+            report.to_string(),
+            "Matched 3 of 4 functions (75.0%)\n  by GUID:        2\n  by constraints: 1\nUnmatched:        1\n"
+        );
+        let report_json = report.to_json();
+        assert_eq!(report_json["matched_by_constraints"], json!(1));
+        assert_eq!(report_json["functions"][1]["name"], json!("memset"));
+        assert_eq!(report_json["functions"][1]["kind"], json!("constraints"));
+        assert_eq!(report_json["functions"][1]["level"], json!("llil"));
+    }
 }
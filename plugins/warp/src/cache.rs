@@ -1,5 +1,7 @@
 use crate::convert::{from_bn_symbol, from_bn_type_internal};
-use crate::{build_function, function_guid};
+use crate::{
+    build_function, byte_function_guid, function_guid_with_options, GenerationSettings, GuidOptions,
+};
 use binaryninja::architecture::Architecture;
 use binaryninja::binary_view::{BinaryView, BinaryViewExt};
 use binaryninja::confidence::MAX_CONFIDENCE;
@@ -17,7 +19,7 @@ use dashmap::mapref::one::Ref;
 use dashmap::DashMap;
 use std::collections::HashSet;
 use std::hash::{DefaultHasher, Hash, Hasher};
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 use warp::r#type::ComputedType;
 use warp::signature::function::constraints::FunctionConstraint;
 use warp::signature::function::{Function, FunctionGUID};
@@ -26,7 +28,10 @@ pub static MATCHED_FUNCTION_CACHE: OnceLock<DashMap<ViewID, MatchedFunctionCache
     OnceLock::new();
 pub static FUNCTION_CACHE: OnceLock<DashMap<ViewID, FunctionCache>> = OnceLock::new();
 pub static GUID_CACHE: OnceLock<DashMap<ViewID, GUIDCache>> = OnceLock::new();
-pub static TYPE_REF_CACHE: OnceLock<DashMap<ViewID, TypeRefCache>> = OnceLock::new();
+/// Shared behind an [`Arc`] so threads converting types for the same view never hold a map lock while recursing.
+pub static TYPE_REF_CACHE: OnceLock<DashMap<ViewID, Arc<TypeRefCache>>> = OnceLock::new();
+/// The start addresses of the functions matched in each view this session.
+pub static MATCHED_ADDRESSES: OnceLock<DashMap<ViewID, HashSet<u64>>> = OnceLock::new();
 
 pub fn register_cache_destructor() {
     pub static mut CACHE_DESTRUCTOR: CacheDestructor = CacheDestructor;
@@ -68,6 +73,42 @@ pub fn try_cached_function_match(function: &BNFunction) -> Option<Function> {
         .to_owned()
 }
 
+/// Record that the function at `address` was matched, see [`matched_addresses`].
+pub fn record_matched_address(view: &BinaryView, address: u64) {
+    let matched_addresses = MATCHED_ADDRESSES.get_or_init(Default::default);
+    matched_addresses
+        .entry(ViewID::from(view))
+        .or_default()
+        .insert(address);
+}
+
+/// The start addresses of every function matched in the view this session, sorted by address.
+pub fn matched_addresses(view: &BinaryView) -> Vec<u64> {
+    let Some(matched_addresses) = MATCHED_ADDRESSES.get() else {
+        return Vec::new();
+    };
+    let mut addresses: Vec<u64> = matched_addresses
+        .get(&ViewID::from(view))
+        .map(|addresses| addresses.iter().copied().collect())
+        .unwrap_or_default();
+    addresses.sort_unstable();
+    addresses
+}
+
+/// Forget the matched functions of every view, they are recorded again once functions are re-matched.
+pub fn invalidate_matched_addresses() {
+    if let Some(matched_addresses) = MATCHED_ADDRESSES.get() {
+        matched_addresses.clear();
+    }
+}
+
+/// Forget the matched functions of the view, see [`invalidate_matched_addresses`].
+pub fn invalidate_view_matched_addresses(view: &BinaryView) {
+    if let Some(matched_addresses) = MATCHED_ADDRESSES.get() {
+        matched_addresses.remove(&ViewID::from(view));
+    }
+}
+
 pub fn cached_function<A: Architecture>(
     function: &BNFunction,
     llil: &RegularLowLevelILFunction<A>,
@@ -140,6 +181,80 @@ pub fn cached_function_guid<A: Architecture, M: FunctionMutability>(
     }
 }
 
+/// Get the GUID of the function, falling back to the byte-only GUID if there is no `llil`.
+///
+/// Without the fallback functions which fail to lift (e.g. thunks and stubs) never get a GUID and
+/// never match. The byte-only GUID is derived in its own namespace, see [`crate::byte_function_guid`].
+pub fn cached_function_guid_or_bytes<A: Architecture, M: FunctionMutability>(
+    function: &BNFunction,
+    llil: Option<&LowLevelILFunction<A, M, NonSSA<RegularNonSSA>>>,
+) -> Option<FunctionGUID> {
+    match llil {
+        Some(llil) => Some(cached_function_guid(function, llil)),
+        None => {
+            log::debug!(
+                "No LLIL for function at 0x{:x}, using the byte-only GUID",
+                function.start()
+            );
+            cached_byte_function_guid(function)
+        }
+    }
+}
+
+/// Get the byte-only GUID for functions without LLIL, see [`crate::byte_function_guid`].
+pub fn cached_byte_function_guid(function: &BNFunction) -> Option<FunctionGUID> {
+    let view = function.view();
+    let view_id = ViewID::from(view);
+    let guid_cache = GUID_CACHE.get_or_init(Default::default);
+    match guid_cache.get(&view_id) {
+        Some(cache) => cache.byte_function_guid(function),
+        None => {
+            let cache = GUIDCache::default();
+            let guid = cache.byte_function_guid(function);
+            guid_cache.insert(view_id, cache);
+            guid
+        }
+    }
+}
+
+/// Remove the cached GUIDs of every function in the view, along with the functions and matches built from them.
+///
+/// This is safe to call while analysis is running, GUIDs are lazily recomputed from the current IL.
+pub fn invalidate_view_guids(view: &BinaryView) {
+    let view_id = ViewID::from(view);
+    if let Some(cache) = GUID_CACHE.get() {
+        cache.remove(&view_id);
+    }
+    if let Some(cache) = FUNCTION_CACHE.get() {
+        cache.remove(&view_id);
+    }
+    if let Some(cache) = MATCHED_FUNCTION_CACHE.get() {
+        cache.remove(&view_id);
+    }
+    invalidate_view_matched_addresses(view);
+}
+
+/// Remove the cached GUID of the function, along with the function and match built from it.
+///
+/// NOTE: The cached functions of the entire view are removed, as their constraints reference the GUID.
+pub fn invalidate_function_guid(function: &BNFunction) {
+    let view_id = ViewID::from(function.view());
+    let function_id = FunctionID::from(function);
+    if let Some(cache) = GUID_CACHE.get() {
+        if let Some(view_cache) = cache.get(&view_id) {
+            view_cache.cache.remove(&function_id);
+        }
+    }
+    if let Some(cache) = FUNCTION_CACHE.get() {
+        cache.remove(&view_id);
+    }
+    if let Some(cache) = MATCHED_FUNCTION_CACHE.get() {
+        if let Some(view_cache) = cache.get(&view_id) {
+            view_cache.cache.remove(&function_id);
+        }
+    }
+}
+
 pub fn try_cached_function_guid(function: &BNFunction) -> Option<FunctionGUID> {
     let view = function.view();
     let view_id = ViewID::from(view);
@@ -154,21 +269,25 @@ pub fn cached_type_reference(
 ) -> Option<ComputedType> {
     let view_id = ViewID::from(view);
     let type_ref_cache = TYPE_REF_CACHE.get_or_init(Default::default);
-    match type_ref_cache.get(&view_id) {
-        Some(cache) => cache.cached_type_reference(view, visited_refs, type_ref),
-        None => {
-            let cache = TypeRefCache::default();
-            let ntr = cache.cached_type_reference(view, visited_refs, type_ref);
-            type_ref_cache.insert(view_id, cache);
-            ntr
-        }
-    }
+    // Create the view cache up front so concurrent callers share it instead of racing to insert their own.
+    let cache = match type_ref_cache.get(&view_id) {
+        Some(cache) => cache.clone(),
+        None => type_ref_cache
+            .entry(view_id)
+            .or_insert_with(|| {
+                Arc::new(TypeRefCache::new(
+                    GenerationSettings::from_view(view).max_type_depth,
+                ))
+            })
+            .clone(),
+    };
+    cache.cached_type_reference(view, visited_refs, type_ref)
 }
 
-pub fn cached_type_references(view: &BinaryView) -> Option<Ref<ViewID, TypeRefCache>> {
+pub fn cached_type_references(view: &BinaryView) -> Option<Arc<TypeRefCache>> {
     let view_id = ViewID::from(view);
     let type_ref_cache = TYPE_REF_CACHE.get_or_init(Default::default);
-    type_ref_cache.get(&view_id)
+    type_ref_cache.get(&view_id).map(|cache| cache.clone())
 }
 
 #[derive(Clone, Debug, Default)]
@@ -211,7 +330,7 @@ impl FunctionCache {
         match self.cache.get(&function_id) {
             Some(function) => function.value().to_owned(),
             None => {
-                let function = build_function(function, llil);
+                let function = build_function(function, llil, false);
                 self.cache.insert(function_id, function.clone());
                 function
             }
@@ -339,13 +458,26 @@ impl GUIDCache {
         match self.cache.get(&function_id) {
             Some(function_guid) => function_guid.value().to_owned(),
             None => {
-                let function_guid = function_guid(function, llil);
+                let options = GuidOptions::from_view(&function.view());
+                let function_guid = function_guid_with_options(function, llil, &options);
                 self.cache.insert(function_id, function_guid);
                 function_guid
             }
         }
     }
 
+    pub fn byte_function_guid(&self, function: &BNFunction) -> Option<FunctionGUID> {
+        let function_id = FunctionID::from(function);
+        match self.cache.get(&function_id) {
+            Some(function_guid) => Some(function_guid.value().to_owned()),
+            None => {
+                let function_guid = byte_function_guid(function)?;
+                self.cache.insert(function_id, function_guid);
+                Some(function_guid)
+            }
+        }
+    }
+
     pub fn try_function_guid(&self, function: &BNFunction) -> Option<FunctionGUID> {
         let function_id = FunctionID::from(function);
         self.cache
@@ -356,18 +488,35 @@ impl GUIDCache {
 
 #[derive(Clone, Debug, Default)]
 pub struct TypeRefCache {
-    pub cache: DashMap<TypeRefID, Option<ComputedType>>,
+    /// Keyed on the remaining depth as well, a type reached with less depth left may be truncated.
+    pub cache: DashMap<(TypeRefID, Option<usize>), Option<ComputedType>>,
+    /// Type references nested deeper than this are not expanded, see [`GenerationSettings::max_type_depth`].
+    pub max_depth: Option<usize>,
 }
 
 impl TypeRefCache {
+    pub fn new(max_depth: Option<usize>) -> Self {
+        Self {
+            cache: DashMap::new(),
+            max_depth,
+        }
+    }
+
     /// NOTE: No self-referential type must be used on this function.
+    ///
+    /// Returns `None` without caching if `type_ref` is nested deeper than [`TypeRefCache::max_depth`].
     pub fn cached_type_reference(
         &self,
         view: &BinaryView,
         visited_refs: &mut HashSet<TypeRefID>,
         type_ref: &BNNamedTypeReference,
     ) -> Option<ComputedType> {
-        let ntr_id = TypeRefID::from(type_ref);
+        // The type reference is already visited, so the visited references are the depth of it.
+        let remaining_depth = match self.max_depth {
+            Some(max_depth) => Some(max_depth.checked_sub(visited_refs.len())?),
+            None => None,
+        };
+        let ntr_id = (TypeRefID::from(type_ref), remaining_depth);
         match self.cache.get(&ntr_id) {
             Some(cache) => cache.to_owned(),
             None => match type_ref.target(view) {
@@ -387,6 +536,16 @@ impl TypeRefCache {
             },
         }
     }
+
+    /// The computed types, types which are identical at different remaining depths are only returned once.
+    pub fn computed_types(&self) -> Vec<ComputedType> {
+        let mut seen_guids = HashSet::new();
+        self.cache
+            .iter()
+            .filter_map(|t| t.to_owned())
+            .filter(|t| seen_guids.insert(t.guid))
+            .collect()
+    }
 }
 
 /// A unique view ID, used for caching.
@@ -482,6 +641,189 @@ impl ObjectDestructor for CacheDestructor {
         if let Some(cache) = TYPE_REF_CACHE.get() {
             cache.remove(&view_id);
         }
+        if let Some(cache) = MATCHED_ADDRESSES.get() {
+            cache.remove(&view_id);
+        }
         log::debug!("Removed WARP caches for {:?}", view.file().filename());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::from_bn_type;
+    use crate::test::{session, view_with_function, view_with_functions};
+    use binaryninja::file_metadata::FileMetadata;
+    use binaryninja::symbol::SymbolType;
+    use binaryninja::types::{
+        MemberAccess, MemberScope, NamedTypeReferenceClass, StructureBuilder, Type as BNType,
+    };
+    use rayon::prelude::*;
+    use std::path::PathBuf;
+    use warp::r#type::guid::TypeGUID;
+
+    #[test]
+    fn invalidated_guids_are_regenerated() {
+        let _session = session();
+        // xor eax, eax; ret; mov eax, 0x1; ret
+        let code = [0x31, 0xc0, 0xc3, 0xb8, 0x01, 0x00, 0x00, 0x00, 0xc3];
+        let (view, [function, other_function]) = view_with_functions(&code, [0, 3]);
+        let llil = function.low_level_il().expect("Failed to get LLIL");
+        let other_llil = other_function.low_level_il().expect("Failed to get LLIL");
+        let guid = cached_function_guid(&function, &llil);
+        let other_guid = cached_function_guid(&other_function, &other_llil);
+
+        invalidate_function_guid(&function);
+        assert_eq!(try_cached_function_guid(&function), None);
+        assert_eq!(try_cached_function_guid(&other_function), Some(other_guid));
+        assert_eq!(cached_function_guid(&function, &llil), guid);
+
+        invalidate_view_guids(&view);
+        assert_eq!(try_cached_function_guid(&function), None);
+        assert_eq!(try_cached_function_guid(&other_function), None);
+    }
+
+    #[test]
+    fn type_references_limited_to_max_depth() {
+        let _session = session();
+        // struct A { struct B member; }; struct B { C member; }; typedef int32_t C;
+        let member_struct = |ntr: &BNNamedTypeReference| {
+            let mut builder = StructureBuilder::new();
+            builder.append(
+                &BNType::named_type(ntr),
+                "member",
+                MemberAccess::PublicAccess,
+                MemberScope::NoScope,
+            );
+            BNType::structure(&builder.finalize())
+        };
+        let c_ref = BNNamedTypeReference::new_with_id(
+            NamedTypeReferenceClass::TypedefNamedTypeClass,
+            "c-id",
+            "C",
+        );
+        let b_ref = BNNamedTypeReference::new_with_id(
+            NamedTypeReferenceClass::StructNamedTypeClass,
+            "b-id",
+            "B",
+        );
+        let a_ref = BNNamedTypeReference::new_with_id(
+            NamedTypeReferenceClass::StructNamedTypeClass,
+            "a-id",
+            "A",
+        );
+        let a_ty = BNType::named_type(&a_ref);
+        let new_view = || {
+            let view =
+                BinaryView::from_data(&FileMetadata::new(), &[0]).expect("Failed to create view");
+            view.define_auto_type_with_id("C", "c-id", &BNType::int(4, true));
+            view.define_auto_type_with_id("B", "b-id", &member_struct(&c_ref));
+            view.define_auto_type_with_id("A", "a-id", &member_struct(&b_ref));
+            view
+        };
+
+        let unlimited_view = new_view();
+        from_bn_type(&unlimited_view, &a_ty, MAX_CONFIDENCE);
+        let cache = cached_type_references(&unlimited_view).expect("No type reference cache");
+        assert_eq!(cache.cache.len(), 3);
+
+        // Only `A` is expanded, `B` is nested too deep.
+        let limited_view = new_view();
+        TYPE_REF_CACHE.get_or_init(Default::default).insert(
+            ViewID::from(&*limited_view),
+            Arc::new(TypeRefCache::new(Some(1))),
+        );
+        from_bn_type(&limited_view, &a_ty, MAX_CONFIDENCE);
+        let cache = cached_type_references(&limited_view).expect("No type reference cache");
+        assert_eq!(cache.cache.len(), 1);
+
+        // `B` is complete when converted first, but truncated when nested in `A`, so the GUID of `A`
+        // must not depend on which of them was converted first.
+        let b_ty = BNType::named_type(&b_ref);
+        let limited_guid = |convert_b_first: bool| {
+            let view = new_view();
+            TYPE_REF_CACHE
+                .get_or_init(Default::default)
+                .insert(ViewID::from(&*view), Arc::new(TypeRefCache::new(Some(2))));
+            if convert_b_first {
+                from_bn_type(&view, &b_ty, MAX_CONFIDENCE);
+            }
+            TypeGUID::from(&from_bn_type(&view, &a_ty, MAX_CONFIDENCE))
+        };
+        assert_eq!(limited_guid(true), limited_guid(false));
+    }
+
+    #[test]
+    fn parallel_type_references_share_view_cache() {
+        let _session = session();
+        let view =
+            BinaryView::from_data(&FileMetadata::new(), &[0]).expect("Failed to create view");
+        let named_types = (0..64)
+            .map(|idx| {
+                let name = format!("T{}", idx);
+                let type_id = format!("t{}-id", idx);
+                view.define_auto_type_with_id(
+                    name.as_str(),
+                    type_id.as_str(),
+                    &BNType::int(4, true),
+                );
+                let ntr = BNNamedTypeReference::new_with_id(
+                    NamedTypeReferenceClass::TypedefNamedTypeClass,
+                    type_id.as_str(),
+                    name.as_str(),
+                );
+                BNType::named_type(&ntr)
+            })
+            .collect::<Vec<_>>();
+
+        let parallel_guids = named_types
+            .par_iter()
+            .map(|ty| TypeGUID::from(&from_bn_type(&view, ty, MAX_CONFIDENCE)))
+            .collect::<Vec<_>>();
+
+        // Every thread must have written into the same view cache, none of the inserts may be lost.
+        let cache = cached_type_references(&view).expect("No type reference cache");
+        assert_eq!(cache.cache.len(), named_types.len());
+        for (ty, parallel_guid) in named_types.iter().zip(parallel_guids) {
+            let guid = TypeGUID::from(&from_bn_type(&view, ty, MAX_CONFIDENCE));
+            assert_eq!(guid, parallel_guid);
+        }
+    }
+
+    #[test]
+    fn missing_llil_falls_back_to_byte_guid() {
+        let _session = session();
+        // Only control flow is analyzed, so the import thunk has basic blocks but no LLIL.
+        let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
+        let view = binaryninja::load_with_options(
+            out_dir.join("thunk").join("puts_thunk"),
+            true,
+            Some(r#"{"analysis.mode": "controlFlow"}"#.to_string()),
+        )
+        .expect("Failed to load view");
+        let thunk = view
+            .functions()
+            .iter()
+            .find(|f| f.symbol().sym_type() == SymbolType::ImportedFunction)
+            .expect("No import thunk")
+            .to_owned();
+        let llil = thunk.low_level_il().ok();
+        assert!(llil.is_none(), "Import thunk has LLIL");
+
+        let guid = cached_function_guid_or_bytes(&thunk, llil.as_deref());
+        assert!(guid.is_some());
+        assert_eq!(guid, byte_function_guid(&thunk));
+        assert_eq!(try_cached_function_guid(&thunk), guid);
+    }
+
+    #[test]
+    fn available_llil_uses_llil_guid() {
+        let _session = session();
+        // xor eax, eax; ret
+        let (_view, function) = view_with_function(&[0x31, 0xc0, 0xc3]);
+        let llil = function.low_level_il().expect("Failed to get LLIL");
+        let guid = cached_function_guid_or_bytes(&function, Some(&llil));
+        assert_eq!(guid, Some(crate::function_guid(&function, &llil)));
+        assert_ne!(guid, byte_function_guid(&function));
+    }
+}
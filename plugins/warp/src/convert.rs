@@ -11,7 +11,7 @@ use binaryninja::types::{
     BaseStructure as BNBaseStructure, EnumerationBuilder as BNEnumerationBuilder,
     FunctionParameter as BNFunctionParameter, MemberAccess as BNMemberAccess, MemberAccess,
     MemberScope as BNMemberScope, NamedTypeReference, NamedTypeReference as BNNamedTypeReference,
-    NamedTypeReferenceClass, StructureBuilder as BNStructureBuilder,
+    NamedTypeReferenceClass, Structure as BNStructure, StructureBuilder as BNStructureBuilder,
     StructureMember as BNStructureMember,
 };
 use binaryninja::types::{
@@ -28,7 +28,7 @@ use warp::r#type::class::{
     EnumerationMember, FloatClass, FunctionClass, FunctionMember, IntegerClass, PointerClass,
     ReferrerClass, StructureClass, StructureMember, TypeClass,
 };
-use warp::r#type::Type;
+use warp::r#type::{Alignment, Type};
 use warp::symbol::class::SymbolClass;
 use warp::symbol::{Symbol, SymbolModifiers};
 
@@ -116,6 +116,14 @@ pub fn to_bn_symbol_at_address(view: &BinaryView, symbol: &Symbol, addr: u64) ->
     symbol_builder.create()
 }
 
+/// Convert the Binary Ninja type, `confidence` is the confidence given to the converted type.
+///
+/// NOTE: `confidence` is not a depth, named types are expanded up to the maximum type depth of the
+/// view, see [`crate::GenerationSettings::max_type_depth`].
+///
+/// This may be called from multiple threads for the same view. The visited type references are per
+/// call, the only shared state is the type reference cache of the view, see [`cached_type_reference`].
+/// Threads converting the same type reference at once both convert it and store the same type.
 pub fn from_bn_type(view: &BinaryView, raw_ty: &BNType, confidence: u8) -> Type {
     from_bn_type_internal(view, &mut HashSet::new(), raw_ty, confidence)
 }
@@ -128,6 +136,7 @@ pub fn from_bn_type_internal(
 ) -> Type {
     let bytes_to_bits = |val| val * 8;
     let raw_ty_bit_width = bytes_to_bits(raw_ty.width());
+    let mut alignment = Alignment::default();
     let type_class = match raw_ty.type_class() {
         BNTypeClass::VoidTypeClass => TypeClass::Void,
         BNTypeClass::BoolTypeClass => {
@@ -201,6 +210,8 @@ pub fn from_bn_type_internal(
             });
             members.extend(base_to_member_iter);
 
+            alignment = from_bn_structure_alignment(&raw_struct);
+
             // TODO: Check if union
             let struct_class = StructureClass::new(members);
             TypeClass::Structure(struct_class)
@@ -341,13 +352,33 @@ pub fn from_bn_type_internal(
         confidence,
         // TODO: Fill these out...
         modifiers: vec![],
-        alignment: Default::default(),
+        alignment,
         // TODO: Filling this out is... weird.
         // TODO: we _do_ want this for networked types (this is the only way we can update type is if we fill this out)
         ancestors: vec![],
     }
 }
 
+/// The alignment of the structure, only fixed if it differs from the alignment of its members.
+///
+/// Packed structures have a fixed alignment of `1`. The alignment of other structures is only stored
+/// when set explicitly, so that the GUID of a structure with the natural alignment does not change.
+fn from_bn_structure_alignment(raw_struct: &BNStructure) -> Alignment {
+    if raw_struct.is_packed() {
+        return Alignment::Fixed(1);
+    }
+    let member_alignment = raw_struct
+        .members()
+        .iter()
+        .map(|member| member.ty.contents.alignment())
+        .max()
+        .unwrap_or(1);
+    match raw_struct.alignment() {
+        alignment if alignment == member_alignment => Alignment::Access,
+        alignment => Alignment::Fixed(alignment as u16),
+    }
+}
+
 pub fn from_bn_calling_convention(raw_cc: BNRef<BNCallingConvention>) -> CallingConvention {
     // NOTE: Currently calling convention just stores the name.
     CallingConvention::new(raw_cc.name().as_str())
@@ -366,6 +397,16 @@ pub fn to_bn_calling_convention<A: BNArchitecture>(
 }
 
 pub fn to_bn_type<A: BNArchitecture>(arch: &A, ty: &Type) -> BNRef<BNType> {
+    to_bn_type_with_confidence(arch, ty, MAX_CONFIDENCE)
+}
+
+/// Same as [`to_bn_type`], but the return type, parameters and calling convention of a function type
+/// are created with the given confidence instead of [`MAX_CONFIDENCE`].
+pub fn to_bn_type_with_confidence<A: BNArchitecture>(
+    arch: &A,
+    ty: &Type,
+    confidence: u8,
+) -> BNRef<BNType> {
     let bits_to_bytes = |val: u64| (val / 8);
     let addr_size = arch.address_size() as u64;
     match ty.class.as_ref() {
@@ -401,8 +442,16 @@ pub fn to_bn_type<A: BNArchitecture>(arch: &A, ty: &Type) -> BNRef<BNType> {
         TypeClass::Structure(c) => {
             let mut builder = BNStructureBuilder::new();
             // TODO: Structure type class?
-            // TODO: Alignment
             // TODO: Other modifiers?
+            match ty.alignment {
+                Alignment::Fixed(1) => {
+                    builder.packed(true);
+                }
+                Alignment::Fixed(alignment) => {
+                    builder.alignment(alignment as usize);
+                }
+                Alignment::Access => {}
+            }
             let mut base_structs: Vec<BNBaseStructure> = Vec::new();
             for member in &c.members {
                 let member_type = BNConf::new(to_bn_type(arch, &member.ty), u8::MAX);
@@ -515,7 +564,7 @@ pub fn to_bn_type<A: BNArchitecture>(arch: &A, ty: &Type) -> BNRef<BNType> {
                 .in_members
                 .iter()
                 .map(|member| {
-                    let member_type = to_bn_type(arch, &member.ty);
+                    let member_type = BNConf::new(to_bn_type(arch, &member.ty), confidence);
                     let name = member.name.clone();
                     // TODO: Location AND fix default param name
                     BNFunctionParameter::new(member_type, name.unwrap_or("param_IDK".into()), None)
@@ -528,14 +577,18 @@ pub fn to_bn_type<A: BNArchitecture>(arch: &A, ty: &Type) -> BNRef<BNType> {
                 Some(cc) => {
                     let calling_convention = to_bn_calling_convention(arch, cc);
                     BNType::function_with_opts(
-                        &return_type,
+                        BNConf::new(return_type.as_ref(), confidence),
                         &params,
                         variable_args,
-                        BNConf::new(calling_convention, u8::MAX),
+                        BNConf::new(calling_convention, confidence),
                         BNConf::new(0, 0),
                     )
                 }
-                None => BNType::function(&return_type, params, variable_args),
+                None => BNType::function(
+                    BNConf::new(return_type.as_ref(), confidence),
+                    params,
+                    variable_args,
+                ),
             }
         }
         TypeClass::Referrer(c) => {
@@ -571,21 +624,14 @@ pub fn to_bn_type<A: BNArchitecture>(arch: &A, ty: &Type) -> BNRef<BNType> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test::session;
     use binaryninja::binary_view::BinaryViewExt;
-    use binaryninja::headless::Session;
     use std::path::PathBuf;
-    use std::sync::OnceLock;
     use warp::r#type::guid::TypeGUID;
 
-    static INIT: OnceLock<Session> = OnceLock::new();
-
-    fn get_session<'a>() -> &'a Session {
-        INIT.get_or_init(|| Session::new().expect("Failed to initialize session"))
-    }
-
     #[test]
     fn type_conversion() {
-        let session = get_session();
+        let session = session();
         let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
         for entry in std::fs::read_dir(out_dir).expect("Failed to read OUT_DIR") {
             let entry = entry.expect("Failed to read directory entry");
@@ -597,7 +643,7 @@ mod tests {
                         .types()
                         .iter()
                         .map(|qualified_name_and_type| {
-                            let ty = from_bn_type(&bv, &qualified_name_and_type.ty, u8::MAX);
+                            let ty = from_bn_type(&bv, &qualified_name_and_type.ty, MAX_CONFIDENCE);
                             (TypeGUID::from(&ty), ty)
                         })
                         .collect();
@@ -607,10 +653,103 @@ mod tests {
         }
     }
 
+    #[test]
+    fn function_pointer_member_can_return() {
+        let _session = session();
+        let file = binaryninja::file_metadata::FileMetadata::new();
+        let view = BinaryView::from_data(&file, &[0; 0x10]).expect("Failed to create view");
+        let arch = binaryninja::architecture::CoreArchitecture::by_name("x86_64")
+            .expect("Failed to get architecture");
+        // struct { void (*callback)(); }
+        let void = BNType::void();
+        let callback_ty = BNType::function(void.as_ref(), vec![], false);
+        let mut builder = BNStructureBuilder::new();
+        builder.insert(
+            &BNType::pointer(&arch, callback_ty.as_ref()),
+            "callback",
+            0,
+            false,
+            BNMemberAccess::PublicAccess,
+            BNMemberScope::NoScope,
+        );
+        let raw_ty = BNType::structure(&builder.finalize());
+
+        let ty = from_bn_type(&view, &raw_ty, MAX_CONFIDENCE);
+        let round_trip_ty = to_bn_type(&arch, &ty);
+        let members = round_trip_ty
+            .get_structure()
+            .expect("Not a structure")
+            .members();
+        let round_trip_callback_ty = members[0].ty.contents.target().expect("Not a pointer");
+        // A function type without no-return status is never converted to a no-return one.
+        assert!(round_trip_callback_ty.contents.can_return().contents);
+        let round_trip_guid = TypeGUID::from(&from_bn_type(&view, &round_trip_ty, MAX_CONFIDENCE));
+        assert_eq!(round_trip_guid, TypeGUID::from(&ty));
+    }
+
+    #[test]
+    fn packed_structure_round_trip() {
+        let _session = session();
+        let file = binaryninja::file_metadata::FileMetadata::new();
+        let view = BinaryView::from_data(&file, &[0; 0x10]).expect("Failed to create view");
+        let arch = binaryninja::architecture::CoreArchitecture::by_name("x86_64")
+            .expect("Failed to get architecture");
+        let mut builder = BNStructureBuilder::new();
+        builder.packed(true);
+        builder.insert(
+            &BNType::int(1, false),
+            "tag",
+            0,
+            false,
+            BNMemberAccess::PublicAccess,
+            BNMemberScope::NoScope,
+        );
+        builder.insert(
+            &BNType::int(4, false),
+            "value",
+            1,
+            false,
+            BNMemberAccess::PublicAccess,
+            BNMemberScope::NoScope,
+        );
+        let raw_ty = BNType::structure(&builder.finalize());
+
+        let ty = from_bn_type(&view, &raw_ty, MAX_CONFIDENCE);
+        assert_eq!(ty.alignment, Alignment::Fixed(1));
+        let round_trip_ty = to_bn_type(&arch, &ty);
+        let raw_struct = raw_ty.get_structure().unwrap();
+        let round_trip_struct = round_trip_ty.get_structure().expect("Not a structure");
+        assert!(round_trip_struct.is_packed());
+        assert_eq!(round_trip_ty.width(), 5);
+        assert_eq!(round_trip_ty.width(), raw_ty.width());
+        assert_eq!(round_trip_ty.alignment(), raw_ty.alignment());
+        let offsets = |s: &BNStructure| s.members().iter().map(|m| m.offset).collect::<Vec<_>>();
+        assert_eq!(offsets(&round_trip_struct), offsets(&raw_struct));
+        // Converting the reconstructed type again must give the same GUID.
+        let round_trip_guid = TypeGUID::from(&from_bn_type(&view, &round_trip_ty, MAX_CONFIDENCE));
+        assert_eq!(round_trip_guid, TypeGUID::from(&ty));
+
+        // Structures with the natural alignment do not store it.
+        let mut builder = BNStructureBuilder::new();
+        builder.insert(
+            &BNType::int(4, false),
+            "value",
+            0,
+            false,
+            BNMemberAccess::PublicAccess,
+            BNMemberScope::NoScope,
+        );
+        let raw_ty = BNType::structure(&builder.finalize());
+        assert_eq!(
+            from_bn_type(&view, &raw_ty, MAX_CONFIDENCE).alignment,
+            Alignment::Access
+        );
+    }
+
     #[ignore]
     #[test]
     fn check_for_leaks() {
-        let session = get_session();
+        let session = session();
         let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
         for entry in std::fs::read_dir(out_dir).expect("Failed to read OUT_DIR") {
             let entry = entry.expect("Failed to read directory entry");
@@ -622,7 +761,7 @@ mod tests {
                         .types()
                         .iter()
                         .map(|t| {
-                            let ty = from_bn_type(&inital_bv, &t.ty, u8::MAX);
+                            let ty = from_bn_type(&inital_bv, &t.ty, MAX_CONFIDENCE);
                             (TypeGUID::from(&ty), ty)
                         })
                         .collect();
@@ -643,7 +782,7 @@ mod tests {
                             .types()
                             .iter()
                             .map(|t| {
-                                let ty = from_bn_type(&second_bv, &t.ty, u8::MAX);
+                                let ty = from_bn_type(&second_bv, &t.ty, MAX_CONFIDENCE);
                                 (TypeGUID::from(&ty), ty)
                             })
                             .collect();
@@ -0,0 +1,589 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use warp::r#type::guid::TypeGUID;
+use warp::signature::function::constraints::FunctionConstraint;
+use warp::signature::function::{Function, FunctionGUID};
+use warp::signature::Data;
+
+/// Editing of existing signature [`Data`], so a single bad signature does not require regenerating the file.
+pub trait DataExt {
+    /// Remove the functions with the `guid` and symbol `name`, returning the number of functions removed.
+    ///
+    /// Other functions sharing the GUID are kept. If no function with the GUID remains, constraints
+    /// of the remaining functions on that GUID are left constraining only on their symbol,
+    /// constraints without a symbol are removed. Constraints on other GUIDs are left as-is, they
+    /// commonly reference functions defined outside of the file (e.g. library callees).
+    fn remove_function(&mut self, guid: FunctionGUID, name: &str) -> usize;
+
+    /// Replace the function with the same GUID and symbol name as `func`, or add it if there is none.
+    ///
+    /// Returns the replaced function.
+    fn upsert_function(&mut self, func: Function) -> Option<Function>;
+
+    /// Whether there is nothing worth saving, i.e. no functions and no types.
+    ///
+    /// A signature file with only types is still useful, so both are checked.
+    fn is_empty(&self) -> bool {
+        self.function_count() == 0 && self.type_count() == 0
+    }
+
+    fn function_count(&self) -> usize;
+
+    fn type_count(&self) -> usize;
+
+    /// How well the functions can be told apart by their GUID alone, see [`GuidStats`].
+    fn guid_stats(&self) -> GuidStats;
+
+    /// Merge the data exactly like [`Data::merge`], calling `progress(done, total)` for each data
+    /// added to the merge. Returning `false` from `progress` cancels the merge and `None` is returned.
+    ///
+    /// NOTE: Once every data has been added the merge itself is run and can no longer be cancelled.
+    fn merge_with_progress(
+        data: Vec<Data>,
+        progress: &mut impl FnMut(usize, usize) -> bool,
+    ) -> Option<Data>
+    where
+        Self: Sized;
+
+    /// What changed going from this data to `other`, see [`DataDiff`].
+    fn diff(&self, other: &Data) -> DataDiff;
+}
+
+/// A function of a [`DataDiff`], functions are identified by their GUID and symbol name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DiffFunction {
+    pub guid: FunctionGUID,
+    pub name: String,
+}
+
+impl DiffFunction {
+    fn new(func: &Function) -> Self {
+        Self {
+            guid: func.guid,
+            name: func.symbol.name.to_owned(),
+        }
+    }
+
+    fn sort_key(&self) -> (uuid::Uuid, &str) {
+        (self.guid.guid, &self.name)
+    }
+}
+
+impl Display for DiffFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, crate::GuidString(self.guid))
+    }
+}
+
+/// A type of a [`DataDiff`], types are identified by their GUID.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DiffType {
+    pub guid: TypeGUID,
+    pub name: Option<String>,
+}
+
+impl Display for DiffType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{} ({})", name, self.guid),
+            None => write!(f, "<anonymous> ({})", self.guid),
+        }
+    }
+}
+
+/// The differences between two signature [`Data`], as returned by [`DataExt::diff`].
+///
+/// Every list is sorted by GUID (then name), so the diff of the same data is always the same.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DataDiff {
+    /// Functions only in the other data.
+    pub added_functions: Vec<DiffFunction>,
+    /// Functions no longer in the other data.
+    pub removed_functions: Vec<DiffFunction>,
+    /// Functions in both, but with a different type.
+    pub changed_functions: Vec<DiffFunction>,
+    /// Types only in the other data.
+    pub added_types: Vec<DiffType>,
+    /// Types no longer in the other data.
+    pub removed_types: Vec<DiffType>,
+}
+
+impl DataDiff {
+    /// Whether the data has the same functions and types.
+    ///
+    /// NOTE: Changed function constraints are not part of the diff.
+    pub fn is_empty(&self) -> bool {
+        self.added_functions.is_empty()
+            && self.removed_functions.is_empty()
+            && self.changed_functions.is_empty()
+            && self.added_types.is_empty()
+            && self.removed_types.is_empty()
+    }
+}
+
+impl Display for DataDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for func in &self.added_functions {
+            writeln!(f, "+ function {}", func)?;
+        }
+        for func in &self.removed_functions {
+            writeln!(f, "- function {}", func)?;
+        }
+        for func in &self.changed_functions {
+            writeln!(f, "~ function {}", func)?;
+        }
+        for ty in &self.added_types {
+            writeln!(f, "+ type {}", ty)?;
+        }
+        for ty in &self.removed_types {
+            writeln!(f, "- type {}", ty)?;
+        }
+        Ok(())
+    }
+}
+
+/// Statistics on the function GUIDs of signature [`Data`].
+///
+/// Functions sharing a GUID can only be matched through their constraints, a high number of
+/// colliding GUIDs means the signatures will match poorly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GuidStats {
+    /// The number of functions.
+    pub functions: usize,
+    /// The number of distinct function GUIDs.
+    pub distinct_guids: usize,
+    /// The number of GUIDs shared by two or more functions.
+    pub colliding_guids: usize,
+    /// The most functions sharing a single GUID.
+    pub max_bucket: usize,
+    /// The GUID shared by the most functions, `None` if no GUID is shared.
+    pub worst_guid: Option<FunctionGUID>,
+}
+
+impl Display for GuidStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} functions, {} distinct GUIDs, {} colliding GUIDs",
+            self.functions, self.distinct_guids, self.colliding_guids
+        )?;
+        if let Some(worst_guid) = &self.worst_guid {
+            write!(
+                f,
+                " (worst: {} shared by {} functions)",
+                crate::GuidString(*worst_guid),
+                self.max_bucket
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl DataExt for Data {
+    fn remove_function(&mut self, guid: FunctionGUID, name: &str) -> usize {
+        let prev_len = self.functions.len();
+        self.functions
+            .retain(|f| f.guid != guid || f.symbol.name != name);
+        let removed = prev_len - self.functions.len();
+        // Colliding functions with another name still define the GUID.
+        if removed > 0 && !self.functions.iter().any(|f| f.guid == guid) {
+            for func in &mut self.functions {
+                let constraints = &mut func.constraints;
+                constraints.adjacent = strip_removed_guid(&constraints.adjacent, guid);
+                constraints.call_sites = strip_removed_guid(&constraints.call_sites, guid);
+                constraints.caller_sites = strip_removed_guid(&constraints.caller_sites, guid);
+            }
+        }
+        removed
+    }
+
+    fn upsert_function(&mut self, func: Function) -> Option<Function> {
+        match self
+            .functions
+            .iter_mut()
+            .find(|f| f.guid == func.guid && f.symbol.name == func.symbol.name)
+        {
+            Some(existing) => Some(std::mem::replace(existing, func)),
+            None => {
+                self.functions.push(func);
+                None
+            }
+        }
+    }
+
+    fn function_count(&self) -> usize {
+        self.functions.len()
+    }
+
+    fn type_count(&self) -> usize {
+        self.types.len()
+    }
+
+    fn guid_stats(&self) -> GuidStats {
+        let mut buckets: HashMap<FunctionGUID, usize> = HashMap::new();
+        for func in &self.functions {
+            *buckets.entry(func.guid).or_default() += 1;
+        }
+        let worst = buckets
+            .iter()
+            .max_by_key(|&(guid, count)| (*count, guid.guid))
+            .filter(|&(_, count)| *count > 1);
+        GuidStats {
+            functions: self.functions.len(),
+            distinct_guids: buckets.len(),
+            colliding_guids: buckets.values().filter(|&&count| count > 1).count(),
+            max_bucket: buckets.values().copied().max().unwrap_or_default(),
+            worst_guid: worst.map(|(guid, _)| *guid),
+        }
+    }
+
+    fn merge_with_progress(
+        data: Vec<Data>,
+        progress: &mut impl FnMut(usize, usize) -> bool,
+    ) -> Option<Data> {
+        let total = data.len();
+        let mut unmerged = Vec::with_capacity(total);
+        for (done, entry) in data.into_iter().enumerate() {
+            if !progress(done, total) {
+                return None;
+            }
+            unmerged.push(entry);
+        }
+        if !progress(total, total) {
+            return None;
+        }
+        // The merge is left to `Data::merge` so that GUIDs are resolved the same way.
+        Some(Data::merge(unmerged))
+    }
+
+    fn diff(&self, other: &Data) -> DataDiff {
+        // NOTE: The same function can be in the data more than once with different types.
+        let function_types = |data: &Data| -> HashMap<DiffFunction, HashSet<TypeGUID>> {
+            let mut function_types: HashMap<DiffFunction, HashSet<TypeGUID>> = HashMap::new();
+            for func in &data.functions {
+                function_types
+                    .entry(DiffFunction::new(func))
+                    .or_default()
+                    .insert(TypeGUID::from(&func.ty));
+            }
+            function_types
+        };
+        let types = |data: &Data| -> HashMap<TypeGUID, Option<String>> {
+            data.types
+                .iter()
+                .map(|computed| (computed.guid.to_owned(), computed.ty.name.to_owned()))
+                .collect()
+        };
+
+        let old_functions = function_types(self);
+        let new_functions = function_types(other);
+        let mut diff = DataDiff::default();
+        for (func, new_types) in &new_functions {
+            match old_functions.get(func) {
+                None => diff.added_functions.push(func.to_owned()),
+                Some(old_types) if old_types != new_types => {
+                    diff.changed_functions.push(func.to_owned())
+                }
+                Some(_) => {}
+            }
+        }
+        diff.removed_functions = old_functions
+            .keys()
+            .filter(|func| !new_functions.contains_key(func))
+            .cloned()
+            .collect();
+
+        let old_types = types(self);
+        let new_types = types(other);
+        let missing_types = |from: &HashMap<TypeGUID, Option<String>>,
+                             to: &HashMap<TypeGUID, Option<String>>| {
+            let mut missing: Vec<DiffType> = from
+                .iter()
+                .filter(|(guid, _)| !to.contains_key(guid))
+                .map(|(guid, name)| DiffType {
+                    guid: guid.to_owned(),
+                    name: name.to_owned(),
+                })
+                .collect();
+            missing.sort_by_cached_key(|ty| (ty.guid.to_string(), ty.name.to_owned()));
+            missing
+        };
+        diff.added_types = missing_types(&new_types, &old_types);
+        diff.removed_types = missing_types(&old_types, &new_types);
+
+        for functions in [
+            &mut diff.added_functions,
+            &mut diff.removed_functions,
+            &mut diff.changed_functions,
+        ] {
+            functions.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+        }
+        diff
+    }
+}
+
+fn strip_removed_guid(
+    constraints: &HashSet<FunctionConstraint>,
+    removed_guid: FunctionGUID,
+) -> HashSet<FunctionConstraint> {
+    constraints
+        .iter()
+        .filter_map(|constraint| match constraint.guid {
+            Some(guid) if guid == removed_guid => {
+                // Still constrain on the symbol, if there is one.
+                constraint.symbol.as_ref()?;
+                Some(FunctionConstraint {
+                    guid: None,
+                    ..constraint.to_owned()
+                })
+            }
+            _ => Some(constraint.to_owned()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use warp::r#type::class::{IntegerClass, TypeClass};
+    use warp::r#type::{ComputedType, Type};
+    use warp::signature::basic_block::BasicBlockGUID;
+    use warp::signature::function::constraints::FunctionConstraints;
+    use warp::symbol::class::SymbolClass;
+    use warp::symbol::{Symbol, SymbolModifiers};
+
+    fn function_guid(bytes: &[u8]) -> FunctionGUID {
+        FunctionGUID::from_basic_blocks(&[BasicBlockGUID::from(bytes)])
+    }
+
+    fn function(name: &str, guid: FunctionGUID, call_sites: &[FunctionConstraint]) -> Function {
+        Function {
+            guid,
+            symbol: Symbol::new(
+                name.to_string(),
+                SymbolClass::Function,
+                SymbolModifiers::default(),
+            ),
+            ty: Type::builder::<String, _>().class(TypeClass::Void).build(),
+            constraints: FunctionConstraints {
+                adjacent: HashSet::new(),
+                call_sites: call_sites.iter().cloned().collect(),
+                caller_sites: HashSet::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn remove_function_strips_removed_guid() {
+        let memcpy_guid = function_guid(&[0xc3]);
+        let main_guid = function_guid(&[0x90, 0xc3]);
+        let memcpy_call = FunctionConstraint {
+            guid: Some(memcpy_guid),
+            symbol: Some(Symbol::new(
+                "memcpy".to_string(),
+                SymbolClass::Function,
+                SymbolModifiers::default(),
+            )),
+            offset: 0x10,
+        };
+        let anonymous_call = FunctionConstraint {
+            guid: Some(memcpy_guid),
+            symbol: None,
+            offset: 0x20,
+        };
+        // The callee is not defined in the file, the constraint must survive any removal.
+        let external_call = FunctionConstraint {
+            guid: Some(function_guid(&[0xcc, 0xc3])),
+            symbol: None,
+            offset: 0x30,
+        };
+        let mut data = Data::default();
+        data.functions.push(function("memcpy", memcpy_guid, &[]));
+        data.functions.push(function("memmove", memcpy_guid, &[]));
+        data.functions.push(function(
+            "main",
+            main_guid,
+            &[
+                memcpy_call.clone(),
+                anonymous_call.clone(),
+                external_call.clone(),
+            ],
+        ));
+
+        assert_eq!(data.remove_function(function_guid(&[0xcc]), "memcpy"), 0);
+        assert_eq!(data.remove_function(memcpy_guid, "bzero"), 0);
+        // The colliding memmove still defines the GUID, nothing is stripped.
+        assert_eq!(data.remove_function(memcpy_guid, "memcpy"), 1);
+        assert_eq!(data.functions.len(), 2);
+        assert_eq!(
+            data.functions[1].constraints.call_sites,
+            HashSet::from([memcpy_call.clone(), anonymous_call, external_call.clone()])
+        );
+
+        assert_eq!(data.remove_function(memcpy_guid, "memmove"), 1);
+        assert_eq!(data.functions.len(), 1);
+        let expected_call = FunctionConstraint {
+            guid: None,
+            ..memcpy_call
+        };
+        assert_eq!(
+            data.functions[0].constraints.call_sites,
+            HashSet::from([expected_call, external_call])
+        );
+    }
+
+    #[test]
+    fn empty_data() {
+        let mut data = Data::default();
+        assert!(data.is_empty());
+        data.functions
+            .push(function("memcpy", function_guid(&[0xc3]), &[]));
+        assert!(!data.is_empty());
+        assert_eq!((data.function_count(), data.type_count()), (1, 0));
+    }
+
+    #[test]
+    fn guid_stats() {
+        let mut data = Data::default();
+        assert_eq!(data.guid_stats(), GuidStats::default());
+
+        let shared_guid = function_guid(&[0xc3]);
+        data.functions.push(function("memcpy", shared_guid, &[]));
+        data.functions.push(function("memmove", shared_guid, &[]));
+        data.functions.push(function("bzero", shared_guid, &[]));
+        data.functions
+            .push(function("main", function_guid(&[0x90, 0xc3]), &[]));
+        assert_eq!(
+            data.guid_stats(),
+            GuidStats {
+                functions: 4,
+                distinct_guids: 2,
+                colliding_guids: 1,
+                max_bucket: 3,
+                worst_guid: Some(shared_guid),
+            }
+        );
+    }
+
+    #[test]
+    fn merge_with_progress() {
+        let mut data_a = Data::default();
+        data_a
+            .functions
+            .push(function("memcpy", function_guid(&[0xc3]), &[]));
+        let mut data_b = Data::default();
+        data_b
+            .functions
+            .push(function("main", function_guid(&[0x90, 0xc3]), &[]));
+        let unmerged = vec![data_a, data_b];
+
+        let mut reported = Vec::new();
+        let merged = Data::merge_with_progress(unmerged.clone(), &mut |done, total| {
+            reported.push((done, total));
+            true
+        });
+        assert_eq!(reported, vec![(0, 2), (1, 2), (2, 2)]);
+        let expected = Data::merge(unmerged.clone());
+        assert_eq!(
+            merged.map(|data| data.to_bytes()),
+            Some(expected.to_bytes())
+        );
+
+        // Cancelled after the first data.
+        let cancelled = Data::merge_with_progress(unmerged, &mut |done, _| done < 1);
+        assert!(cancelled.is_none());
+    }
+
+    fn integer_type(name: &str, width: u16) -> Type {
+        let mut ty = Type::builder::<String, _>()
+            .class(TypeClass::Integer(IntegerClass {
+                width: Some(width),
+                signed: false,
+            }))
+            .build();
+        ty.name = Some(name.to_string());
+        ty
+    }
+
+    #[test]
+    fn diff() {
+        let kept_guid = function_guid(&[0xc3]);
+        let removed_guid = function_guid(&[0x90, 0xc3]);
+        let added_guid = function_guid(&[0x90, 0x90, 0xc3]);
+        let size_ty = ComputedType::new(integer_type("size_t", 64));
+        let word_ty = ComputedType::new(integer_type("word_t", 16));
+
+        let mut old = Data::default();
+        old.functions.push(function("memcpy", kept_guid, &[]));
+        old.functions.push(function("memmove", kept_guid, &[]));
+        old.functions.push(function("main", removed_guid, &[]));
+        old.types.push(word_ty.clone());
+        assert!(old.diff(&old).is_empty());
+
+        let mut new = Data::default();
+        new.functions.push(function("memcpy", kept_guid, &[]));
+        let mut changed = function("memmove", kept_guid, &[]);
+        changed.ty = integer_type("size_t", 64);
+        new.functions.push(changed);
+        new.functions.push(function("start", added_guid, &[]));
+        new.functions.push(function("_start", added_guid, &[]));
+        new.types.push(size_ty.clone());
+
+        let diff_function = |name: &str, guid: FunctionGUID| DiffFunction {
+            guid,
+            name: name.to_string(),
+        };
+        let diff = old.diff(&new);
+        assert_eq!(
+            diff,
+            DataDiff {
+                added_functions: vec![
+                    diff_function("_start", added_guid),
+                    diff_function("start", added_guid),
+                ],
+                removed_functions: vec![diff_function("main", removed_guid)],
+                changed_functions: vec![diff_function("memmove", kept_guid)],
+                added_types: vec![DiffType {
+                    guid: size_ty.guid,
+                    name: Some("size_t".to_string()),
+                }],
+                removed_types: vec![DiffType {
+                    guid: word_ty.guid,
+                    name: Some("word_t".to_string()),
+                }],
+            }
+        );
+        // The reverse diff swaps the added and removed.
+        let reverse = new.diff(&old);
+        assert_eq!(reverse.added_functions, diff.removed_functions);
+        assert_eq!(reverse.removed_types, diff.added_types);
+        assert_eq!(reverse.changed_functions, diff.changed_functions);
+    }
+
+    #[test]
+    fn upsert_function_replaces_by_guid_and_symbol() {
+        let guid = function_guid(&[0xc3]);
+        let mut data = Data::default();
+        assert!(data
+            .upsert_function(function("memcpy", guid, &[]))
+            .is_none());
+        // Same GUID but a different symbol is a different function.
+        assert!(data
+            .upsert_function(function("memmove", guid, &[]))
+            .is_none());
+        assert_eq!(data.functions.len(), 2);
+
+        let call = FunctionConstraint {
+            guid: None,
+            symbol: None,
+            offset: 0x8,
+        };
+        let replaced = data.upsert_function(function("memcpy", guid, &[call.clone()]));
+        assert!(replaced.is_some_and(|f| f.constraints.call_sites.is_empty()));
+        assert_eq!(data.functions.len(), 2);
+        assert_eq!(
+            data.functions[0].constraints.call_sites,
+            HashSet::from([call])
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! Helpers for inspecting the signatures of a view, usable in tests and at runtime.
+
+use crate::cache::cached_function_guid_or_bytes;
+use binaryninja::binary_view::{BinaryView, BinaryViewExt};
+use warp::signature::function::FunctionGUID;
+
+/// The address, name and GUID of every function in the view, sorted by address.
+///
+/// Functions without LLIL use the byte-only GUID, functions without any GUID are left out.
+pub fn dump_function_guids(view: &BinaryView) -> Vec<(u64, String, FunctionGUID)> {
+    let mut function_guids: Vec<_> = view
+        .functions()
+        .iter()
+        .filter_map(|f| {
+            let llil = f.low_level_il().ok();
+            let guid = cached_function_guid_or_bytes(&f, llil.as_deref())?;
+            Some((f.start(), f.symbol().short_name().to_string(), guid))
+        })
+        .collect();
+    function_guids.sort_by_key(|(addr, _, _)| *addr);
+    function_guids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::cached_function_guid;
+    use crate::test::session;
+    use std::path::PathBuf;
+
+    #[test]
+    fn function_guids_sorted_by_address() {
+        let session = session();
+        let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
+        let view = session
+            .load(out_dir.join("atox.obj"))
+            .expect("Failed to load view");
+        let function_guids = dump_function_guids(&view);
+        assert!(!function_guids.is_empty());
+        assert!(function_guids.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+
+        for (addr, name, guid) in &function_guids {
+            let function = view
+                .function_at(&view.default_platform().unwrap(), *addr)
+                .expect("No function at address");
+            assert_eq!(*name, function.symbol().short_name().to_string());
+            if let Ok(llil) = function.low_level_il() {
+                assert_eq!(*guid, cached_function_guid(&function, &llil));
+            }
+        }
+    }
+}
@@ -1,14 +1,18 @@
 use crate::cache::{
-    cached_adjacency_constraints, cached_call_site_constraints, cached_function_guid,
+    cached_adjacency_constraints, cached_byte_function_guid, cached_call_site_constraints,
+    cached_function_guid,
 };
 use crate::convert::{from_bn_symbol, from_bn_type};
+use crate::match_result::GuidLevel;
+use crate::matcher::MatcherSettings;
 use binaryninja::architecture::{
-    Architecture, ImplicitRegisterExtend, Register as BNRegister, RegisterInfo,
+    Architecture, ImplicitRegisterExtend, Intrinsic, Register as BNRegister, RegisterInfo,
 };
 use binaryninja::basic_block::BasicBlock as BNBasicBlock;
-use binaryninja::binary_view::BinaryViewExt;
+use binaryninja::binary_view::{AnalysisState, BinaryView, BinaryViewExt};
 use binaryninja::confidence::MAX_CONFIDENCE;
 use binaryninja::function::{Function as BNFunction, NativeBlock};
+use binaryninja::high_level_il::{HighLevelILLiftedInstruction, HighLevelILLiftedOperand};
 use binaryninja::low_level_il::expression::{ExpressionHandler, LowLevelILExpressionKind};
 use binaryninja::low_level_il::function::{
     FunctionMutability, LowLevelILFunction, NonSSA, RegularNonSSA,
@@ -18,53 +22,668 @@ use binaryninja::low_level_il::instruction::{
 };
 use binaryninja::low_level_il::{LowLevelILRegister, VisitorAction};
 use binaryninja::rc::Ref as BNRef;
-use std::path::PathBuf;
+use binaryninja::settings::QueryOptions;
+use binaryninja::symbol::{Symbol as BNSymbol, SymbolType as BNSymbolType};
+use binaryninja::variable::Variable;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use warp::signature::basic_block::BasicBlockGUID;
-use warp::signature::function::constraints::FunctionConstraints;
+use warp::signature::function::constraints::{FunctionConstraint, FunctionConstraints};
 use warp::signature::function::{Function, FunctionGUID};
+use warp::signature::Data;
+use warp::symbol::class::SymbolClass;
+use warp::symbol::{Symbol, SymbolModifiers};
+
+/// Namespace used to derive byte-only function GUIDs, see [`byte_function_guid`].
+const BYTE_FUNCTION_GUID_NAMESPACE: uuid::Uuid =
+    uuid::uuid!("1b6c4cf4-8919-45b1-83f5-6eafe4d98422");
+
+/// Namespace used to derive HLIL function GUIDs, see [`function_guid_hlil`].
+const HLIL_FUNCTION_GUID_NAMESPACE: uuid::Uuid =
+    uuid::uuid!("b6a6e01b-a636-44c5-b45a-11ac252fea33");
 
 pub mod cache;
 pub mod convert;
+pub mod data;
+pub mod debug;
+pub mod match_result;
 mod matcher;
 /// Only used when compiled for cdylib target.
 mod plugin;
+#[cfg(any(test, feature = "test"))]
+pub mod test;
+
+pub use match_result::{match_result_for, WarpBinaryViewExt};
+pub use matcher::{
+    add_to_signature_blacklist, available_signature_platforms, list_signature_files,
+    match_view_with_files, remove_from_signature_blacklist, SignatureFileInfo, SignatureOrigin,
+};
+
+/// The directory containing the signatures shipped with Binary Ninja, `None` if there is no install directory.
+pub fn core_signature_dir() -> Option<PathBuf> {
+    root_core_signature_dir(&binaryninja::install_directory().ok()?)
+}
 
-pub fn core_signature_dir() -> PathBuf {
-    // Get core signatures for the given platform
-    let install_dir = binaryninja::install_directory();
+/// The directory containing the users signatures, `None` if there is no user directory.
+///
+/// If the `analysis.warp.userSignatureDir` setting is set that directory is used instead, so that
+/// signatures can be kept outside the user directory, e.g. in a shared repository.
+pub fn user_signature_dir() -> Option<PathBuf> {
+    match MatcherSettings::global().user_signature_dir {
+        Some(dir) => Some(dir),
+        None => Some(root_user_signature_dir(
+            &binaryninja::user_directory().ok()?,
+        )),
+    }
+}
+
+/// Read and parse the signature file at `path`, `Ok(None)` if the file could not be parsed.
+///
+/// With the `mmap` feature the file is memory mapped instead of read into a buffer. The signature
+/// format requires random access so the file is still parsed as a whole, but the mapped pages are
+/// backed by the file, so the peak heap usage is that of the parsed [`Data`] and not the file size
+/// on top of it.
+pub fn read_signature_file(path: &Path) -> std::io::Result<Option<Data>> {
+    #[cfg(feature = "mmap")]
+    {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: Signature files are not expected to be modified while they are being parsed.
+        let contents = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(Data::from_bytes(&contents))
+    }
+    #[cfg(not(feature = "mmap"))]
+    {
+        let contents = std::fs::read(path)?;
+        Ok(Data::from_bytes(&contents))
+    }
+}
+
+/// The core signature directory for the given install directory.
+///
+/// This is the only place the platform specific layout of the install is handled.
+fn root_core_signature_dir(install_dir: &Path) -> Option<PathBuf> {
     // macOS core dir is separate from the install dir.
     #[cfg(target_os = "macos")]
-    let core_dir = install_dir.parent().unwrap().join("Resources");
+    let core_dir = install_dir.parent()?.join("Resources");
     #[cfg(not(target_os = "macos"))]
-    let core_dir = install_dir;
-    core_dir.join("signatures")
+    let core_dir = install_dir.to_path_buf();
+    Some(core_dir.join("signatures"))
+}
+
+/// The user signature directory for the given user directory.
+fn root_user_signature_dir(user_dir: &Path) -> PathBuf {
+    user_dir.join("signatures/")
+}
+
+/// A [`FunctionGUID`] or [`BasicBlockGUID`] formatted as canonical lowercase hex.
+///
+/// The GUIDs are defined by the `warp` crate, so [`std::fmt::Display`] and [`std::str::FromStr`] are
+/// implemented on this wrapper instead:
+///
+/// ```
+/// use warp::signature::function::FunctionGUID;
+/// use warp_ninja::GuidString;
+///
+/// let guid_str = "a1b2c3d4-e5f6-5a7b-8c9d-0e1f2a3b4c5d";
+/// let GuidString(guid) = guid_str.parse::<GuidString<FunctionGUID>>().unwrap();
+/// assert_eq!(GuidString(guid).to_string(), guid_str);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GuidString<T>(pub T);
+
+/// The GUIDs that can be formatted with [`GuidString`].
+pub trait WarpGuid {
+    fn uuid(&self) -> uuid::Uuid;
+
+    fn from_uuid(guid: uuid::Uuid) -> Self;
+}
+
+impl WarpGuid for FunctionGUID {
+    fn uuid(&self) -> uuid::Uuid {
+        self.guid
+    }
+
+    fn from_uuid(guid: uuid::Uuid) -> Self {
+        FunctionGUID { guid }
+    }
+}
+
+impl WarpGuid for BasicBlockGUID {
+    fn uuid(&self) -> uuid::Uuid {
+        self.guid
+    }
+
+    fn from_uuid(guid: uuid::Uuid) -> Self {
+        BasicBlockGUID { guid }
+    }
+}
+
+impl<T: WarpGuid> std::fmt::Display for GuidString<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.uuid().hyphenated())
+    }
+}
+
+impl<T: WarpGuid> std::str::FromStr for GuidString<T> {
+    type Err = uuid::Error;
+
+    /// Parse the string produced by [`GuidString`], surrounding whitespace is ignored.
+    fn from_str(guid_str: &str) -> Result<Self, Self::Err> {
+        let guid = uuid::Uuid::parse_str(guid_str.trim())?;
+        Ok(GuidString(T::from_uuid(guid)))
+    }
+}
+
+/// Render the constraints as plain text, one constraint per line with its offset, GUID and symbol name.
+///
+/// Constraints without a GUID or symbol show `*` in its place. Useful to review why a function was matched.
+pub fn function_constraints_report(constraints: &FunctionConstraints) -> String {
+    let mut report = String::new();
+    let mut add_section = |title: &str, constraints: &HashSet<FunctionConstraint>| {
+        let mut constraints = constraints.iter().collect::<Vec<_>>();
+        constraints.sort_by_key(|c| (c.offset, c.symbol.as_ref().map(|s| s.name.to_owned())));
+        report.push_str(&format!("{} ({}):\n", title, constraints.len()));
+        for constraint in constraints {
+            let offset = match constraint.offset {
+                offset if offset < 0 => format!("-{:#x}", offset.unsigned_abs()),
+                offset => format!("+{:#x}", offset),
+            };
+            let guid = constraint
+                .guid
+                .map(|g| GuidString(g).to_string())
+                .unwrap_or("*".to_string());
+            let name = constraint
+                .symbol
+                .as_ref()
+                .map(|s| s.name.as_str())
+                .unwrap_or("*");
+            report.push_str(&format!("    {} {} {}\n", offset, guid, name));
+        }
+    };
+    add_section("Adjacent", &constraints.adjacent);
+    add_section("Call sites", &constraints.call_sites);
+    add_section("Caller sites", &constraints.caller_sites);
+    report
+}
+
+/// A call-site constraint of a function as the matcher sees it, see [`call_site_constraints`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedConstraint {
+    /// The address of the call site.
+    pub address: u64,
+    /// The GUID of the called function as canonical hex, `None` if the called function has no GUID (e.g. an import).
+    pub guid: Option<String>,
+    /// The name of the called function in the view, `None` if the called function has no symbol.
+    pub name: Option<String>,
+}
+
+/// The call-site constraints of the function with the called functions named by the symbols in the view, sorted by address.
+///
+/// Useful to see why the constraints picked a function when matching, the constraints are cached
+/// and the same as those used by the matcher.
+pub fn call_site_constraints(function: &BNFunction) -> Vec<ResolvedConstraint> {
+    let view = function.view();
+    let mut constraints = cached_call_site_constraints(function)
+        .into_iter()
+        .map(|constraint| {
+            let address = function.start().wrapping_add_signed(constraint.offset);
+            let constraint_name = constraint.symbol.map(|symbol| symbol.name);
+            // The constraint holds the raw name, prefer the name of the called symbol in the view.
+            let called_symbols = view
+                .code_refs_from_addr(address, Some(function))
+                .into_iter()
+                .filter_map(|target| view.symbol_by_address(target))
+                .collect::<Vec<_>>();
+            let called_symbol = match called_symbols.as_slice() {
+                [symbol] => Some(symbol),
+                symbols => symbols
+                    .iter()
+                    .find(|symbol| Some(symbol.raw_name().as_str()) == constraint_name.as_deref()),
+            };
+            ResolvedConstraint {
+                address,
+                guid: constraint.guid.map(|guid| GuidString(guid).to_string()),
+                name: called_symbol
+                    .map(|symbol| symbol.full_name().to_string())
+                    .or(constraint_name),
+            }
+        })
+        .collect::<Vec<_>>();
+    constraints.sort_by(|a, b| (a.address, &a.name).cmp(&(b.address, &b.name)));
+    constraints
+}
+
+/// Why a view has no functions to create signatures for, see [`check_named_functions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingNamedFunctions {
+    /// The view has no functions at all, analysis might not have completed.
+    NoFunctions,
+    /// The view has this many functions, but none of them are named (e.g. a stripped binary).
+    NoNamedFunctions(usize),
+}
+
+/// Check that the view has at least one named function, see [`is_function_named`].
+///
+/// Which functions are named is taken from the view settings, see [`GenerationSettings::include_symbol_types`].
+pub fn check_named_functions(view: &BinaryView) -> Result<(), MissingNamedFunctions> {
+    let functions = view.functions();
+    let include_symbol_types = GenerationSettings::from_view(view).include_symbol_types;
+    if functions.is_empty() {
+        Err(MissingNamedFunctions::NoFunctions)
+    } else if !functions
+        .iter()
+        .any(|f| is_function_named(&f, &include_symbol_types))
+    {
+        Err(MissingNamedFunctions::NoNamedFunctions(functions.len()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Whether the function has a meaningful name, i.e. its symbol is of one of the `include_symbol_types`.
+///
+/// With the default [`IncludedSymbolTypes`] this excludes auto-generated names such as `sub_401000`.
+///
+/// NOTE: Functions with user annotations are considered named, even if the symbol was never changed.
+pub fn is_function_named(func: &BNFunction, include_symbol_types: &IncludedSymbolTypes) -> bool {
+    // Checking for user annotations is cheaper than retrieving the symbol name, so do it first.
+    func.has_user_annotations() || include_symbol_types.includes(&func.symbol())
+}
+
+/// Whether the function name was generated by analysis from the address, e.g. `sub_401000`.
+///
+/// Only the prefix and the hex address are checked, so names that merely contain `sub_` (e.g.
+/// `my_sub_init`) are not considered auto-generated.
+pub fn is_auto_generated_name(name: &str) -> bool {
+    // Thunks to auto-generated functions are named `j_sub_401000`.
+    let name = name.strip_prefix("j_").unwrap_or(name);
+    name.strip_prefix("sub_")
+        .is_some_and(|addr| !addr.is_empty() && addr.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Which function symbols count as named, see [`is_function_named`].
+///
+/// Configured with the `analysis.warp.includeSymbolTypes` setting, see [`IncludedSymbolTypes::KEYS`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IncludedSymbolTypes {
+    /// Regular function symbols, this also covers symbol types without a key.
+    pub function: bool,
+    /// Functions identified as library functions (e.g. by signature matching).
+    pub library_function: bool,
+    /// Functions imported from another module.
+    pub imported_function: bool,
+    /// Symbolic functions (e.g. from debug info without an address in the binary).
+    pub symbolic: bool,
+    /// External functions.
+    pub external: bool,
+    /// Functions with an auto-generated name, see [`is_auto_generated_name`].
+    pub auto_named: bool,
+}
+
+impl IncludedSymbolTypes {
+    /// The setting keys with a description of the functions they include.
+    pub const KEYS: &'static [(&'static str, &'static str)] = &[
+        ("function", "Regular function symbols"),
+        ("libraryFunction", "Library function symbols"),
+        ("importedFunction", "Imported function symbols"),
+        ("symbolic", "Symbolic function symbols"),
+        ("external", "External function symbols"),
+        (
+            "autoNamed",
+            "Functions with an auto-generated name, e.g. sub_401000",
+        ),
+    ];
+
+    /// Build from the setting keys, unknown keys are logged and ignored.
+    pub fn from_keys<S: AsRef<str>>(keys: &[S]) -> Self {
+        let mut include = IncludedSymbolTypes {
+            function: false,
+            library_function: false,
+            imported_function: false,
+            symbolic: false,
+            external: false,
+            auto_named: false,
+        };
+        for key in keys {
+            match key.as_ref() {
+                "function" => include.function = true,
+                "libraryFunction" => include.library_function = true,
+                "importedFunction" => include.imported_function = true,
+                "symbolic" => include.symbolic = true,
+                "external" => include.external = true,
+                "autoNamed" => include.auto_named = true,
+                unknown => log::warn!(
+                    "Unknown symbol type `{}` in `{}`, expected one of {:?}",
+                    unknown,
+                    GenerationSettings::INCLUDE_SYMBOL_TYPES_SETTING,
+                    Self::KEYS.iter().map(|(key, _)| key).collect::<Vec<_>>()
+                ),
+            }
+        }
+        include
+    }
+
+    /// The setting keys of the included symbol types, the inverse of [`IncludedSymbolTypes::from_keys`].
+    pub fn keys(&self) -> Vec<&'static str> {
+        [
+            self.function,
+            self.library_function,
+            self.imported_function,
+            self.symbolic,
+            self.external,
+            self.auto_named,
+        ]
+        .into_iter()
+        .zip(Self::KEYS)
+        .filter_map(|(included, (key, _))| included.then_some(*key))
+        .collect()
+    }
+
+    /// Whether a function with the symbol counts as named.
+    pub fn includes(&self, symbol: &BNSymbol) -> bool {
+        if is_auto_generated_name(symbol.short_name().as_str()) {
+            return self.auto_named;
+        }
+        match symbol.sym_type() {
+            BNSymbolType::LibraryFunction => self.library_function,
+            BNSymbolType::ImportedFunction => self.imported_function,
+            BNSymbolType::Symbolic => self.symbolic,
+            BNSymbolType::External => self.external,
+            _ => self.function,
+        }
+    }
+}
+
+impl Default for IncludedSymbolTypes {
+    /// Every symbol type except auto-generated names.
+    fn default() -> Self {
+        Self::from_keys(GenerationSettings::INCLUDE_SYMBOL_TYPES_DEFAULT)
+    }
+}
+
+/// Whether a signature should be generated for the function, shared by every signature generator so
+/// they produce the same signatures for the same binary.
+///
+/// Functions with skipped analysis (e.g. too large) are never included, they have no reliable IL
+/// and would produce a garbage GUID. Unnamed functions, see [`GenerationSettings::include_symbol_types`],
+/// are only included with `include_unnamed`.
+pub fn should_signature_function(
+    func: &BNFunction,
+    include_unnamed: bool,
+    settings: &GenerationSettings,
+) -> bool {
+    // Cheap predicates first, the name requires retrieving the symbol.
+    !func.analysis_skipped()
+        && (include_unnamed || is_function_named(func, &settings.include_symbol_types))
 }
 
-pub fn user_signature_dir() -> PathBuf {
-    binaryninja::user_directory().join("signatures/")
+/// Settings limiting the signatures generated, shared by every signature generator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GenerationSettings {
+    /// Skip functions whose address span is larger than this, in bytes.
+    ///
+    /// Giant functions (e.g. obfuscated dispatchers) are slow to signature and match unreliably.
+    ///
+    /// This is set to `None` (unlimited) by default.
+    pub max_function_size: Option<u64>,
+    /// How deep named types are expanded when converting types, see [`crate::convert::from_bn_type`].
+    ///
+    /// Named types nested deeper than this are only referenced by name, without a type GUID. Capping
+    /// this speeds up the conversion of deeply nested types (e.g. template heavy C++).
+    ///
+    /// This is set to `None` (unlimited) by default.
+    pub max_type_depth: Option<usize>,
+    /// Which function symbols count as named, only named functions are signatured by default.
+    ///
+    /// This is set to [`GenerationSettings::INCLUDE_SYMBOL_TYPES_DEFAULT`] by default, which excludes
+    /// auto-generated `sub_` names.
+    pub include_symbol_types: IncludedSymbolTypes,
 }
 
+impl GenerationSettings {
+    /// Zero is unlimited, settings cannot be null.
+    pub const MAX_FUNCTION_SIZE_DEFAULT: u64 = 0;
+    pub const MAX_FUNCTION_SIZE_SETTING: &'static str = "analysis.warp.maxFunctionSize";
+    /// Zero is unlimited, settings cannot be null.
+    pub const MAX_TYPE_DEPTH_DEFAULT: u64 = 0;
+    pub const MAX_TYPE_DEPTH_SETTING: &'static str = "analysis.warp.maxTypeDepth";
+    pub const INCLUDE_SYMBOL_TYPES_DEFAULT: &'static [&'static str] = &[
+        "function",
+        "libraryFunction",
+        "importedFunction",
+        "symbolic",
+        "external",
+    ];
+    pub const INCLUDE_SYMBOL_TYPES_SETTING: &'static str = "analysis.warp.includeSymbolTypes";
+
+    /// Register the generation settings, call this once when you initialize so that the settings exist.
+    pub fn register() {
+        let bn_settings = binaryninja::settings::Settings::new();
+        let max_function_size_props = serde_json::json!({
+            "title" : "Maximum Function Size",
+            "type" : "number",
+            "default" : Self::MAX_FUNCTION_SIZE_DEFAULT,
+            "description" : "Functions larger than this (in bytes) are skipped when generating signatures, zero is unlimited.",
+            "ignore" : ["SettingsProjectScope"]
+        });
+        bn_settings.register_setting_json(
+            Self::MAX_FUNCTION_SIZE_SETTING,
+            max_function_size_props.to_string(),
+        );
+
+        let max_type_depth_props = serde_json::json!({
+            "title" : "Maximum Type Depth",
+            "type" : "number",
+            "default" : Self::MAX_TYPE_DEPTH_DEFAULT,
+            "description" : "Named types nested deeper than this are referenced by name only when converting types, zero is unlimited. Lower this if type conversion is slow for deeply nested types.",
+            "ignore" : ["SettingsProjectScope"]
+        });
+        bn_settings.register_setting_json(
+            Self::MAX_TYPE_DEPTH_SETTING,
+            max_type_depth_props.to_string(),
+        );
+
+        let symbol_type_keys = IncludedSymbolTypes::KEYS
+            .iter()
+            .map(|(key, description)| format!("\"{}\" ({})", key, description))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let include_symbol_types_props = serde_json::json!({
+            "title" : "Include Symbol Types",
+            "type" : "array",
+            "sorted" : false,
+            "default" : Self::INCLUDE_SYMBOL_TYPES_DEFAULT,
+            "description" : format!("Functions with these symbol types are considered named and have signatures generated, functions with user annotations are always included. Available symbol types: {}.", symbol_type_keys),
+            "ignore" : ["SettingsProjectScope"]
+        });
+        bn_settings.register_setting_json(
+            Self::INCLUDE_SYMBOL_TYPES_SETTING,
+            include_symbol_types_props.to_string(),
+        );
+    }
+
+    /// The settings for the view, taken from the view settings.
+    pub fn from_view(view: &BinaryView) -> Self {
+        let mut settings = GenerationSettings::default();
+        let bn_settings = binaryninja::settings::Settings::new();
+        let mut query_options = QueryOptions::new_with_view(view);
+        if bn_settings.contains(Self::MAX_FUNCTION_SIZE_SETTING) {
+            let max_function_size = bn_settings
+                .get_integer_with_opts(Self::MAX_FUNCTION_SIZE_SETTING, &mut query_options);
+            settings.max_function_size = Some(max_function_size).filter(|&size| size != 0);
+        }
+        if bn_settings.contains(Self::MAX_TYPE_DEPTH_SETTING) {
+            let max_type_depth =
+                bn_settings.get_integer_with_opts(Self::MAX_TYPE_DEPTH_SETTING, &mut query_options);
+            settings.max_type_depth = Some(max_type_depth as usize).filter(|&depth| depth != 0);
+        }
+        if bn_settings.contains(Self::INCLUDE_SYMBOL_TYPES_SETTING) {
+            let include_symbol_types = bn_settings
+                .get_string_list_with_opts(Self::INCLUDE_SYMBOL_TYPES_SETTING, &mut query_options);
+            let keys = include_symbol_types.iter().collect::<Vec<_>>();
+            settings.include_symbol_types = IncludedSymbolTypes::from_keys(&keys);
+        }
+        settings
+    }
+
+    /// Whether the address span of the function exceeds [`GenerationSettings::max_function_size`].
+    pub fn is_function_too_large(&self, func: &BNFunction) -> bool {
+        self.max_function_size
+            .is_some_and(|max| func.highest_address() - func.start() > max)
+    }
+}
+
+/// Build the function signature for `func`.
+///
+/// Adjacency constraints are only added once analysis of the view is complete, unless
+/// `assume_analyzed` is set by a caller that already knows analysis is done.
 pub fn build_function<A: Architecture, M: FunctionMutability>(
     func: &BNFunction,
     llil: &LowLevelILFunction<A, M, NonSSA<RegularNonSSA>>,
+    assume_analyzed: bool,
+) -> Function {
+    build_function_with_guid(
+        func,
+        cached_function_guid(func, llil),
+        GuidLevel::Llil,
+        assume_analyzed,
+    )
+}
+
+/// Build the function using a byte-only GUID, for functions without LLIL, see [`byte_function_guid`].
+pub fn build_byte_function(func: &BNFunction) -> Option<Function> {
+    Some(build_function_with_guid(
+        func,
+        cached_byte_function_guid(func)?,
+        GuidLevel::Bytes,
+        false,
+    ))
+}
+
+/// Build the function using the experimental HLIL GUID, see [`function_guid_hlil`].
+pub fn build_hlil_function(func: &BNFunction) -> Option<Function> {
+    Some(build_function_with_guid(
+        func,
+        function_guid_hlil(func)?,
+        GuidLevel::Hlil,
+        false,
+    ))
+}
+
+fn build_function_with_guid(
+    func: &BNFunction,
+    guid: FunctionGUID,
+    level: GuidLevel,
+    assume_analyzed: bool,
 ) -> Function {
     let bn_fn_ty = func.function_type();
+    let adjacent = if assume_analyzed || is_analysis_complete(&func.view()) {
+        // NOTE: We do not filter out adjacent functions here.
+        cached_adjacency_constraints(func, |_| true)
+    } else {
+        log::debug!(
+            "Analysis incomplete, omitting adjacency constraints for function 0x{:x}",
+            func.start()
+        );
+        HashSet::new()
+    };
+    let mut markers = HashSet::new();
+    if !func.can_return().contents {
+        markers.insert(marker_constraint(NO_RETURN_CONSTRAINT_NAME.to_string()));
+    }
+    if level != GuidLevel::Llil {
+        markers.insert(marker_constraint(format!(
+            "{}{}",
+            GUID_LEVEL_CONSTRAINT_PREFIX, level
+        )));
+    }
     Function {
-        guid: cached_function_guid(func, llil),
+        guid,
         symbol: from_bn_symbol(&func.symbol()),
-        ty: from_bn_type(&func.view(), &bn_fn_ty, MAX_CONFIDENCE),
+        ty: from_bn_type(&func.view(), &bn_fn_ty, function_type_confidence(func)),
         constraints: FunctionConstraints {
             // NOTE: Adding adjacent only works if analysis is complete.
-            // NOTE: We do not filter out adjacent functions here.
-            adjacent: cached_adjacency_constraints(func, |_| true),
+            adjacent,
             call_sites: cached_call_site_constraints(func),
             // TODO: Add caller sites (when adjacent and call sites are minimal)
             // NOTE: Adding caller sites only works if analysis is complete.
-            caller_sites: Default::default(),
+            caller_sites: markers,
         },
     }
 }
 
+/// The symbol name of the caller site constraint marking a function that cannot return.
+///
+/// WARP function types have no no-return status, storing it in the type would change its GUID. It is
+/// carried as a caller site constraint instead, caller sites are not used for matching.
+pub const NO_RETURN_CONSTRAINT_NAME: &str = "__warp_no_return";
+
+/// The symbol name prefix of the caller site constraint storing the [`GuidLevel`] of the signature.
+///
+/// Signatures without it are LLIL signatures, so signature files from before the GUID levels still match.
+pub const GUID_LEVEL_CONSTRAINT_PREFIX: &str = "__warp_guid_level:";
+
+/// A caller site constraint only carrying a symbol name, see [`NO_RETURN_CONSTRAINT_NAME`].
+fn marker_constraint(name: String) -> FunctionConstraint {
+    FunctionConstraint {
+        guid: None,
+        symbol: Some(Symbol::new(
+            name,
+            SymbolClass::Function,
+            SymbolModifiers::default(),
+        )),
+        offset: 0,
+    }
+}
+
+fn marker_constraint_names(function: &Function) -> impl Iterator<Item = &str> {
+    function
+        .constraints
+        .caller_sites
+        .iter()
+        .filter(|constraint| constraint.guid.is_none())
+        .filter_map(|constraint| constraint.symbol.as_ref())
+        .map(|symbol| symbol.name.as_str())
+}
+
+/// Whether the signature was created from a function that cannot return, see [`NO_RETURN_CONSTRAINT_NAME`].
+pub fn is_no_return(function: &Function) -> bool {
+    marker_constraint_names(function).any(|name| name == NO_RETURN_CONSTRAINT_NAME)
+}
+
+/// The level of the signature GUID, see [`GUID_LEVEL_CONSTRAINT_PREFIX`].
+pub fn signature_guid_level(function: &Function) -> GuidLevel {
+    marker_constraint_names(function)
+        .find_map(|name| name.strip_prefix(GUID_LEVEL_CONSTRAINT_PREFIX))
+        .and_then(GuidLevel::from_name)
+        .unwrap_or(GuidLevel::Llil)
+}
+
+/// Whether analysis of the view has finished, adjacency constraints computed before then may be wrong.
+pub fn is_analysis_complete(view: &BinaryView) -> bool {
+    matches!(view.analysis_progress().state, AnalysisState::IdleState)
+}
+
+/// The confidence of the functions type, so that guessed types are not stored as if they were certain.
+///
+/// A user set type is always [`MAX_CONFIDENCE`], otherwise the type is only as confident as its least
+/// confident part (return type, parameters or calling convention).
+pub fn function_type_confidence(func: &BNFunction) -> u8 {
+    if func.has_user_type() {
+        return MAX_CONFIDENCE;
+    }
+    let return_confidence = func.return_type().confidence;
+    let parameters_confidence = func.parameter_variables().confidence;
+    let calling_convention_confidence = func
+        .calling_convention()
+        .map(|cc| cc.confidence)
+        .unwrap_or(MAX_CONFIDENCE);
+    return_confidence
+        .min(parameters_confidence)
+        .min(calling_convention_confidence)
+}
+
 /// Basic blocks sorted from high to low.
 pub fn sorted_basic_blocks(func: &BNFunction) -> Vec<BNRef<BNBasicBlock<NativeBlock>>> {
     let mut basic_blocks = func
@@ -76,21 +695,154 @@ pub fn sorted_basic_blocks(func: &BNFunction) -> Vec<BNRef<BNBasicBlock<NativeBl
     basic_blocks
 }
 
+/// Options controlling which instructions of a function contribute to its GUID.
+///
+/// GUIDs computed with different options are not comparable, the options must be the same when
+/// generating signatures and when matching against them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GuidOptions {
+    /// Leave NOPs and moves of a register to itself out of the GUID.
+    ///
+    /// This allows a function to match after it has been hot-patched (or padded differently).
+    /// Disabling it makes GUIDs more specific, which can help with binaries that are never
+    /// hot-patched, but the same function with and without the hot-patch padding will no longer match.
+    ///
+    /// This is set to [GuidOptions::BLACKLIST_NOPS_DEFAULT] by default.
+    pub blacklist_nops: bool,
+}
+
+impl GuidOptions {
+    pub const BLACKLIST_NOPS_DEFAULT: bool = true;
+    pub const BLACKLIST_NOPS_SETTING: &'static str = "analysis.warp.blacklistNops";
+
+    /// Register the GUID settings, call this once when you initialize so that the settings exist.
+    pub fn register() {
+        let bn_settings = binaryninja::settings::Settings::new();
+        let blacklist_nops_props = serde_json::json!({
+            "title" : "Blacklist NOPs",
+            "type" : "boolean",
+            "default" : Self::BLACKLIST_NOPS_DEFAULT,
+            "description" : "Leave NOPs and redundant moves out of function GUIDs so that hot-patched functions still match. Disabling this makes GUIDs more specific, but they will only match functions with the exact same padding. Signatures must be generated with the same value to match.",
+            "ignore" : ["SettingsProjectScope"]
+        });
+        bn_settings.register_setting_json(
+            Self::BLACKLIST_NOPS_SETTING,
+            blacklist_nops_props.to_string(),
+        );
+    }
+
+    /// The options for the view, taken from the view settings.
+    pub fn from_view(view: &BinaryView) -> Self {
+        let mut options = GuidOptions::default();
+        let bn_settings = binaryninja::settings::Settings::new();
+        let mut query_options = QueryOptions::new_with_view(view);
+        if bn_settings.contains(Self::BLACKLIST_NOPS_SETTING) {
+            options.blacklist_nops =
+                bn_settings.get_bool_with_opts(Self::BLACKLIST_NOPS_SETTING, &mut query_options);
+        }
+        options
+    }
+}
+
+impl Default for GuidOptions {
+    fn default() -> Self {
+        Self {
+            blacklist_nops: GuidOptions::BLACKLIST_NOPS_DEFAULT,
+        }
+    }
+}
+
 pub fn function_guid<A: Architecture, M: FunctionMutability>(
     func: &BNFunction,
     llil: &LowLevelILFunction<A, M, NonSSA<RegularNonSSA>>,
+) -> FunctionGUID {
+    function_guid_with_options(func, llil, &GuidOptions::default())
+}
+
+/// Compute the GUID of the function with the given options, see [`GuidOptions`].
+pub fn function_guid_with_options<A: Architecture, M: FunctionMutability>(
+    func: &BNFunction,
+    llil: &LowLevelILFunction<A, M, NonSSA<RegularNonSSA>>,
+    options: &GuidOptions,
 ) -> FunctionGUID {
     let basic_blocks = sorted_basic_blocks(func);
+    // Read the whole function once instead of once per basic block.
+    let function_bytes = FunctionBytes::read(&func.view(), &basic_blocks);
     let basic_block_guids = basic_blocks
         .iter()
-        .map(|bb| basic_block_guid(bb, llil))
+        .map(|bb| basic_block_guid_with_bytes(bb, llil, &function_bytes.block_bytes(bb), options))
         .collect::<Vec<_>>();
     FunctionGUID::from_basic_blocks(&basic_block_guids)
 }
 
+/// The bytes of a function, read once for the entire address range of its basic blocks.
+struct FunctionBytes {
+    start: u64,
+    bytes: Vec<u8>,
+}
+
+impl FunctionBytes {
+    /// Functions spanning more than this are not read at once, their basic blocks are read separately.
+    ///
+    /// This avoids reading everything in between for functions with far away blocks (e.g. cold paths).
+    const MAX_READ_LEN: u64 = 0x100000;
+
+    fn read(view: &BinaryView, basic_blocks: &[BNRef<BNBasicBlock<NativeBlock>>]) -> Self {
+        let start = basic_blocks.iter().map(|bb| bb.start_index()).min();
+        let end = basic_blocks.iter().map(|bb| bb.end_index()).max();
+        match (start, end) {
+            (Some(start), Some(end)) if end.saturating_sub(start) <= Self::MAX_READ_LEN => Self {
+                start,
+                bytes: view.read_vec(start, (end - start) as usize),
+            },
+            _ => Self {
+                start: 0,
+                bytes: Vec::new(),
+            },
+        }
+    }
+
+    /// The bytes of the basic block, read from the view if the block was not part of the function read.
+    fn block_bytes(&self, basic_block: &BNBasicBlock<NativeBlock>) -> Cow<[u8]> {
+        let block_start = basic_block.start_index();
+        let block_len = basic_block.end_index().saturating_sub(block_start) as usize;
+        let offset = block_start.wrapping_sub(self.start) as usize;
+        match self.bytes.get(offset..offset.saturating_add(block_len)) {
+            Some(bytes) if block_start >= self.start => Cow::Borrowed(bytes),
+            _ => Cow::Owned(read_block_bytes(basic_block)),
+        }
+    }
+}
+
+fn read_block_bytes(basic_block: &BNBasicBlock<NativeBlock>) -> Vec<u8> {
+    let view = basic_block.function().view();
+    let basic_block_start = basic_block.start_index();
+    let basic_block_len = basic_block.end_index().saturating_sub(basic_block_start) as usize;
+    view.read_vec(basic_block_start, basic_block_len)
+}
+
 pub fn basic_block_guid<A: Architecture, M: FunctionMutability>(
     basic_block: &BNBasicBlock<NativeBlock>,
     llil: &LowLevelILFunction<A, M, NonSSA<RegularNonSSA>>,
+) -> BasicBlockGUID {
+    basic_block_guid_with_options(basic_block, llil, &GuidOptions::default())
+}
+
+/// Compute the GUID of the basic block with the given options, see [`GuidOptions`].
+pub fn basic_block_guid_with_options<A: Architecture, M: FunctionMutability>(
+    basic_block: &BNBasicBlock<NativeBlock>,
+    llil: &LowLevelILFunction<A, M, NonSSA<RegularNonSSA>>,
+    options: &GuidOptions,
+) -> BasicBlockGUID {
+    basic_block_guid_with_bytes(basic_block, llil, &read_block_bytes(basic_block), options)
+}
+
+/// Compute the GUID of the basic block from its already read `block_bytes`, see [`basic_block_guid`].
+fn basic_block_guid_with_bytes<A: Architecture, M: FunctionMutability>(
+    basic_block: &BNBasicBlock<NativeBlock>,
+    llil: &LowLevelILFunction<A, M, NonSSA<RegularNonSSA>>,
+    block_bytes: &[u8],
+    options: &GuidOptions,
 ) -> BasicBlockGUID {
     let func = basic_block.function();
     let view = func.view();
@@ -99,6 +851,9 @@ pub fn basic_block_guid<A: Architecture, M: FunctionMutability>(
 
     // NOPs and useless moves are blacklisted to allow for hot-patchable functions.
     let is_blacklisted_instr = |instr: &LowLevelILInstruction<A, M, NonSSA<RegularNonSSA>>| {
+        if !options.blacklist_nops {
+            return false;
+        }
         match instr.kind() {
             LowLevelILInstructionKind::Nop(_) => true,
             LowLevelILInstructionKind::SetReg(op) => {
@@ -127,78 +882,245 @@ pub fn basic_block_guid<A: Architecture, M: FunctionMutability>(
     };
 
     let is_variant_instr = |instr: &LowLevelILInstruction<A, M, NonSSA<RegularNonSSA>>| {
-        let is_variant_expr = |expr: &LowLevelILExpressionKind<A, M, NonSSA<RegularNonSSA>>| {
-            // TODO: Checking the section here is slow, we should gather all section ranges outside of this.
-            match expr {
-                LowLevelILExpressionKind::ConstPtr(op)
-                    if !view.sections_at(op.value()).is_empty() =>
-                {
-                    // Constant Pointer must be in a section for it to be relocatable.
-                    // NOTE: We cannot utilize segments here as there will be a zero based segment.
-                    true
-                }
-                LowLevelILExpressionKind::ExternPtr(_) => true,
-                LowLevelILExpressionKind::Const(op) if !view.sections_at(op.value()).is_empty() => {
-                    // Constant value must be in a section for it to be relocatable.
-                    // NOTE: We cannot utilize segments here as there will be a zero based segment.
-                    true
-                }
-                _ => false,
-            }
-        };
-
-        // Visit instruction expressions looking for variant expression, [VisitorAction::Halt] means variant.
-        instr.visit_tree(&mut |expr| {
-            if is_variant_expr(&expr.kind()) {
-                // Found a variant expression
-                VisitorAction::Halt
-            } else {
-                VisitorAction::Descend
-            }
-        }) == VisitorAction::Halt
+        instruction_is_variant(&view, instr)
     };
 
-    let basic_block_range = basic_block.start_index()..basic_block.end_index();
-    let mut basic_block_bytes = Vec::with_capacity(basic_block_range.count());
+    // Each instruction is sliced out of the basic block bytes.
+    let basic_block_start = basic_block.start_index();
+    let mut basic_block_bytes = Vec::with_capacity(block_bytes.len());
     for instr_addr in basic_block.into_iter() {
-        let mut instr_bytes = view.read_vec(instr_addr, max_instr_len);
-        if let Some(instr_info) = arch.instruction_info(&instr_bytes, instr_addr) {
-            instr_bytes.truncate(instr_info.length);
+        let instr_offset = (instr_addr - basic_block_start) as usize;
+        let Some(remaining_bytes) = block_bytes.get(instr_offset..) else {
+            break;
+        };
+        let max_instr_bytes = &remaining_bytes[..remaining_bytes.len().min(max_instr_len)];
+        if let Some(instr_info) = arch.instruction_info(max_instr_bytes, instr_addr) {
+            let instr_bytes = &max_instr_bytes[..instr_info.length.min(max_instr_bytes.len())];
             if let Some(instr_llil) = llil.instruction_at(instr_addr) {
                 // If instruction is blacklisted don't include the bytes.
                 if !is_blacklisted_instr(&instr_llil) {
                     if is_variant_instr(&instr_llil) {
                         // Found a variant instruction, mask off entire instruction.
-                        instr_bytes.fill(0);
+                        basic_block_bytes.resize(basic_block_bytes.len() + instr_bytes.len(), 0);
+                    } else {
+                        // Add the instructions bytes to the basic blocks bytes
+                        basic_block_bytes.extend_from_slice(instr_bytes);
                     }
-                    // Add the instructions bytes to the basic blocks bytes
-                    basic_block_bytes.extend(instr_bytes);
                 }
             }
         }
     }
 
-    BasicBlockGUID::from(basic_block_bytes.as_slice())
+    basic_block_guid_from_bytes(&basic_block_bytes)
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::cache::cached_function_guid;
-    use binaryninja::binary_view::BinaryViewExt;
-    use binaryninja::headless::Session;
-    use std::path::PathBuf;
-    use std::sync::OnceLock;
+/// Whether the instruction references a relocatable address, the bytes of such instructions are
+/// masked (zeroed) by [`basic_block_guid`].
+///
+/// An address is relocatable if it is within a section of the `view` or is an external pointer.
+/// Use this to show which bytes will be masked before generating signatures.
+pub fn instruction_is_variant<A: Architecture, M: FunctionMutability>(
+    view: &BinaryView,
+    instr: &LowLevelILInstruction<A, M, NonSSA<RegularNonSSA>>,
+) -> bool {
+    let is_variant_expr = |expr: &LowLevelILExpressionKind<A, M, NonSSA<RegularNonSSA>>| {
+        // TODO: Checking the section here is slow, we should gather all section ranges outside of this.
+        match expr {
+            LowLevelILExpressionKind::ConstPtr(op) if !view.sections_at(op.value()).is_empty() => {
+                // Constant Pointer must be in a section for it to be relocatable.
+                // NOTE: We cannot utilize segments here as there will be a zero based segment.
+                true
+            }
+            LowLevelILExpressionKind::ExternPtr(_) => true,
+            LowLevelILExpressionKind::Const(op) if !view.sections_at(op.value()).is_empty() => {
+                // Constant value must be in a section for it to be relocatable.
+                // NOTE: We cannot utilize segments here as there will be a zero based segment.
+                true
+            }
+            _ => false,
+        }
+    };
+
+    // Visit instruction expressions looking for variant expression, [VisitorAction::Halt] means variant.
+    instr.visit_tree(&mut |expr| {
+        if is_variant_expr(&expr.kind()) {
+            // Found a variant expression
+            VisitorAction::Halt
+        } else {
+            VisitorAction::Descend
+        }
+    }) == VisitorAction::Halt
+}
+
+/// Compute the GUID of a basic block from its bytes, as they are hashed by [`basic_block_guid`].
+///
+/// The bytes are hashed as-is, the caller is responsible for removing blacklisted instructions and
+/// masking (zeroing) variant instructions beforehand, otherwise the GUID will not match the GUID of
+/// the same basic block in a view.
+///
+/// ```
+/// // xor eax, eax; ret
+/// let guid = warp_ninja::basic_block_guid_from_bytes(&[0x31, 0xc0, 0xc3]);
+/// assert_eq!(guid, warp_ninja::basic_block_guid_from_bytes(&[0x31, 0xc0, 0xc3]));
+/// println!("{}", warp_ninja::GuidString(guid));
+/// ```
+pub fn basic_block_guid_from_bytes(bytes: &[u8]) -> BasicBlockGUID {
+    BasicBlockGUID::from(bytes)
+}
+
+/// Compute the GUID from the raw bytes of the function, used when LLIL is unavailable (e.g. import thunks).
+///
+/// Without LLIL no instructions are blacklisted or masked, the GUID is then derived in a separate
+/// namespace so that it never collides with a GUID from [`function_guid`]. Byte-only GUIDs are only
+/// comparable to other byte-only GUIDs.
+///
+/// Returns `None` if the function has no basic blocks.
+pub fn byte_function_guid(func: &BNFunction) -> Option<FunctionGUID> {
+    let basic_block_guids = sorted_basic_blocks(func)
+        .iter()
+        .map(|bb| byte_basic_block_guid(bb))
+        .collect::<Vec<_>>();
+    if basic_block_guids.is_empty() {
+        return None;
+    }
+    let guid = FunctionGUID::from_basic_blocks(&basic_block_guids).guid;
+    Some(FunctionGUID {
+        guid: uuid::Uuid::new_v5(&BYTE_FUNCTION_GUID_NAMESPACE, guid.as_bytes()),
+    })
+}
+
+/// Compute the GUID from the raw bytes of the basic block, see [`byte_function_guid`].
+pub fn byte_basic_block_guid(basic_block: &BNBasicBlock<NativeBlock>) -> BasicBlockGUID {
+    basic_block_guid_from_bytes(&read_block_bytes(basic_block))
+}
+
+/// **Experimental** Compute the GUID from the normalized HLIL of the function.
+///
+/// Unlike [`function_guid`] this is not sensitive to instruction selection, so the same source compiled
+/// by different compiler versions is more likely to produce the same GUID. Only the structure of the HLIL
+/// is hashed: the operation of every expression and its operands, with relocatable constants masked
+/// like in [`basic_block_guid`] and variables replaced by the order they are first used in.
+///
+/// The GUID is derived in a separate namespace, HLIL GUIDs are only comparable to other HLIL GUIDs.
+///
+/// Returns `None` if the function has no HLIL.
+pub fn function_guid_hlil(func: &BNFunction) -> Option<FunctionGUID> {
+    let hlil = func.high_level_il(true).ok()?;
+    let view = func.view();
+    let mut normalizer = HlilNormalizer {
+        view: &view,
+        variables: HashMap::new(),
+        bytes: Vec::new(),
+    };
+    normalizer.visit_instr(&hlil.root().lift());
+    Some(FunctionGUID {
+        guid: uuid::Uuid::new_v5(&HLIL_FUNCTION_GUID_NAMESPACE, &normalizer.bytes),
+    })
+}
 
-    static INIT: OnceLock<Session> = OnceLock::new();
+/// Serializes a HLIL expression tree into the bytes hashed by [`function_guid_hlil`].
+struct HlilNormalizer<'a> {
+    view: &'a BinaryView,
+    /// Variables numbered by the order they are first used in.
+    variables: HashMap<Variable, u32>,
+    bytes: Vec<u8>,
+}
 
-    fn get_session<'a>() -> &'a Session {
-        // TODO: This is not shared between other test modules, should still be fine (mutex in core now).
-        INIT.get_or_init(|| Session::new().expect("Failed to initialize session"))
+impl HlilNormalizer<'_> {
+    fn visit_instr(&mut self, instr: &HighLevelILLiftedInstruction) {
+        self.bytes.extend_from_slice(instr.name().as_bytes());
+        self.bytes.extend_from_slice(&instr.size.to_le_bytes());
+        self.bytes.push(b'(');
+        for (_, operand) in instr.operands() {
+            self.visit_operand(&operand);
+            self.bytes.push(b',');
+        }
+        self.bytes.push(b')');
     }
 
+    fn visit_operand(&mut self, operand: &HighLevelILLiftedOperand) {
+        match operand {
+            HighLevelILLiftedOperand::Expr(expr) => self.visit_instr(expr),
+            HighLevelILLiftedOperand::ExprList(exprs) => {
+                exprs.iter().for_each(|expr| self.visit_instr(expr))
+            }
+            HighLevelILLiftedOperand::Int(value) => self.visit_int(*value),
+            HighLevelILLiftedOperand::IntList(values) => {
+                values.iter().for_each(|value| self.visit_int(*value))
+            }
+            HighLevelILLiftedOperand::Float(value) => {
+                self.bytes.extend_from_slice(&value.to_le_bytes())
+            }
+            HighLevelILLiftedOperand::Intrinsic(intrinsic) => {
+                self.bytes.extend_from_slice(intrinsic.name().as_bytes())
+            }
+            HighLevelILLiftedOperand::MemberIndex(index) => {
+                let index = index.unwrap_or(usize::MAX);
+                self.bytes.extend_from_slice(&index.to_le_bytes())
+            }
+            HighLevelILLiftedOperand::Var(var) => self.visit_var(*var),
+            HighLevelILLiftedOperand::VarSsa(var) => self.visit_var(var.variable),
+            HighLevelILLiftedOperand::VarSsaList(vars) => {
+                vars.iter().for_each(|var| self.visit_var(var.variable))
+            }
+            // Constant data and labels are specific to the binary, mask them off.
+            HighLevelILLiftedOperand::ConstantData(_) | HighLevelILLiftedOperand::Label(_) => {
+                self.bytes.push(b'?')
+            }
+        }
+    }
+
+    fn visit_int(&mut self, value: u64) {
+        // Value must be in a section for it to be relocatable, see [`basic_block_guid`].
+        if self.view.sections_at(value).is_empty() {
+            self.bytes.extend_from_slice(&value.to_le_bytes());
+        } else {
+            self.bytes.push(b'?');
+        }
+    }
+
+    fn visit_var(&mut self, var: Variable) {
+        let next_index = self.variables.len() as u32;
+        let index = *self.variables.entry(var).or_insert(next_index);
+        self.bytes.extend_from_slice(&index.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::cached_function_guid;
+    use crate::match_result::GuidLevel;
+    use crate::test::{session, view_with_function, view_with_functions};
+    use crate::{
+        basic_block_guid, basic_block_guid_from_bytes, build_byte_function, build_function,
+        build_hlil_function, byte_function_guid, call_site_constraints, check_named_functions,
+        core_signature_dir, function_constraints_report, function_guid, function_guid_hlil,
+        function_guid_with_options, instruction_is_variant, is_analysis_complete,
+        is_auto_generated_name, is_function_named, read_signature_file, root_core_signature_dir,
+        root_user_signature_dir, should_signature_function, signature_guid_level,
+        sorted_basic_blocks, user_signature_dir, GenerationSettings, GuidOptions, GuidString,
+        IncludedSymbolTypes, MissingNamedFunctions,
+    };
+    use binaryninja::binary_view::{BinaryView, BinaryViewExt};
+    use binaryninja::confidence::{Conf, MAX_CONFIDENCE};
+    use binaryninja::file_metadata::FileMetadata;
+    use binaryninja::platform::Platform;
+    use binaryninja::section::Section;
+    use binaryninja::symbol::{SymbolBuilder, SymbolType};
+    use binaryninja::types::Type as BNType;
+    use std::collections::{HashMap, HashSet};
+    use std::path::{Path, PathBuf};
+    use warp::r#type::class::TypeClass;
+    use warp::signature::basic_block::BasicBlockGUID;
+    use warp::signature::function::constraints::{FunctionConstraint, FunctionConstraints};
+    use warp::signature::function::FunctionGUID;
+    use warp::signature::Data;
+    use warp::symbol::class::SymbolClass;
+    use warp::symbol::{Symbol, SymbolModifiers};
+
     #[test]
     fn insta_signatures() {
-        let session = get_session();
+        let session = session();
         let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
         for entry in std::fs::read_dir(out_dir).expect("Failed to read OUT_DIR") {
             let entry = entry.expect("Failed to read directory entry");
@@ -217,4 +1139,470 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn signature_round_trip() {
+        let session = session();
+        let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
+        let view = session
+            .load(out_dir.join("atox.obj"))
+            .expect("Failed to load view");
+        assert!(crate::test::roundtrip(&view));
+    }
+
+    #[test]
+    fn guid_string_round_trip() {
+        let bb_guid = BasicBlockGUID::from([0x55, 0x48, 0x89, 0xe5, 0xc3].as_slice());
+        let bb_guid_str = GuidString(bb_guid).to_string();
+        assert_eq!(bb_guid_str, bb_guid_str.to_lowercase());
+        assert_eq!(bb_guid_str.parse().ok(), Some(GuidString(bb_guid)));
+
+        let func_guid = FunctionGUID::from_basic_blocks(&[bb_guid]);
+        let func_guid_str = GuidString(func_guid).to_string();
+        assert_eq!(func_guid_str, func_guid_str.to_lowercase());
+        assert_eq!(func_guid_str.parse().ok(), Some(GuidString(func_guid)));
+        // Surrounding whitespace, e.g. from pasting the GUID, is ignored.
+        assert_eq!(
+            format!(" {}\n", func_guid_str).parse().ok(),
+            Some(GuidString(func_guid))
+        );
+        assert!("not a guid".parse::<GuidString<FunctionGUID>>().is_err());
+    }
+
+    #[test]
+    fn byte_function_guid_thunk() {
+        let _session = session();
+        // jmp qword [rel 0x1000]
+        let thunk_bytes = [0xff, 0x25, 0xfa, 0x0f, 0x00, 0x00];
+        let (_view, function) = view_with_function(&thunk_bytes);
+
+        let byte_guid = byte_function_guid(&function).expect("Function has no basic blocks");
+        assert_eq!(byte_function_guid(&function), Some(byte_guid));
+        // Byte-only GUIDs must never collide with LLIL GUIDs.
+        if let Ok(llil) = function.low_level_il() {
+            assert_ne!(function_guid(&function, &llil), byte_guid);
+        }
+    }
+
+    #[test]
+    fn function_guid_hlil_instruction_selection() {
+        let session = session();
+        // The same functions built with different instruction selection, see `fixtures/hlil`.
+        let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap().join("hlil");
+        let guids_by_name = |file_name: &str| {
+            let view = session
+                .load(out_dir.join(file_name))
+                .expect("Failed to load view");
+            view.functions()
+                .iter()
+                .map(|f| {
+                    let llil = f.low_level_il().expect("Function has no LLIL");
+                    let hlil_guid = function_guid_hlil(&f).expect("Function has no HLIL");
+                    let name = f.symbol().raw_name().to_string();
+                    (name, (function_guid(&f, &llil), hlil_guid))
+                })
+                .collect::<HashMap<_, _>>()
+        };
+        let unoptimized = guids_by_name("instr_select_O0.o");
+        let optimized = guids_by_name("instr_select_O2.o");
+
+        for name in ["return_zero", "add_one"] {
+            let (unoptimized_llil, unoptimized_hlil) = unoptimized[name];
+            let (optimized_llil, optimized_hlil) = optimized[name];
+            // Instruction selection changes the LLIL GUID but not the HLIL GUID.
+            assert_ne!(unoptimized_llil, optimized_llil, "{}", name);
+            assert_eq!(unoptimized_hlil, optimized_hlil, "{}", name);
+            // HLIL GUIDs must never collide with LLIL GUIDs.
+            assert_ne!(optimized_hlil, optimized_llil, "{}", name);
+        }
+        assert_ne!(optimized["return_zero"].1, optimized["add_one"].1);
+    }
+
+    #[test]
+    fn signature_guid_level_stored() {
+        let _session = session();
+        // xor eax, eax; ret
+        let (_view, function) = view_with_function(&[0x31, 0xc0, 0xc3]);
+        let llil = function.low_level_il().expect("Function has no LLIL");
+        let llil_signature = build_function(&function, &llil, false);
+        assert_eq!(signature_guid_level(&llil_signature), GuidLevel::Llil);
+        let byte_signature = build_byte_function(&function).expect("Function has no blocks");
+        assert_eq!(signature_guid_level(&byte_signature), GuidLevel::Bytes);
+        let hlil_signature = build_hlil_function(&function).expect("Function has no HLIL");
+        assert_eq!(signature_guid_level(&hlil_signature), GuidLevel::Hlil);
+    }
+
+    #[test]
+    fn signature_dir_layout() {
+        #[cfg(target_os = "macos")]
+        {
+            let install_dir = Path::new("/Applications/Binary Ninja.app/Contents/MacOS");
+            assert_eq!(
+                root_core_signature_dir(install_dir),
+                Some(PathBuf::from(
+                    "/Applications/Binary Ninja.app/Contents/Resources/signatures"
+                ))
+            );
+        }
+        #[cfg(not(target_os = "macos"))]
+        {
+            let install_dir = Path::new("/opt/binaryninja");
+            assert_eq!(
+                root_core_signature_dir(install_dir),
+                Some(PathBuf::from("/opt/binaryninja/signatures"))
+            );
+        }
+        let user_dir = Path::new("/home/user/.binaryninja");
+        assert_eq!(
+            root_user_signature_dir(user_dir),
+            PathBuf::from("/home/user/.binaryninja/signatures/")
+        );
+    }
+
+    #[test]
+    fn signature_dir_entry_points() {
+        let _session = session();
+        // The public entry points must resolve through the same root as the matcher.
+        let install_dir = binaryninja::install_directory().expect("No install directory");
+        assert_eq!(core_signature_dir(), root_core_signature_dir(&install_dir));
+        let user_dir = binaryninja::user_directory().expect("No user directory");
+        assert_eq!(
+            user_signature_dir(),
+            Some(root_user_signature_dir(&user_dir))
+        );
+    }
+
+    #[test]
+    fn constraints_report() {
+        let bb_guid = BasicBlockGUID::from([0xc3].as_slice());
+        let callee_guid = FunctionGUID::from_basic_blocks(&[bb_guid]);
+        let constraints = FunctionConstraints {
+            adjacent: HashSet::from([FunctionConstraint {
+                guid: None,
+                symbol: None,
+                offset: -0x20,
+            }]),
+            call_sites: HashSet::from([FunctionConstraint {
+                guid: Some(callee_guid),
+                symbol: Some(Symbol::new(
+                    "memcpy".to_string(),
+                    SymbolClass::Function,
+                    SymbolModifiers::default(),
+                )),
+                offset: 0x10,
+            }]),
+            caller_sites: HashSet::new(),
+        };
+
+        let report = function_constraints_report(&constraints);
+        let expected = format!(
+            "Adjacent (1):\n    -0x20 * *\nCall sites (1):\n    +0x10 {} memcpy\nCaller sites (0):\n",
+            GuidString(callee_guid)
+        );
+        assert_eq!(report, expected);
+    }
+
+    #[test]
+    fn call_site_constraints_named() {
+        let _session = session();
+        let mut code = vec![0xc3; 0x40];
+        // call 0x20; call 0x30; call 0x20; ret
+        code[..0x10].copy_from_slice(&[
+            0xe8, 0x1b, 0x00, 0x00, 0x00, 0xe8, 0x26, 0x00, 0x00, 0x00, 0xe8, 0x11, 0x00, 0x00,
+            0x00, 0xc3,
+        ]);
+        let file = FileMetadata::new();
+        let view = BinaryView::from_data(&file, &code).expect("Failed to create view");
+        let platform = Platform::by_name("linux-x86_64").expect("Failed to get platform");
+        for (name, address) in [("memcpy", 0x20), ("memset", 0x30)] {
+            let symbol = SymbolBuilder::new(SymbolType::ImportedFunction, name, address).create();
+            view.define_user_symbol(&symbol);
+        }
+        let function = view
+            .create_user_function(&platform, 0)
+            .expect("Failed to create function");
+        view.update_analysis_and_wait();
+
+        let constraints = call_site_constraints(&function);
+        let call_sites = constraints
+            .iter()
+            .map(|c| (c.address, c.name.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            call_sites,
+            vec![
+                (0x0, Some("memcpy")),
+                (0x5, Some("memset")),
+                (0xa, Some("memcpy"))
+            ]
+        );
+    }
+
+    #[test]
+    fn function_type_confidence_user_vs_inferred() {
+        let _session = session();
+        // mov eax, 0x1; ret
+        let code = [0xb8, 0x01, 0x00, 0x00, 0x00, 0xc3];
+        let (view, function) = view_with_function(&code);
+
+        let return_confidence =
+            |function: &warp::signature::function::Function| match function.ty.class.as_ref() {
+                TypeClass::Function(class) => class.out_members[0].ty.confidence,
+                _ => panic!("Function type is not a function"),
+            };
+
+        // The return type is inferred by analysis, it must not be stored as certain.
+        let inferred = build_byte_function(&function).expect("Failed to build function");
+        assert!(inferred.ty.confidence < MAX_CONFIDENCE);
+        assert!(return_confidence(&inferred) < MAX_CONFIDENCE);
+
+        let int_ty = BNType::int(4, true);
+        function.set_user_return_type(Conf::new(int_ty.as_ref(), MAX_CONFIDENCE));
+        view.update_analysis_and_wait();
+        let user_set = build_byte_function(&function).expect("Failed to build function");
+        assert_eq!(return_confidence(&user_set), MAX_CONFIDENCE);
+    }
+
+    #[test]
+    fn function_guid_matches_basic_block_guids() {
+        let _session = session();
+        // test edi, edi; je 0xa; mov eax, 0x1; ret; xor eax, eax; ret
+        let code = [
+            0x85, 0xff, 0x74, 0x06, 0xb8, 0x01, 0x00, 0x00, 0x00, 0xc3, 0x31, 0xc0, 0xc3,
+        ];
+        let (_view, function) = view_with_function(&code);
+
+        // Reading the function at once must produce the same GUID as reading each basic block.
+        let llil = function.low_level_il().expect("Function has no LLIL");
+        let basic_blocks = sorted_basic_blocks(&function);
+        assert!(basic_blocks.len() > 1);
+        let basic_block_guids = basic_blocks
+            .iter()
+            .map(|bb| basic_block_guid(bb, &llil))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            function_guid(&function, &llil),
+            FunctionGUID::from_basic_blocks(&basic_block_guids)
+        );
+        // The last basic block has nothing to mask, it is the same as hashing its bytes.
+        assert_eq!(
+            basic_block_guids.last(),
+            Some(&basic_block_guid_from_bytes(&[0x31, 0xc0, 0xc3]))
+        );
+    }
+
+    #[test]
+    fn nop_blacklist_toggle() {
+        let _session = session();
+        // nop; xor eax, eax; ret
+        let code = [0x90, 0x31, 0xc0, 0xc3];
+        let (_view, function) = view_with_function(&code);
+        let llil = function.low_level_il().expect("Function has no LLIL");
+
+        let blacklisted = GuidOptions::default();
+        let not_blacklisted = GuidOptions {
+            blacklist_nops: false,
+        };
+        let blacklisted_guid = function_guid_with_options(&function, &llil, &blacklisted);
+        let not_blacklisted_guid = function_guid_with_options(&function, &llil, &not_blacklisted);
+        assert_eq!(blacklisted_guid, function_guid(&function, &llil));
+        assert_ne!(blacklisted_guid, not_blacklisted_guid);
+        // With the blacklist the NOP is left out, without it the NOP is hashed like any other instruction.
+        assert_eq!(
+            blacklisted_guid,
+            FunctionGUID::from_basic_blocks(&[basic_block_guid_from_bytes(&[0x31, 0xc0, 0xc3])])
+        );
+        assert_eq!(
+            not_blacklisted_guid,
+            FunctionGUID::from_basic_blocks(&[basic_block_guid_from_bytes(&code)])
+        );
+    }
+
+    #[test]
+    fn max_function_size() {
+        let _session = session();
+        // xor eax, eax; ret
+        let code = [0x31, 0xc0, 0xc3];
+        let (_view, function) = view_with_function(&code);
+
+        // Unlimited by default.
+        assert!(!GenerationSettings::default().is_function_too_large(&function));
+        let small = GenerationSettings {
+            max_function_size: Some(1),
+            ..Default::default()
+        };
+        assert!(small.is_function_too_large(&function));
+        let large = GenerationSettings {
+            max_function_size: Some(0x100),
+            ..Default::default()
+        };
+        assert!(!large.is_function_too_large(&function));
+    }
+
+    #[test]
+    fn variant_instructions() {
+        let _session = session();
+        // mov eax, 0x10; ret
+        let mut code = vec![0xb8, 0x10, 0x00, 0x00, 0x00, 0xc3];
+        code.resize(0x20, 0);
+        let file = FileMetadata::new();
+        let view = BinaryView::from_data(&file, &code).expect("Failed to create view");
+        view.add_section(Section::builder(".data", 0x10..0x20));
+        let platform = Platform::by_name("linux-x86_64").expect("Failed to get platform");
+        let function = view
+            .create_user_function(&platform, 0)
+            .expect("Failed to create function");
+        view.update_analysis_and_wait();
+
+        let llil = function.low_level_il().expect("Function has no LLIL");
+        // The constant is within a section, it is relocatable.
+        let mov = llil.instruction_at(0u64).expect("No instruction at 0x0");
+        assert!(instruction_is_variant(&view, &mov));
+        let ret = llil.instruction_at(5u64).expect("No instruction at 0x5");
+        assert!(!instruction_is_variant(&view, &ret));
+    }
+
+    #[test]
+    fn named_functions_check() {
+        let _session = session();
+        let file = FileMetadata::new();
+        // ret
+        let view = BinaryView::from_data(&file, &[0xc3]).expect("Failed to create view");
+        assert_eq!(
+            check_named_functions(&view),
+            Err(MissingNamedFunctions::NoFunctions)
+        );
+
+        let platform = Platform::by_name("linux-x86_64").expect("Failed to get platform");
+        view.create_user_function(&platform, 0)
+            .expect("Failed to create function");
+        view.update_analysis_and_wait();
+        assert_eq!(
+            check_named_functions(&view),
+            Err(MissingNamedFunctions::NoNamedFunctions(1))
+        );
+    }
+
+    #[test]
+    fn included_symbol_types_setting() {
+        let _session = session();
+        assert!(is_auto_generated_name("sub_401000"));
+        assert!(is_auto_generated_name("j_sub_401000"));
+        // Only auto-generated function names count, `data_` names are regular names.
+        assert!(!is_auto_generated_name("data_401000"));
+        // Only the prefix counts, names containing `sub_` are regular names.
+        assert!(!is_auto_generated_name("my_sub_init"));
+        assert!(!is_auto_generated_name("sub_"));
+
+        // ret; ret
+        let file = FileMetadata::new();
+        let view = BinaryView::from_data(&file, &[0xc3, 0xc3]).expect("Failed to create view");
+        let platform = Platform::by_name("linux-x86_64").expect("Failed to get platform");
+        let unnamed = view
+            .create_user_function(&platform, 0)
+            .expect("Failed to create function");
+        // Auto symbols, user symbols would count as user annotations.
+        view.define_auto_symbol(
+            &SymbolBuilder::new(SymbolType::LibraryFunction, "memcpy", 1).create(),
+        );
+        let library = view
+            .create_user_function(&platform, 1)
+            .expect("Failed to create function");
+        view.update_analysis_and_wait();
+
+        let default = GenerationSettings::default();
+        assert_eq!(
+            default.include_symbol_types.keys(),
+            GenerationSettings::INCLUDE_SYMBOL_TYPES_DEFAULT
+        );
+        assert!(!should_signature_function(&unnamed, false, &default));
+        assert!(should_signature_function(&library, false, &default));
+
+        // Include auto-generated names and exclude library functions.
+        let toggled = GenerationSettings {
+            include_symbol_types: IncludedSymbolTypes::from_keys(&["function", "autoNamed"]),
+            ..Default::default()
+        };
+        assert!(is_function_named(&unnamed, &toggled.include_symbol_types));
+        assert!(should_signature_function(&unnamed, false, &toggled));
+        assert!(!should_signature_function(&library, false, &toggled));
+        // Unnamed functions are still included on request.
+        assert!(should_signature_function(&library, true, &toggled));
+    }
+
+    #[test]
+    fn adjacency_omitted_mid_analysis() {
+        let _session = session();
+        // xor eax, eax; ret; mov eax, 0x1; ret
+        let code = [0x31, 0xc0, 0xc3, 0xb8, 0x01, 0x00, 0x00, 0x00, 0xc3];
+        let (view, [function, _]) = view_with_functions(&code, [0, 3]);
+        let llil = function.low_level_il().expect("Failed to get LLIL");
+        assert!(is_analysis_complete(&view));
+        assert!(!build_function(&function, &llil, false)
+            .constraints
+            .adjacent
+            .is_empty());
+
+        // Hold the requested update so that analysis is left incomplete.
+        view.set_analysis_hold(true);
+        view.update_analysis();
+        assert!(!is_analysis_complete(&view));
+        let signature = build_function(&function, &llil, false);
+        assert!(signature.constraints.adjacent.is_empty());
+        // Callers that know analysis is done can opt out of the check.
+        let signature = build_function(&function, &llil, true);
+        assert!(!signature.constraints.adjacent.is_empty());
+        view.set_analysis_hold(false);
+    }
+
+    #[test]
+    fn signature_file_reading() {
+        let dir = std::env::temp_dir().join(format!("warp_sig_read_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sig_path = dir.join("libc.sbin");
+        std::fs::write(&sig_path, Data::default().to_bytes()).unwrap();
+        let corrupt_path = dir.join("corrupt.sbin");
+        std::fs::write(&corrupt_path, b"not a signature file").unwrap();
+
+        let data = read_signature_file(&sig_path).expect("Failed to read signature file");
+        assert!(data.is_some_and(|data| data.functions.is_empty()));
+        assert!(read_signature_file(&corrupt_path).unwrap().is_none());
+        assert!(read_signature_file(&dir.join("missing.sbin")).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn skipped_functions_not_signatured() {
+        let _session = session();
+        // xor eax, eax; ret; mov eax, 0x1; ret
+        let code = [0x31, 0xc0, 0xc3, 0xb8, 0x01, 0x00, 0x00, 0x00, 0xc3];
+        let file = FileMetadata::new();
+        let view = BinaryView::from_data(&file, &code).expect("Failed to create view");
+        let platform = Platform::by_name("linux-x86_64").expect("Failed to get platform");
+        let function = view
+            .create_user_function(&platform, 0)
+            .expect("Failed to create function");
+        let skipped_function = view
+            .create_user_function(&platform, 3)
+            .expect("Failed to create function");
+        // Skip the function as analysis would for a function above the size limit.
+        skipped_function.set_analysis_skipped(true);
+        view.update_analysis_and_wait();
+
+        assert!(skipped_function.analysis_skipped());
+        // Skipped functions are excluded even when unnamed functions are included.
+        let settings = GenerationSettings::default();
+        assert!(should_signature_function(&function, true, &settings));
+        assert!(!should_signature_function(
+            &skipped_function,
+            true,
+            &settings
+        ));
+        assert!(!should_signature_function(
+            &skipped_function,
+            false,
+            &settings
+        ));
+    }
 }
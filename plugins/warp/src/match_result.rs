@@ -0,0 +1,385 @@
+use binaryninja::binary_view::{BinaryView, BinaryViewExt};
+use binaryninja::confidence::MAX_CONFIDENCE;
+use binaryninja::function::Function as BNFunction;
+use binaryninja::metadata::Metadata;
+use binaryninja::rc::Ref as BNRef;
+use binaryninja::types::Type as BNType;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use warp::signature::function::FunctionGUID;
+
+use crate::cache::matched_addresses;
+use crate::matcher::MatcherSettings;
+use crate::GuidString;
+
+/// Prefix of the view metadata key the match result for a function is stored under.
+///
+/// The full key is the prefix followed by the function address, e.g. `warp.matched.0x401000`.
+pub const MATCH_RESULT_METADATA_PREFIX: &str = "warp.matched";
+
+/// How a function was matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MatchKind {
+    /// The function GUID was unique amongst the loaded signatures.
+    Guid,
+    /// The function GUID was shared with other functions (or the function is trivial) and the
+    /// constraints were used to pick the matched function.
+    Constraints,
+}
+
+impl MatchKind {
+    /// The confidence given to a match of this kind.
+    pub fn confidence(&self) -> u8 {
+        match self {
+            MatchKind::Guid => MAX_CONFIDENCE,
+            MatchKind::Constraints => MAX_CONFIDENCE / 2,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            MatchKind::Guid => "guid",
+            MatchKind::Constraints => "constraints",
+        }
+    }
+
+    fn from_name(kind: &str) -> Option<Self> {
+        match kind {
+            "guid" => Some(MatchKind::Guid),
+            "constraints" => Some(MatchKind::Constraints),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for MatchKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The kind of function GUID a function was matched with, see `Matcher::match_with_levels`.
+///
+/// Signatures of every level can be in the same signature file, each level derives its GUIDs in a
+/// separate namespace so they never collide. The level is also stored in the signature, see
+/// [`crate::signature_guid_level`], so only GUIDs of the same level are compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GuidLevel {
+    /// The LLIL GUID, see [`crate::function_guid`].
+    Llil,
+    /// The byte-only GUID, see [`crate::byte_function_guid`].
+    Bytes,
+    /// The experimental HLIL GUID, see [`crate::function_guid_hlil`].
+    Hlil,
+}
+
+impl GuidLevel {
+    fn as_str(&self) -> &'static str {
+        match self {
+            GuidLevel::Llil => "llil",
+            GuidLevel::Bytes => "bytes",
+            GuidLevel::Hlil => "hlil",
+        }
+    }
+
+    pub(crate) fn from_name(level: &str) -> Option<Self> {
+        match level {
+            "llil" => Some(GuidLevel::Llil),
+            "bytes" => Some(GuidLevel::Bytes),
+            "hlil" => Some(GuidLevel::Hlil),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for GuidLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The provenance of a matched function, stored in the view metadata so that it survives a save.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchResult {
+    /// The symbol name of the matched function.
+    pub name: String,
+    /// The GUID of the matched function.
+    pub guid: FunctionGUID,
+    /// The signature file the matched function was loaded from, if known.
+    pub source: Option<PathBuf>,
+    pub kind: MatchKind,
+    pub confidence: u8,
+    /// The GUID level the function was matched at, `None` if matched with the GUID generated by
+    /// the GUID activity, see `Matcher::match_with_levels`.
+    pub level: Option<GuidLevel>,
+}
+
+impl MatchResult {
+    fn metadata_key(address: u64) -> String {
+        format!("{}.{:#x}", MATCH_RESULT_METADATA_PREFIX, address)
+    }
+
+    fn to_metadata(&self) -> BNRef<Metadata> {
+        let mut values: HashMap<&str, BNRef<Metadata>> = HashMap::new();
+        values.insert("name", self.name.as_str().into());
+        values.insert("guid", GuidString(self.guid).to_string().into());
+        values.insert("kind", self.kind.as_str().into());
+        values.insert("confidence", (self.confidence as u64).into());
+        if let Some(source) = &self.source {
+            values.insert("source", source.to_string_lossy().to_string().into());
+        }
+        if let Some(level) = &self.level {
+            values.insert("level", level.as_str().into());
+        }
+        values.into()
+    }
+
+    fn from_metadata(metadata: &Metadata) -> Option<Self> {
+        let values = HashMap::<String, BNRef<Metadata>>::try_from(metadata).ok()?;
+        let get_string = |key: &str| String::try_from(values.get(key)?.as_ref()).ok();
+        Some(Self {
+            name: get_string("name")?,
+            guid: get_string("guid")?
+                .parse::<GuidString<FunctionGUID>>()
+                .ok()?
+                .0,
+            source: get_string("source").map(PathBuf::from),
+            kind: MatchKind::from_name(&get_string("kind")?)?,
+            confidence: u64::try_from(values.get("confidence")?.as_ref()).ok()? as u8,
+            level: get_string("level").and_then(|level| GuidLevel::from_name(&level)),
+        })
+    }
+}
+
+/// Store the match result for the function at `address`, replacing any previous result.
+pub fn store_match_result(view: &BinaryView, address: u64, result: &MatchResult) {
+    view.store_metadata(
+        MatchResult::metadata_key(address),
+        result.to_metadata(),
+        false,
+    );
+}
+
+/// Get the match result for the function at `address`, `None` if the function was never matched.
+pub fn match_result_for(view: &BinaryView, address: u64) -> Option<MatchResult> {
+    let metadata = view.query_metadata(MatchResult::metadata_key(address))?;
+    MatchResult::from_metadata(&metadata)
+}
+
+/// WARP extensions to [`BinaryView`].
+pub trait WarpBinaryViewExt: BinaryViewExt {
+    /// Every function WARP matched in the view this session, sorted by address.
+    ///
+    /// Unlike [`match_result_for`] this is not persisted, functions matched before the view was
+    /// saved are only listed once they are matched again. Functions that no longer exist are skipped.
+    fn warp_matched_functions(&self) -> Vec<BNRef<BNFunction>> {
+        let view = self.as_ref();
+        matched_addresses(view)
+            .into_iter()
+            .flat_map(|address| {
+                view.functions_at(address)
+                    .iter()
+                    .map(|function| function.to_owned())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+impl WarpBinaryViewExt for BinaryView {}
+
+/// Whether the match should be applied to the function, so that matching never overrides prior work.
+///
+/// A match is not applied over a previous match with a higher confidence, nor, with
+/// `preserve_user_symbols`, over a symbol the user defined. Symbols defined by WARP for a previous
+/// match are not considered user defined, even when they were applied as user symbols.
+pub fn should_apply_match(
+    function: &BNFunction,
+    result: &MatchResult,
+    preserve_user_symbols: bool,
+) -> bool {
+    let view = function.view();
+    let previous = match_result_for(&view, function.start());
+    if previous
+        .as_ref()
+        .is_some_and(|previous| previous.confidence > result.confidence)
+    {
+        return false;
+    }
+    let symbol = function.symbol();
+    let is_user_symbol = !symbol.auto_defined()
+        && previous.is_none_or(|previous| previous.name != symbol.raw_name().to_string());
+    !(preserve_user_symbols && is_user_symbol)
+}
+
+/// Apply the calling convention and no-return status of the matched function type `ty`.
+///
+/// Each is applied only when enabled in `settings` and the matched one has a higher confidence than
+/// the one the function has. User set attributes have the maximum confidence, so they are never
+/// overridden.
+pub fn apply_function_attributes(function: &BNFunction, ty: &BNType, settings: &MatcherSettings) {
+    if settings.apply_calling_convention {
+        if let Some(calling_convention) = ty.calling_convention() {
+            let current_confidence = function
+                .calling_convention()
+                .map(|current| current.confidence)
+                .unwrap_or_default();
+            if calling_convention.confidence > current_confidence {
+                function.set_auto_calling_convention(Some(&calling_convention));
+            }
+        }
+    }
+    if settings.apply_no_return {
+        let can_return = ty.can_return();
+        if can_return.confidence > function.can_return().confidence {
+            function.set_auto_can_return(can_return);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::Matcher;
+    use crate::test::{session, view_with_function, view_with_functions};
+    use binaryninja::file_metadata::FileMetadata;
+    use binaryninja::platform::Platform;
+    use binaryninja::symbol::{Symbol as BNSymbol, SymbolType};
+    use warp::r#type::class::TypeClass;
+    use warp::signature::basic_block::BasicBlockGUID;
+    use warp::signature::Data;
+    use warp::symbol::class::SymbolClass;
+    use warp::symbol::{Symbol, SymbolModifiers};
+
+    #[test]
+    fn match_result_round_trip() {
+        let _session = session();
+        let file = FileMetadata::new();
+        let view = BinaryView::from_data(&file, &[0; 0x10]).expect("Failed to create view");
+        assert_eq!(match_result_for(&view, 0x0), None);
+
+        let bb_guid = BasicBlockGUID::from([0xc3].as_slice());
+        let result = MatchResult {
+            name: "memcpy".to_string(),
+            guid: FunctionGUID::from_basic_blocks(&[bb_guid]),
+            source: Some(PathBuf::from("libc.sbin")),
+            kind: MatchKind::Constraints,
+            confidence: MatchKind::Constraints.confidence(),
+            level: Some(GuidLevel::Hlil),
+        };
+        store_match_result(&view, 0x0, &result);
+        assert_eq!(match_result_for(&view, 0x0), Some(result));
+        assert_eq!(match_result_for(&view, 0x8), None);
+    }
+
+    #[test]
+    fn user_symbol_survives_match() {
+        let _session = session();
+        // xor eax, eax; ret
+        let (view, function) = view_with_function(&[0x31, 0xc0, 0xc3]);
+
+        let bb_guid = BasicBlockGUID::from([0x31, 0xc0, 0xc3].as_slice());
+        let result = MatchResult {
+            name: "return_zero".to_string(),
+            guid: FunctionGUID::from_basic_blocks(&[bb_guid]),
+            source: None,
+            kind: MatchKind::Guid,
+            confidence: MatchKind::Guid.confidence(),
+            level: None,
+        };
+        // An auto symbol is always replaced.
+        assert!(should_apply_match(&function, &result, true));
+
+        let user_symbol = BNSymbol::builder(SymbolType::Function, "my_function", 0).create();
+        view.define_user_symbol(&user_symbol);
+        assert!(!should_apply_match(&function, &result, true));
+        assert!(should_apply_match(&function, &result, false));
+
+        // The symbol defined by a previous match is not a user edit.
+        view.undefine_user_symbol(&user_symbol);
+        let warp_symbol = BNSymbol::builder(SymbolType::Function, "return_zero", 0).create();
+        view.define_user_symbol(&warp_symbol);
+        store_match_result(&view, 0, &result);
+        assert!(should_apply_match(&function, &result, true));
+
+        // A lower confidence match never replaces a higher confidence match.
+        let constraint_result = MatchResult {
+            kind: MatchKind::Constraints,
+            confidence: MatchKind::Constraints.confidence(),
+            ..result.clone()
+        };
+        assert!(!should_apply_match(&function, &constraint_result, false));
+    }
+
+    #[test]
+    fn no_return_applied() {
+        let _session = session();
+        // xor eax, eax; ret
+        let code = [0x31, 0xc0, 0xc3];
+        let platform = Platform::by_name("linux-x86_64").expect("Failed to get platform");
+        let signature_view =
+            BinaryView::from_data(&FileMetadata::new(), &code).expect("Failed to create view");
+        let signature_function = signature_view
+            .create_user_function(&platform, 0)
+            .expect("Failed to create function");
+        signature_function.set_user_can_return(false);
+        signature_view.update_analysis_and_wait();
+        let llil = signature_function
+            .low_level_il()
+            .expect("Function has no LLIL");
+        let mut signature = crate::build_function(&signature_function, &llil, false);
+        // The no-return status is carried next to the type, the return type is kept.
+        assert!(crate::is_no_return(&signature));
+        assert!(matches!(
+            signature.ty.class.as_ref(),
+            TypeClass::Function(class) if !class.out_members.is_empty()
+        ));
+        signature.symbol = Symbol::new(
+            "exit_stub".to_string(),
+            SymbolClass::Function,
+            SymbolModifiers::default(),
+        );
+
+        let (view, function) = view_with_function(&code);
+        assert!(function.can_return().contents);
+
+        let mut data = Data::default();
+        data.functions.push(signature);
+        let mut matcher = Matcher::from_data(data);
+        // The function is trivial, it would otherwise have to match on its constraints.
+        matcher.settings.trivial_function_len = 0;
+        matcher.match_function(&function);
+        assert!(match_result_for(&view, 0).is_some());
+
+        // The no-return status is carried through the signature with the confidence of the match.
+        let can_return = function.can_return();
+        assert!(!can_return.contents);
+        assert!(can_return.confidence > 0);
+    }
+
+    #[test]
+    fn matched_functions_cleared_on_invalidate() {
+        let _session = session();
+        // xor eax, eax; ret; ret
+        let (view, _functions) = view_with_functions(&[0x31, 0xc0, 0xc3, 0xc3], [0, 3]);
+        let (other_view, _function) = view_with_function(&[0x31, 0xc0, 0xc3]);
+        assert!(view.warp_matched_functions().is_empty());
+
+        crate::cache::record_matched_address(&view, 3);
+        crate::cache::record_matched_address(&view, 0);
+        // Not a function, skipped.
+        crate::cache::record_matched_address(&view, 1);
+        crate::cache::record_matched_address(&other_view, 0);
+        let matched: Vec<u64> = view
+            .warp_matched_functions()
+            .iter()
+            .map(|function| function.start())
+            .collect();
+        assert_eq!(matched, vec![0, 3]);
+
+        // Only the view is invalidated, the global invalidation would race with the other tests.
+        crate::cache::invalidate_view_guids(&view);
+        assert!(view.warp_matched_functions().is_empty());
+        assert_eq!(other_view.warp_matched_functions().len(), 1);
+    }
+}
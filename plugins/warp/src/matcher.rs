@@ -1,16 +1,22 @@
 use binaryninja::architecture::Architecture as BNArchitecture;
 use binaryninja::binary_view::{BinaryView, BinaryViewExt};
+use binaryninja::confidence::MAX_CONFIDENCE;
 use binaryninja::function::Function as BNFunction;
 use binaryninja::platform::Platform;
 use binaryninja::rc::Guard;
 use binaryninja::rc::Ref as BNRef;
 use dashmap::DashMap;
+use rayon::prelude::*;
 use serde_json::json;
+use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::hash::{DefaultHasher, Hash, Hasher};
-use std::path::PathBuf;
-use std::sync::OnceLock;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::{Arc, OnceLock};
+use std::time::SystemTime;
 use walkdir::{DirEntry, WalkDir};
 use warp::r#type::class::TypeClass;
 use warp::r#type::guid::TypeGUID;
@@ -19,26 +25,91 @@ use warp::signature::function::{Function, FunctionGUID};
 use warp::signature::Data;
 
 use crate::cache::{
-    cached_adjacency_constraints, cached_call_site_constraints, cached_function_match,
-    try_cached_function_guid,
+    cached_adjacency_constraints, cached_call_site_constraints, cached_function_guid,
+    cached_function_match, invalidate_matched_addresses, try_cached_function_guid,
 };
 use crate::convert::to_bn_type;
-use crate::plugin::on_matched_function;
-use crate::{core_signature_dir, user_signature_dir};
+use crate::match_result::{GuidLevel, MatchKind, MatchResult};
+use crate::plugin::{on_matched_function, APPLY_MATCHED_LOCK};
+use crate::{
+    byte_function_guid, core_signature_dir, function_guid_hlil, read_signature_file,
+    signature_guid_level, user_signature_dir,
+};
+
+/// The matcher of each platform, see [`with_platform_matcher`].
+///
+/// Each matcher is behind its own cell so that it can be built without holding the map lock.
+pub static PLAT_MATCHER_CACHE: OnceLock<DashMap<PlatformID, Arc<OnceLock<Matcher>>>> =
+    OnceLock::new();
+
+/// The [`PlatformID`] of each platform, keyed by the platform handle.
+///
+/// Retrieving and hashing the platform name for every matched function adds up on large views.
+/// The cache holds a reference to the platform, so the handle is never reused by another platform.
+static PLATFORM_ID_CACHE: OnceLock<DashMap<BNRef<Platform>, PlatformID>> = OnceLock::new();
 
-pub static PLAT_MATCHER_CACHE: OnceLock<DashMap<PlatformID, Matcher>> = OnceLock::new();
+/// The [`PlatformID`] of the platform, only computed the first time the platform is seen.
+fn cached_platform_id(platform: &BNRef<Platform>) -> PlatformID {
+    let platform_id_cache = PLATFORM_ID_CACHE.get_or_init(Default::default);
+    if let Some(platform_id) = platform_id_cache.get(platform) {
+        return *platform_id;
+    }
+    *platform_id_cache
+        .entry(platform.to_owned())
+        .or_insert_with(|| PlatformID::from(platform.as_ref()))
+}
 
 pub fn cached_function_matcher(function: &BNFunction) {
-    let platform = function.platform();
-    let platform_id = PlatformID::from(platform.as_ref());
+    with_platform_matcher(&function.platform(), |matcher| {
+        matcher.match_function(function)
+    });
+}
+
+/// Call `f` with the matcher of the platform, the matcher is built the first time the platform is seen.
+///
+/// Functions are matched in parallel, only the threads matching functions of the platform being
+/// built wait for it, the map lock is only held to insert the empty cell.
+///
+/// NOTE: Callers matching in parallel must build the matchers first, see [`build_view_platform_matchers`].
+/// Building a matcher merges the signature data, which may use rayon itself. A rayon worker waiting on
+/// the cell can then steal a job that waits on the same cell, and never wake up.
+pub fn with_platform_matcher<T>(platform: &BNRef<Platform>, f: impl FnOnce(&Matcher) -> T) -> T {
+    let platform_id = cached_platform_id(platform);
     let matcher_cache = PLAT_MATCHER_CACHE.get_or_init(Default::default);
-    match matcher_cache.get(&platform_id) {
-        Some(matcher) => matcher.match_function(function),
-        None => {
-            let matcher = Matcher::from_platform(platform);
-            matcher.match_function(function);
-            matcher_cache.insert(platform_id, matcher);
+    let cell = match matcher_cache.get(&platform_id) {
+        Some(cell) => cell.clone(),
+        None => matcher_cache.entry(platform_id).or_default().clone(),
+    };
+    f(cell.get_or_init(|| Matcher::from_platform(platform.to_owned())))
+}
+
+/// Build the matchers of the platforms of every function in the view on the calling thread.
+///
+/// Call this before matching the functions in parallel, see [`with_platform_matcher`].
+pub fn build_view_platform_matchers(view: &BinaryView) {
+    let mut platforms = HashMap::new();
+    for function in view.functions().iter() {
+        let platform = function.platform();
+        platforms
+            .entry(cached_platform_id(&platform))
+            .or_insert(platform);
+    }
+    for platform in platforms.values() {
+        with_platform_matcher(platform, |_| ());
+    }
+}
+
+/// The GUID of the function at the given level, `None` if the function has no GUID at that level.
+///
+/// NOTE: The byte-only GUID is not taken from the GUID cache, as that holds the LLIL GUID if there is one.
+fn level_function_guid(function: &BNFunction, level: GuidLevel) -> Option<FunctionGUID> {
+    match level {
+        GuidLevel::Llil => {
+            let llil = function.low_level_il().ok()?;
+            Some(cached_function_guid(function, &llil))
         }
+        GuidLevel::Bytes => byte_function_guid(function),
+        GuidLevel::Hlil => function_guid_hlil(function),
     }
 }
 
@@ -46,6 +117,31 @@ pub fn cached_function_matcher(function: &BNFunction) {
 pub fn invalidate_function_matcher_cache() {
     let matcher_cache = PLAT_MATCHER_CACHE.get_or_init(Default::default);
     matcher_cache.clear();
+    // The matched functions are from the old matchers, they are recorded again when re-matched.
+    invalidate_matched_addresses();
+}
+
+/// The number of types defined by [`Matcher::add_type_to_view`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AddedTypes {
+    pub applied: usize,
+    pub failed: usize,
+}
+
+impl std::ops::AddAssign for AddedTypes {
+    fn add_assign(&mut self, rhs: Self) {
+        self.applied += rhs.applied;
+        self.failed += rhs.failed;
+    }
+}
+
+/// Where a signature was loaded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignatureOrigin {
+    /// The signatures shipped with Binary Ninja, see [`core_signature_dir`].
+    Core,
+    /// The signatures created by the user, see [`user_signature_dir`].
+    User,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -56,31 +152,106 @@ pub struct Matcher {
     pub functions: DashMap<FunctionGUID, Vec<Function>>,
     pub types: DashMap<TypeGUID, Type>,
     pub named_types: DashMap<String, Type>,
+    /// The signature file each function was loaded from, keyed by the function GUID and symbol name.
+    pub sources: DashMap<(FunctionGUID, String), PathBuf>,
+    /// Whether each function is a core or user signature, keyed by the function GUID and symbol name.
+    ///
+    /// A function in both the core and user signatures is considered a user signature.
+    pub origins: DashMap<(FunctionGUID, String), SignatureOrigin>,
 }
 
 impl Matcher {
     /// Create a matcher from the platforms signature subdirectory.
     pub fn from_platform(platform: BNRef<Platform>) -> Self {
         let platform_name = platform.name().to_string();
+        let settings = MatcherSettings::global();
+        let [core_dir, user_dir] = signature_dirs();
+        Self::from_sig_dirs(
+            core_dir,
+            user_dir,
+            &platform_name,
+            settings.maximum_signature_file_size,
+            &settings.signature_blacklist,
+        )
+    }
 
+    /// Create a matcher from the platform subdirectory of the core and user signature directories.
+    fn from_sig_dirs(
+        core_dir: Option<PathBuf>,
+        user_dir: Option<PathBuf>,
+        platform_name: &str,
+        max_file_size: Option<u64>,
+        blacklist: &[PathBuf],
+    ) -> Self {
         // Get core and user signatures.
         // TODO: Separate each file into own bucket for filtering?
-        let plat_core_sig_dir = core_signature_dir().join(&platform_name);
-        let mut data = get_data_from_dir(&plat_core_sig_dir);
-        let plat_user_sig_dir = user_signature_dir().join(&platform_name);
-        let user_data = get_data_from_dir(&plat_user_sig_dir);
+        let core_data =
+            get_data_from_sig_dirs(&[core_dir], platform_name, max_file_size, blacklist);
+        let user_data =
+            get_data_from_sig_dirs(&[user_dir], platform_name, max_file_size, blacklist);
+        Self::from_file_data(core_data, user_data)
+    }
+
+    /// Create a matcher from exactly the given signature files, regardless of the platform.
+    ///
+    /// The files are considered user signatures, see [`SignatureOrigin`]. Files which fail to read
+    /// are logged and skipped, the maximum signature file size does not apply.
+    pub fn from_files(paths: &[PathBuf]) -> Self {
+        let user_data = paths
+            .iter()
+            .filter_map(|path| Some((path.to_owned(), read_data_file(path, None)?)))
+            .collect();
+        Self::from_file_data(HashMap::new(), user_data)
+    }
 
-        data.extend(user_data);
-        let merged_data = Data::merge(data.values().cloned().collect::<Vec<_>>());
-        log::debug!("Loaded signatures: {:?}", data.keys());
-        Matcher::from_data(merged_data)
+    /// Create a matcher from the data of the core and user signature files, keyed by their path.
+    fn from_file_data(
+        core_data: HashMap<PathBuf, Data>,
+        user_data: HashMap<PathBuf, Data>,
+    ) -> Self {
+        let sources = DashMap::new();
+        let origins = DashMap::new();
+        // User signatures are last so that they are the origin of functions in both.
+        let tagged_data = core_data
+            .iter()
+            .map(|entry| (entry, SignatureOrigin::Core))
+            .chain(user_data.iter().map(|entry| (entry, SignatureOrigin::User)));
+        for ((path, file_data), origin) in tagged_data {
+            for func in &file_data.functions {
+                let key = (func.guid, func.symbol.name.to_owned());
+                sources.insert(key.clone(), path.to_owned());
+                origins.insert(key, origin);
+            }
+        }
+        let merged_data = Data::merge(
+            core_data
+                .values()
+                .chain(user_data.values())
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
+        log::debug!(
+            "Loaded signatures: {:?}",
+            core_data.keys().chain(user_data.keys()).collect::<Vec<_>>()
+        );
+        let mut matcher = Matcher::from_data(merged_data);
+        matcher.sources = sources;
+        matcher.origins = origins;
+        matcher
     }
 
     pub fn from_data(data: Data) -> Self {
         let functions = data.functions.into_iter().fold(
             DashMap::new(),
             |map: DashMap<FunctionGUID, Vec<_>>, func| {
-                map.entry(func.guid).or_default().push(func);
+                let mut bucket = map.entry(func.guid).or_default();
+                // The same function can come from multiple signature files, only keep it once so
+                // that it is not treated as a tie with itself, see [`Matcher::match_function`].
+                // Functions are compared as a whole, overloads with the same name but a different
+                // type (e.g. C++ functions with demangled names) are different functions.
+                if !bucket.contains(&func) {
+                    bucket.push(func);
+                }
                 map
             },
         );
@@ -101,23 +272,109 @@ impl Matcher {
             functions,
             types,
             named_types,
+            sources: DashMap::new(),
+            origins: DashMap::new(),
         }
     }
 
-    pub fn extend_with_matcher(&mut self, matcher: Matcher) {
-        self.functions.extend(matcher.functions);
-        self.types.extend(matcher.types);
-        self.named_types.extend(matcher.named_types);
+    pub fn extend_with_matcher(&self, matcher: Matcher) {
+        // Inserted one at a time so that a shared platform matcher can be extended.
+        for (guid, functions) in matcher.functions {
+            self.functions.insert(guid, functions);
+        }
+        for (guid, ty) in matcher.types {
+            self.types.insert(guid, ty);
+        }
+        for (name, ty) in matcher.named_types {
+            self.named_types.insert(name, ty);
+        }
+        for (key, source) in matcher.sources {
+            self.sources.insert(key, source);
+        }
+        for (key, origin) in matcher.origins {
+            self.origins.insert(key, origin);
+        }
     }
 
-    pub fn add_type_to_view<A: BNArchitecture>(&self, view: &BinaryView, arch: &A, ty: &Type) {
+    /// The signature file the function was loaded from, `None` if the matcher was not created from files.
+    pub fn source_of(&self, function: &Function) -> Option<PathBuf> {
+        self.sources
+            .get(&(function.guid, function.symbol.name.to_owned()))
+            .map(|source| source.value().to_owned())
+    }
+
+    /// Whether the function is a core or user signature, `None` if the matcher was not created from files.
+    pub fn origin_of(&self, function: &Function) -> Option<SignatureOrigin> {
+        self.origins
+            .get(&(function.guid, function.symbol.name.to_owned()))
+            .map(|origin| *origin.value())
+    }
+
+    /// Pick a function from candidates which matched equally well, `None` if there is no clear pick.
+    ///
+    /// With [`MatcherSettings::prefer_user_signatures`] a single user signature amongst the
+    /// candidates wins over the core signatures.
+    fn resolve_tie<'a>(&self, candidates: &[&'a Function]) -> Option<&'a Function> {
+        match candidates {
+            [matched] => Some(*matched),
+            _ if self.settings.prefer_user_signatures => {
+                let mut user_candidates = candidates
+                    .iter()
+                    .filter(|f| self.origin_of(f) == Some(SignatureOrigin::User));
+                match (user_candidates.next(), user_candidates.next()) {
+                    (Some(matched), None) => Some(*matched),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Add the type and the types it references to the view, returning how many were defined.
+    ///
+    /// Types which fail to be defined (e.g. the name collides with an existing type) are logged and
+    /// counted in [`AddedTypes::failed`] instead of silently leaving references to them dangling.
+    ///
+    /// Types nested deeper than [`MatcherSettings::maximum_added_type_depth`], or visited after
+    /// [`MatcherSettings::maximum_added_type_visits`] types, are logged and not added.
+    pub fn add_type_to_view<A: BNArchitecture>(
+        &self,
+        view: &BinaryView,
+        arch: &A,
+        ty: &Type,
+    ) -> AddedTypes {
         fn inner_add_type_to_view<A: BNArchitecture>(
             matcher: &Matcher,
             view: &BinaryView,
             arch: &A,
             visited_refs: &mut HashSet<String>,
+            visited_guids: &mut HashSet<TypeGUID>,
+            added: &mut AddedTypes,
+            depth: usize,
+            visits: &mut usize,
             ty: &Type,
         ) {
+            // Bound the work done for a malformed signature file with a huge (but acyclic) type graph.
+            let settings = &matcher.settings;
+            if depth > settings.maximum_added_type_depth {
+                log::warn!(
+                    "Type {:?} is nested deeper than {} types, skipping...",
+                    ty.name,
+                    settings.maximum_added_type_depth
+                );
+                return;
+            }
+            *visits += 1;
+            if *visits > settings.maximum_added_type_visits {
+                // Only warn once, every remaining type is skipped as well.
+                if *visits == settings.maximum_added_type_visits + 1 {
+                    log::warn!(
+                        "Visited more than {} types while adding type, skipping the remaining types...",
+                        settings.maximum_added_type_visits
+                    );
+                }
+                return;
+            }
             let ty_id_str = TypeGUID::from(ty).to_string();
             if view.type_by_id(&ty_id_str).is_some() {
                 // Type already added.
@@ -126,45 +383,125 @@ impl Matcher {
             // Type not already added to the view.
             // Verify all nested types are added before adding type.
             match ty.class.as_ref() {
-                TypeClass::Pointer(c) => {
-                    inner_add_type_to_view(matcher, view, arch, visited_refs, &c.child_type)
-                }
-                TypeClass::Array(c) => {
-                    inner_add_type_to_view(matcher, view, arch, visited_refs, &c.member_type)
-                }
+                TypeClass::Pointer(c) => inner_add_type_to_view(
+                    matcher,
+                    view,
+                    arch,
+                    visited_refs,
+                    visited_guids,
+                    added,
+                    depth + 1,
+                    visits,
+                    &c.child_type,
+                ),
+                TypeClass::Array(c) => inner_add_type_to_view(
+                    matcher,
+                    view,
+                    arch,
+                    visited_refs,
+                    visited_guids,
+                    added,
+                    depth + 1,
+                    visits,
+                    &c.member_type,
+                ),
                 TypeClass::Structure(c) => {
                     for member in &c.members {
-                        inner_add_type_to_view(matcher, view, arch, visited_refs, &member.ty)
+                        inner_add_type_to_view(
+                            matcher,
+                            view,
+                            arch,
+                            visited_refs,
+                            visited_guids,
+                            added,
+                            depth + 1,
+                            visits,
+                            &member.ty,
+                        )
                     }
                 }
-                TypeClass::Enumeration(c) => {
-                    inner_add_type_to_view(matcher, view, arch, visited_refs, &c.member_type)
-                }
+                TypeClass::Enumeration(c) => inner_add_type_to_view(
+                    matcher,
+                    view,
+                    arch,
+                    visited_refs,
+                    visited_guids,
+                    added,
+                    depth + 1,
+                    visits,
+                    &c.member_type,
+                ),
                 TypeClass::Union(c) => {
                     for member in &c.members {
-                        inner_add_type_to_view(matcher, view, arch, visited_refs, &member.ty)
+                        inner_add_type_to_view(
+                            matcher,
+                            view,
+                            arch,
+                            visited_refs,
+                            visited_guids,
+                            added,
+                            depth + 1,
+                            visits,
+                            &member.ty,
+                        )
                     }
                 }
                 TypeClass::Function(c) => {
                     for out_member in &c.out_members {
-                        inner_add_type_to_view(matcher, view, arch, visited_refs, &out_member.ty)
+                        inner_add_type_to_view(
+                            matcher,
+                            view,
+                            arch,
+                            visited_refs,
+                            visited_guids,
+                            added,
+                            depth + 1,
+                            visits,
+                            &out_member.ty,
+                        )
                     }
                     for in_member in &c.in_members {
-                        inner_add_type_to_view(matcher, view, arch, visited_refs, &in_member.ty)
+                        inner_add_type_to_view(
+                            matcher,
+                            view,
+                            arch,
+                            visited_refs,
+                            visited_guids,
+                            added,
+                            depth + 1,
+                            visits,
+                            &in_member.ty,
+                        )
                     }
                 }
                 TypeClass::Referrer(c) => {
                     // Check to see if the referrer has been added to the view.
                     let mut resolved = false;
                     if let Some(ref_guid) = c.guid {
-                        // NOTE: We do not need to check for cyclic reference here because
-                        // NOTE: GUID references are unable to be referenced by themselves.
-                        if view.type_by_id(ref_guid.to_string()).is_none() {
-                            // Add the referrer to the view if it is in the Matcher types
-                            if let Some(ref_ty) = matcher.types.get(&ref_guid) {
-                                inner_add_type_to_view(matcher, view, arch, visited_refs, &ref_ty);
-                                resolved = true;
+                        // NOTE: A well-formed signature file cannot have a GUID reference itself, however
+                        // NOTE: a malformed one can, so we must still check for a cyclic reference here.
+                        if !visited_guids.insert(ref_guid) {
+                            log::warn!("Cyclic type reference found for {}, skipping...", ref_guid);
+                        } else {
+                            if view.type_by_id(ref_guid.to_string()).is_none() {
+                                // Add the referrer to the view if it is in the Matcher types
+                                if let Some(ref_ty) = matcher.types.get(&ref_guid) {
+                                    inner_add_type_to_view(
+                                        matcher,
+                                        view,
+                                        arch,
+                                        visited_refs,
+                                        visited_guids,
+                                        added,
+                                        depth + 1,
+                                        visits,
+                                        &ref_ty,
+                                    );
+                                    resolved = true;
+                                }
                             }
+                            // No longer visiting type.
+                            visited_guids.remove(&ref_guid);
                         }
                     }
 
@@ -176,7 +513,17 @@ impl Matcher {
                         {
                             // Add the ref to the view if it is in the Matcher types
                             if let Some(ref_ty) = matcher.named_types.get(ref_name) {
-                                inner_add_type_to_view(matcher, view, arch, visited_refs, &ref_ty);
+                                inner_add_type_to_view(
+                                    matcher,
+                                    view,
+                                    arch,
+                                    visited_refs,
+                                    visited_guids,
+                                    added,
+                                    depth + 1,
+                                    visits,
+                                    &ref_ty,
+                                );
                             }
                             // No longer visiting type.
                             visited_refs.remove(ref_name);
@@ -185,16 +532,112 @@ impl Matcher {
 
                     // All nested types _should_ be added now, we can add this type.
                     // TODO: Do we want to make unnamed types visible? I think we should, but some people might be opposed.
+                    // NOTE: Types are always defined as auto, a user type of the same name is never overridden.
                     let ty_name = ty.name.to_owned().unwrap_or_else(|| ty_id_str.clone());
-                    view.define_auto_type_with_id(ty_name, ty_id_str, &to_bn_type(arch, ty));
+                    let defined_name = view.define_auto_type_with_id(
+                        ty_name.as_str(),
+                        ty_id_str.as_str(),
+                        &to_bn_type(arch, ty),
+                    );
+                    if view.type_by_id(&ty_id_str).is_none() {
+                        log::warn!("Failed to define type {} ({})", ty_name, ty_id_str);
+                        added.failed += 1;
+                    } else if defined_name.to_string() != ty_name {
+                        // References to the type by name will not resolve to the defined type.
+                        log::warn!(
+                            "Type {} ({}) collides with an existing type, defined as {}",
+                            ty_name,
+                            ty_id_str,
+                            defined_name
+                        );
+                        added.failed += 1;
+                    } else {
+                        added.applied += 1;
+                    }
                 }
                 _ => {}
             }
         }
-        inner_add_type_to_view(self, view, arch, &mut HashSet::new(), ty)
+        let mut added = AddedTypes::default();
+        inner_add_type_to_view(
+            self,
+            view,
+            arch,
+            &mut HashSet::new(),
+            &mut HashSet::new(),
+            &mut added,
+            0,
+            &mut 0,
+            ty,
+        );
+        added
     }
 
     pub fn match_function(&self, function: &BNFunction) {
+        if let Some(matched_function) =
+            cached_function_match(function, || self.find_function_match(function))
+        {
+            self.apply_function_match(function, &matched_function, None);
+        }
+    }
+
+    /// Match the function with the GUID of each level in order, applying the first match found.
+    ///
+    /// Order the levels from the most to the least precise, e.g. `[GuidLevel::Llil, GuidLevel::Hlil]`
+    /// only falls back to the HLIL GUID if the LLIL GUID does not match, this trades precision for
+    /// matching functions built with a different compiler. The level is stored in the [`MatchResult`].
+    ///
+    /// Returns the level the function matched at. Like [`Matcher::match_function_uncached`] the
+    /// match is not cached.
+    pub fn match_with_levels(
+        &self,
+        function: &BNFunction,
+        levels: &[GuidLevel],
+    ) -> Option<GuidLevel> {
+        levels.iter().copied().find(|&level| {
+            let Some(guid) = level_function_guid(function, level) else {
+                return false;
+            };
+            match self.find_function_match_for_guid(function, guid, Some(level)) {
+                Some(matched_function) => {
+                    self.apply_function_match(function, &matched_function, Some(level));
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    /// Match the function without the matched function cache, returning whether a match was found.
+    ///
+    /// Used to match against a matcher other than the platform matcher (e.g. [`Matcher::from_files`]),
+    /// a function the platform matcher already visited would otherwise never be matched again.
+    /// The match is not cached, so the platform matcher is unaffected.
+    pub fn match_function_uncached(&self, function: &BNFunction) -> bool {
+        match self.find_function_match(function) {
+            Some(matched_function) => {
+                self.apply_function_match(function, &matched_function, None);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn find_function_match(&self, function: &BNFunction) -> Option<Function> {
+        let warp_func_guid = try_cached_function_guid(function)?;
+        self.find_function_match_for_guid(function, warp_func_guid, None)
+    }
+
+    /// Find the match for the GUID among the signatures of the given [`GuidLevel`].
+    ///
+    /// With no level the GUID is the cached one, which is the LLIL or byte-only GUID, HLIL signatures
+    /// are only matched when asked for with [`Matcher::match_with_levels`].
+    fn find_function_match_for_guid(
+        &self,
+        function: &BNFunction,
+        warp_func_guid: FunctionGUID,
+        level: Option<GuidLevel>,
+    ) -> Option<Function> {
         // Call this the first time you matched on the function.
         let resolve_new_types = |matched: &Function| {
             // We also want to resolve the types here.
@@ -202,42 +645,87 @@ impl Matcher {
                 // Recursively go through the function type and resolve referrers
                 let view = function.view();
                 let arch = function.arch();
-                for out_member in &c.out_members {
-                    self.add_type_to_view(&view, &arch, &out_member.ty);
+                // Types are defined by one matched function at a time, like the rest of the match.
+                let _guard = APPLY_MATCHED_LOCK
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                let mut added = AddedTypes::default();
+                for member in c.out_members.iter().chain(&c.in_members) {
+                    added += self.add_type_to_view(&view, &arch, &member.ty);
                 }
-                for in_member in &c.in_members {
-                    self.add_type_to_view(&view, &arch, &in_member.ty);
+                if added.failed > 0 {
+                    log::warn!(
+                        "Failed to define {} of the types for matched function {} at 0x{:x}",
+                        added.failed,
+                        matched.symbol.name,
+                        function.start()
+                    );
                 }
             }
         };
 
-        if let Some(matched_function) = cached_function_match(function, || {
-            // We have yet to match on this function.
-            let function_len = function.highest_address() - function.lowest_address();
-            let is_function_trivial = { function_len < self.settings.trivial_function_len };
-            let is_function_allowed = {
-                function_len > self.settings.minimum_function_len
-                    && function_len < self.settings.maximum_function_len.unwrap_or(u64::MAX)
-            };
-            let warp_func_guid = try_cached_function_guid(function)?;
-            match self.functions.get(&warp_func_guid) {
-                _ if !is_function_allowed => None,
-                Some(matched) if matched.len() == 1 && !is_function_trivial => {
-                    resolve_new_types(&matched[0]);
-                    Some(matched[0].to_owned())
-                }
-                Some(matched) => {
-                    let matched_on = self.match_function_from_constraints(function, &matched)?;
-                    resolve_new_types(matched_on);
-                    Some(matched_on.to_owned())
-                }
-                None => None,
+        let function_len = function.highest_address() - function.lowest_address();
+        let is_function_trivial = { function_len < self.settings.trivial_function_len };
+        let is_function_allowed = {
+            function_len > self.settings.minimum_function_len
+                && function_len < self.settings.maximum_function_len.unwrap_or(u64::MAX)
+        };
+        let is_level_allowed = |matched: &Function| match level {
+            Some(level) => signature_guid_level(matched) == level,
+            None => signature_guid_level(matched) != GuidLevel::Hlil,
+        };
+        let entry = self.functions.get(&warp_func_guid);
+        let matched = entry
+            .as_deref()
+            .map(|matched| match matched.iter().all(is_level_allowed) {
+                true => Cow::Borrowed(matched.as_slice()),
+                false => Cow::Owned(
+                    matched
+                        .iter()
+                        .filter(|f| is_level_allowed(f))
+                        .cloned()
+                        .collect::<Vec<_>>(),
+                ),
+            });
+        match matched.filter(|matched| !matched.is_empty()) {
+            _ if !is_function_allowed => None,
+            Some(matched) if matched.len() == 1 && !is_function_trivial => {
+                resolve_new_types(&matched[0]);
+                Some(matched[0].to_owned())
+            }
+            Some(matched) => {
+                let matched_on = self.match_function_from_constraints(function, &matched)?;
+                resolve_new_types(matched_on);
+                Some(matched_on.to_owned())
             }
-        }) {
-            on_matched_function(function, &matched_function);
+            None => None,
         }
     }
 
+    fn apply_function_match(
+        &self,
+        function: &BNFunction,
+        matched_function: &Function,
+        level: Option<GuidLevel>,
+    ) {
+        let function_len = function.highest_address() - function.lowest_address();
+        let is_function_trivial = { function_len < self.settings.trivial_function_len };
+        // Unique non-trivial GUIDs are matched directly, everything else goes through the constraints.
+        let kind = match self.functions.get(&matched_function.guid) {
+            Some(matched) if matched.len() == 1 && !is_function_trivial => MatchKind::Guid,
+            _ => MatchKind::Constraints,
+        };
+        let result = MatchResult {
+            name: matched_function.symbol.name.to_owned(),
+            guid: matched_function.guid,
+            source: self.source_of(matched_function),
+            kind,
+            confidence: kind.confidence(),
+            level,
+        };
+        on_matched_function(function, matched_function, &result, &self.settings);
+    }
+
     pub fn match_function_from_constraints<'a>(
         &self,
         function: &BNFunction,
@@ -260,26 +748,30 @@ impl Matcher {
             observed_items: &HashSet<T>,
             matched_functions: &'a [Function],
             extract_items: F,
-        ) -> (usize, Option<&'a Function>)
+        ) -> (usize, Vec<&'a Function>)
         where
             F: Fn(&Function) -> HashSet<T>,
             T: Hash + Eq,
         {
             let mut highest_count = 0;
-            let mut matched_func = None;
+            let mut matched_funcs = Vec::new();
             for matched in matched_functions {
                 let matched_items = extract_items(matched);
                 let common_count = observed_items.intersection(&matched_items).count();
+                if common_count == 0 {
+                    // Nothing in common is never a match.
+                    continue;
+                }
                 match common_count.cmp(&highest_count) {
-                    Ordering::Equal => matched_func = None,
+                    Ordering::Equal => matched_funcs.push(matched),
                     Ordering::Greater => {
                         highest_count = common_count;
-                        matched_func = Some(matched);
+                        matched_funcs = vec![matched];
                     }
                     Ordering::Less => {}
                 }
             }
-            (highest_count, matched_func)
+            (highest_count, matched_funcs)
         }
 
         let call_site_guids: HashSet<_> = call_sites.iter().filter_map(|c| c.guid).collect();
@@ -332,27 +824,278 @@ impl Matcher {
         // If there is a tie, the last one wins, which should be call_site guid.
         checked_constraints
             .into_iter()
-            .max_by_key(|&(count, _)| count)
-            .filter(|&(count, _)| count >= self.settings.minimum_matched_constraints)
-            .and_then(|(_, func)| func)
+            .max_by_key(|(count, _)| *count)
+            .filter(|(count, _)| *count >= self.settings.minimum_matched_constraints)
+            .and_then(|(_, funcs)| self.resolve_tie(&funcs))
     }
 }
 
-fn get_data_from_dir(dir: &PathBuf) -> HashMap<PathBuf, Data> {
-    let data_from_entry = |entry: DirEntry| {
-        let path = entry.path();
-        let contents = std::fs::read(path).ok()?;
-        Data::from_bytes(&contents)
-    };
+/// The core and user signature directories, `None` if the directory could not be located.
+fn signature_dirs() -> [Option<PathBuf>; 2] {
+    [core_signature_dir(), user_signature_dir()]
+}
+
+/// Match the functions of the view against exactly the given signature files, see [`Matcher::from_files`].
+///
+/// Each function is matched with the GUID of each level in order, see [`Matcher::match_with_levels`].
+/// The matches are not cached, so the platform matcher is unaffected. Returns the number of matched functions.
+pub fn match_view_with_files(view: &BinaryView, paths: &[PathBuf], levels: &[GuidLevel]) -> usize {
+    let matcher = Matcher::from_files(paths);
+    let matched_functions = AtomicUsize::default();
+    view.functions().par_iter().for_each(|function| {
+        if matcher.match_with_levels(&function, levels).is_some() {
+            matched_functions.fetch_add(1, Relaxed);
+        }
+    });
+    matched_functions.load(Relaxed)
+}
+
+/// The names of the platforms with at least one signature file in the core or user signature directory.
+///
+/// Useful to show which platforms have signatures before creating the [`Matcher`] for them.
+pub fn available_signature_platforms() -> Vec<String> {
+    get_platforms_from_sig_dirs(&signature_dirs())
+}
+
+/// A signature file in the core or user signature directory, see [`list_signature_files`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureFileInfo {
+    pub path: PathBuf,
+    /// The platform subdirectory of the signature directory containing the file, e.g. `linux-x86_64`.
+    pub platform: String,
+    pub origin: SignatureOrigin,
+    /// The number of functions in the file, `None` if the file could not be read or parsed.
+    pub function_count: Option<usize>,
+    /// The size of the file in bytes.
+    pub size: u64,
+}
+
+/// The function count of each signature file along with its modification time when it was counted.
+static SIGNATURE_FILE_COUNTS: OnceLock<DashMap<PathBuf, (SystemTime, Option<usize>)>> =
+    OnceLock::new();
+
+/// List the signature files in the core and user signature directory, without loading them into a [`Matcher`].
+///
+/// Counting the functions requires parsing the file, the count is cached until the file is modified.
+pub fn list_signature_files() -> Vec<SignatureFileInfo> {
+    let [core_dir, user_dir] = signature_dirs();
+    get_signature_files_from_sig_dirs(core_dir, user_dir)
+}
+
+/// The signature files in the platform subdirectories of each signature directory, sorted by path.
+fn get_signature_files_from_sig_dirs(
+    core_dir: Option<PathBuf>,
+    user_dir: Option<PathBuf>,
+) -> Vec<SignatureFileInfo> {
+    let sig_dirs = [
+        (core_dir, SignatureOrigin::Core),
+        (user_dir, SignatureOrigin::User),
+    ];
+    let mut files = Vec::new();
+    for (sig_dir, origin) in sig_dirs {
+        let Some(sig_dir) = sig_dir else {
+            continue;
+        };
+        // Signature files directly in the signature directory belong to no platform.
+        let entries = WalkDir::new(&sig_dir)
+            .min_depth(2)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(is_signature_file);
+        for entry in entries {
+            let platform = entry
+                .path()
+                .strip_prefix(&sig_dir)
+                .ok()
+                .and_then(|path| path.components().next())
+                .map(|component| component.as_os_str().to_string_lossy().to_string());
+            let (Some(platform), Ok(metadata)) = (platform, entry.metadata()) else {
+                continue;
+            };
+            files.push(SignatureFileInfo {
+                function_count: cached_function_count(entry.path(), &metadata),
+                size: metadata.len(),
+                path: entry.into_path(),
+                platform,
+                origin,
+            });
+        }
+    }
+    files
+}
 
+/// The number of functions in the signature file, reused until the file is modified.
+fn cached_function_count(path: &Path, metadata: &std::fs::Metadata) -> Option<usize> {
+    let counts = SIGNATURE_FILE_COUNTS.get_or_init(Default::default);
+    let modified = metadata.modified().ok();
+    if let Some(entry) = counts.get(path) {
+        let (counted_modified, count) = *entry.value();
+        if modified == Some(counted_modified) {
+            return count;
+        }
+    }
+    let count = read_signature_file(path)
+        .ok()
+        .flatten()
+        .map(|data| data.functions.len());
+    // Without a modification time there is no way to tell when the count is stale.
+    if let Some(modified) = modified {
+        counts.insert(path.to_path_buf(), (modified, count));
+    }
+    count
+}
+
+/// The names of the platform subdirectories containing at least one signature file, sorted and deduplicated.
+fn get_platforms_from_sig_dirs(sig_dirs: &[Option<PathBuf>]) -> Vec<String> {
+    let mut platforms = BTreeSet::new();
+    for sig_dir in sig_dirs.iter().flatten() {
+        let Ok(entries) = std::fs::read_dir(sig_dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let has_signature_files = WalkDir::new(entry.path())
+                .min_depth(1)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .any(|e| is_signature_file(&e));
+            if has_signature_files {
+                platforms.insert(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+    platforms.into_iter().collect()
+}
+
+fn is_signature_file(entry: &DirEntry) -> bool {
+    entry.file_type().is_file() && entry.path().extension().is_some_and(|ext| ext == "sbin")
+}
+
+/// Read all signature files in the platform subdirectory of each signature directory.
+///
+/// Missing signature directories (i.e. no install or user directory) are skipped.
+fn get_data_from_sig_dirs(
+    sig_dirs: &[Option<PathBuf>],
+    platform_name: &str,
+    max_file_size: Option<u64>,
+    blacklist: &[PathBuf],
+) -> HashMap<PathBuf, Data> {
+    let mut data = HashMap::new();
+    for sig_dir in sig_dirs {
+        match sig_dir {
+            Some(sig_dir) => {
+                let plat_sig_dir = sig_dir.join(platform_name);
+                data.extend(get_data_from_dir(&plat_sig_dir, max_file_size, blacklist));
+            }
+            None => log::warn!("Signature directory is missing, skipping..."),
+        }
+    }
+    data
+}
+
+/// Read all signature files (with the `sbin` extension) in the directory.
+///
+/// Files larger than `max_file_size` bytes are skipped, pass `None` to read files of any size.
+/// Files in the `blacklist` are skipped, see [`add_to_signature_blacklist`].
+fn get_data_from_dir(
+    dir: &PathBuf,
+    max_file_size: Option<u64>,
+    blacklist: &[PathBuf],
+) -> HashMap<PathBuf, Data> {
     WalkDir::new(dir)
         .into_iter()
         .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-        .filter_map(|e| Some((e.clone().into_path(), data_from_entry(e)?)))
+        .filter(is_signature_file)
+        .filter(|e| {
+            let blacklisted = is_blacklisted(e.path(), blacklist);
+            if blacklisted {
+                log::debug!("Skipping blacklisted signature file {:?}", e.path());
+            }
+            !blacklisted
+        })
+        .filter_map(|e| {
+            let path = e.into_path();
+            let data = read_data_file(&path, max_file_size)?;
+            Some((path, data))
+        })
         .collect()
 }
 
+/// Whether the file is in the blacklist, either by the same path or by resolving to the same file.
+fn is_blacklisted(path: &Path, blacklist: &[PathBuf]) -> bool {
+    let canonical_path = path.canonicalize().ok();
+    blacklist.iter().any(|blacklisted| {
+        blacklisted == path
+            || canonical_path.as_ref().is_some_and(|canonical| {
+                blacklisted.canonicalize().ok().as_ref() == Some(canonical)
+            })
+    })
+}
+
+/// Exclude the signature file from the platform matchers, so that it is no longer matched against.
+///
+/// Use this for signature files created from the view being analyzed, matching them again only
+/// produces circular matches. The platform matchers are rebuilt with the next match.
+pub fn add_to_signature_blacklist(path: &Path) {
+    let mut blacklist = MatcherSettings::global().signature_blacklist;
+    if !blacklist.iter().any(|blacklisted| blacklisted == path) {
+        blacklist.push(path.to_path_buf());
+        set_signature_blacklist(&blacklist);
+    }
+}
+
+/// Include the signature file in the platform matchers again, see [`add_to_signature_blacklist`].
+pub fn remove_from_signature_blacklist(path: &Path) {
+    let mut blacklist = MatcherSettings::global().signature_blacklist;
+    let blacklist_len = blacklist.len();
+    blacklist.retain(|blacklisted| !is_blacklisted(blacklisted, &[path.to_path_buf()]));
+    if blacklist.len() != blacklist_len {
+        set_signature_blacklist(&blacklist);
+    }
+}
+
+fn set_signature_blacklist(blacklist: &[PathBuf]) {
+    let bn_settings = binaryninja::settings::Settings::new();
+    bn_settings.set_string_list(
+        MatcherSettings::SIGNATURE_BLACKLIST_SETTING,
+        blacklist.iter().cloned(),
+    );
+    // Force rebuild platform matchers.
+    invalidate_function_matcher_cache();
+}
+
+/// Read the signature file, failures are logged and `None` is returned.
+///
+/// Files larger than `max_file_size` bytes are skipped, pass `None` to read files of any size.
+fn read_data_file(path: &Path, max_file_size: Option<u64>) -> Option<Data> {
+    let file_size = match std::fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(err) => {
+            log::warn!("Failed to read signature file {:?}: {}", path, err);
+            return None;
+        }
+    };
+    if max_file_size.is_some_and(|max_size| file_size > max_size) {
+        log::warn!(
+            "Skipping signature file {:?}, {} bytes is above the maximum signature file size",
+            path,
+            file_size
+        );
+        return None;
+    }
+    match read_signature_file(path) {
+        Ok(Some(data)) => Some(data),
+        Ok(None) => {
+            log::warn!("Failed to parse signature file {:?}, is it corrupt?", path);
+            None
+        }
+        Err(err) => {
+            log::warn!("Failed to read signature file {:?}: {}", path, err);
+            None
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MatcherSettings {
     /// Any function under this length will be required to constrain.
@@ -375,6 +1118,47 @@ pub struct MatcherSettings {
     ///
     /// This is set to [MatcherSettings::DEFAULT_TRIVIAL_FUNCTION_LEN] by default.
     pub trivial_function_adjacent_allowed: bool,
+    /// Any signature file above this size in bytes will not be loaded.
+    ///
+    /// This is set to [MatcherSettings::MAXIMUM_SIGNATURE_FILE_SIZE_DEFAULT] by default.
+    pub maximum_signature_file_size: Option<u64>,
+    /// The confidence matched function types are applied with, anything below [`MAX_CONFIDENCE`] is applied
+    /// as auto so that user symbols and types are never overridden.
+    ///
+    /// This is set to [MatcherSettings::MATCH_CONFIDENCE_DEFAULT] by default.
+    pub match_confidence: u8,
+    /// When functions match equally well on their constraints, pick the one from the user signatures.
+    ///
+    /// This is set to [MatcherSettings::PREFER_USER_SIGNATURES_DEFAULT] by default.
+    pub prefer_user_signatures: bool,
+    /// Do not apply matches to functions with a user defined symbol, so that renamed functions are kept.
+    ///
+    /// This is set to [MatcherSettings::PRESERVE_USER_SYMBOLS_DEFAULT] by default.
+    pub preserve_user_symbols: bool,
+    /// Apply the calling convention of the matched function type over a lower confidence one.
+    ///
+    /// This is set to [MatcherSettings::APPLY_CALLING_CONVENTION_DEFAULT] by default.
+    pub apply_calling_convention: bool,
+    /// Apply the no-return status of the matched function type over a lower confidence one.
+    ///
+    /// This is set to [MatcherSettings::APPLY_NO_RETURN_DEFAULT] by default.
+    pub apply_no_return: bool,
+    /// The directory user signatures are read from and created in, instead of the one in the user directory.
+    ///
+    /// This is set to `None` by default, see [`crate::user_signature_dir`].
+    pub user_signature_dir: Option<PathBuf>,
+    /// Signature files which are never loaded into the platform matchers.
+    ///
+    /// This is empty by default, see [`add_to_signature_blacklist`].
+    pub signature_blacklist: Vec<PathBuf>,
+    /// Types referenced by a matched function nested deeper than this are not added to the view.
+    ///
+    /// This is set to [MatcherSettings::MAXIMUM_ADDED_TYPE_DEPTH_DEFAULT] by default.
+    pub maximum_added_type_depth: usize,
+    /// The number of types visited when adding a type to the view, the remaining types are not added.
+    ///
+    /// This is set to [MatcherSettings::MAXIMUM_ADDED_TYPE_VISITS_DEFAULT] by default.
+    pub maximum_added_type_visits: usize,
 }
 
 impl MatcherSettings {
@@ -390,6 +1174,28 @@ impl MatcherSettings {
     pub const TRIVIAL_FUNCTION_ADJACENT_ALLOWED_DEFAULT: bool = false;
     pub const TRIVIAL_FUNCTION_ADJACENT_ALLOWED_SETTING: &'static str =
         "analysis.warp.trivialFunctionAdjacentAllowed";
+    pub const MAXIMUM_SIGNATURE_FILE_SIZE_DEFAULT: u64 = 0;
+    pub const MAXIMUM_SIGNATURE_FILE_SIZE_SETTING: &'static str =
+        "analysis.warp.maximumSignatureFileSize";
+    pub const MATCH_CONFIDENCE_DEFAULT: u8 = MAX_CONFIDENCE;
+    pub const MATCH_CONFIDENCE_SETTING: &'static str = "analysis.warp.matchConfidence";
+    pub const USER_SIGNATURE_DIR_SETTING: &'static str = "analysis.warp.userSignatureDir";
+    pub const PREFER_USER_SIGNATURES_DEFAULT: bool = true;
+    pub const PREFER_USER_SIGNATURES_SETTING: &'static str = "analysis.warp.preferUserSignatures";
+    pub const PRESERVE_USER_SYMBOLS_DEFAULT: bool = true;
+    pub const PRESERVE_USER_SYMBOLS_SETTING: &'static str = "analysis.warp.preserveUserSymbols";
+    pub const APPLY_CALLING_CONVENTION_DEFAULT: bool = true;
+    pub const APPLY_CALLING_CONVENTION_SETTING: &'static str =
+        "analysis.warp.applyCallingConvention";
+    pub const APPLY_NO_RETURN_DEFAULT: bool = true;
+    pub const APPLY_NO_RETURN_SETTING: &'static str = "analysis.warp.applyNoReturn";
+    pub const SIGNATURE_BLACKLIST_SETTING: &'static str = "analysis.warp.signatureBlacklist";
+    pub const MAXIMUM_ADDED_TYPE_DEPTH_DEFAULT: usize = 64;
+    pub const MAXIMUM_ADDED_TYPE_DEPTH_SETTING: &'static str =
+        "analysis.warp.maximumAddedTypeDepth";
+    pub const MAXIMUM_ADDED_TYPE_VISITS_DEFAULT: usize = 10000;
+    pub const MAXIMUM_ADDED_TYPE_VISITS_SETTING: &'static str =
+        "analysis.warp.maximumAddedTypeVisits";
 
     /// Populates the [MatcherSettings] to the current Binary Ninja settings instance.
     ///
@@ -459,6 +1265,130 @@ impl MatcherSettings {
             Self::TRIVIAL_FUNCTION_ADJACENT_ALLOWED_SETTING,
             trivial_function_adjacent_allowed_props.to_string(),
         );
+
+        let maximum_signature_file_size_props = json!({
+            "title" : "Maximum Signature File Size",
+            "type" : "number",
+            "default" : Self::MAXIMUM_SIGNATURE_FILE_SIZE_DEFAULT,
+            "description" : "Signature files above this size in bytes will not be loaded. A value of 0 will disable this check.",
+            "ignore" : ["SettingsProjectScope", "SettingsResourceScope"]
+        });
+        bn_settings.register_setting_json(
+            Self::MAXIMUM_SIGNATURE_FILE_SIZE_SETTING,
+            maximum_signature_file_size_props.to_string(),
+        );
+
+        let match_confidence_props = json!({
+            "title" : "Match Confidence",
+            "type" : "number",
+            "default" : Self::MATCH_CONFIDENCE_DEFAULT,
+            "minValue" : 0,
+            "maxValue" : MAX_CONFIDENCE,
+            "description" : "The confidence matched function types are applied with. Below the maximum of 255 matches are applied as auto, so that user symbols and types are never overridden.",
+            "ignore" : ["SettingsProjectScope", "SettingsResourceScope"]
+        });
+        bn_settings.register_setting_json(
+            Self::MATCH_CONFIDENCE_SETTING,
+            match_confidence_props.to_string(),
+        );
+
+        let user_signature_dir_props = json!({
+            "title" : "User Signature Directory",
+            "type" : "string",
+            "default" : "",
+            "uiSelectionAction" : "directory",
+            "description" : "The directory user signatures are loaded from and created in, with a subdirectory for each platform. Leave empty to use the signatures directory in the user directory.",
+            "ignore" : ["SettingsProjectScope", "SettingsResourceScope"]
+        });
+        bn_settings.register_setting_json(
+            Self::USER_SIGNATURE_DIR_SETTING,
+            user_signature_dir_props.to_string(),
+        );
+
+        let prefer_user_signatures_props = json!({
+            "title" : "Prefer User Signatures",
+            "type" : "boolean",
+            "default" : Self::PREFER_USER_SIGNATURES_DEFAULT,
+            "description" : "When functions from the core and user signatures match equally well on their constraints, match the user signature instead of no function.",
+            "ignore" : ["SettingsProjectScope", "SettingsResourceScope"]
+        });
+        bn_settings.register_setting_json(
+            Self::PREFER_USER_SIGNATURES_SETTING,
+            prefer_user_signatures_props.to_string(),
+        );
+
+        let preserve_user_symbols_props = json!({
+            "title" : "Preserve User Symbols",
+            "type" : "boolean",
+            "default" : Self::PRESERVE_USER_SYMBOLS_DEFAULT,
+            "description" : "Functions with a user defined symbol will not be matched, so that functions renamed by the user are never overridden.",
+            "ignore" : ["SettingsProjectScope", "SettingsResourceScope"]
+        });
+        bn_settings.register_setting_json(
+            Self::PRESERVE_USER_SYMBOLS_SETTING,
+            preserve_user_symbols_props.to_string(),
+        );
+
+        let apply_calling_convention_props = json!({
+            "title" : "Apply Calling Convention",
+            "type" : "boolean",
+            "default" : Self::APPLY_CALLING_CONVENTION_DEFAULT,
+            "description" : "Apply the calling convention of the matched function, unless the function already has one with a higher confidence. User set calling conventions are never overridden.",
+            "ignore" : ["SettingsProjectScope", "SettingsResourceScope"]
+        });
+        bn_settings.register_setting_json(
+            Self::APPLY_CALLING_CONVENTION_SETTING,
+            apply_calling_convention_props.to_string(),
+        );
+
+        let apply_no_return_props = json!({
+            "title" : "Apply No Return",
+            "type" : "boolean",
+            "default" : Self::APPLY_NO_RETURN_DEFAULT,
+            "description" : "Apply the no-return status of the matched function, unless the function already has one with a higher confidence. User set no-return status is never overridden.",
+            "ignore" : ["SettingsProjectScope", "SettingsResourceScope"]
+        });
+        bn_settings.register_setting_json(
+            Self::APPLY_NO_RETURN_SETTING,
+            apply_no_return_props.to_string(),
+        );
+
+        let signature_blacklist_props = json!({
+            "title" : "Signature Blacklist",
+            "type" : "array",
+            "sorted" : false,
+            "default" : [],
+            "description" : "Signature files which will not be loaded, such as signature files created from the binary being matched.",
+            "ignore" : ["SettingsProjectScope", "SettingsResourceScope"]
+        });
+        bn_settings.register_setting_json(
+            Self::SIGNATURE_BLACKLIST_SETTING,
+            signature_blacklist_props.to_string(),
+        );
+
+        let maximum_added_type_depth_props = json!({
+            "title" : "Maximum Added Type Depth",
+            "type" : "number",
+            "default" : Self::MAXIMUM_ADDED_TYPE_DEPTH_DEFAULT,
+            "description" : "Types of a matched function nested deeper than this will not be added to the view.",
+            "ignore" : ["SettingsProjectScope", "SettingsResourceScope"]
+        });
+        bn_settings.register_setting_json(
+            Self::MAXIMUM_ADDED_TYPE_DEPTH_SETTING,
+            maximum_added_type_depth_props.to_string(),
+        );
+
+        let maximum_added_type_visits_props = json!({
+            "title" : "Maximum Added Type Visits",
+            "type" : "number",
+            "default" : Self::MAXIMUM_ADDED_TYPE_VISITS_DEFAULT,
+            "description" : "The number of types visited when adding a type of a matched function to the view, the remaining types will not be added.",
+            "ignore" : ["SettingsProjectScope", "SettingsResourceScope"]
+        });
+        bn_settings.register_setting_json(
+            Self::MAXIMUM_ADDED_TYPE_VISITS_SETTING,
+            maximum_added_type_visits_props.to_string(),
+        );
     }
 
     pub fn global() -> Self {
@@ -482,6 +1412,53 @@ impl MatcherSettings {
             settings.minimum_matched_constraints =
                 bn_settings.get_integer(Self::MINIMUM_MATCHED_CONSTRAINTS_SETTING) as usize;
         }
+        if bn_settings.contains(Self::MAXIMUM_SIGNATURE_FILE_SIZE_SETTING) {
+            match bn_settings.get_integer(Self::MAXIMUM_SIGNATURE_FILE_SIZE_SETTING) {
+                0 => settings.maximum_signature_file_size = None,
+                size => settings.maximum_signature_file_size = Some(size),
+            }
+        }
+        if bn_settings.contains(Self::MATCH_CONFIDENCE_SETTING) {
+            settings.match_confidence = bn_settings
+                .get_integer(Self::MATCH_CONFIDENCE_SETTING)
+                .min(MAX_CONFIDENCE as u64) as u8;
+        }
+        if bn_settings.contains(Self::PREFER_USER_SIGNATURES_SETTING) {
+            settings.prefer_user_signatures =
+                bn_settings.get_bool(Self::PREFER_USER_SIGNATURES_SETTING);
+        }
+        if bn_settings.contains(Self::PRESERVE_USER_SYMBOLS_SETTING) {
+            settings.preserve_user_symbols =
+                bn_settings.get_bool(Self::PRESERVE_USER_SYMBOLS_SETTING);
+        }
+        if bn_settings.contains(Self::APPLY_CALLING_CONVENTION_SETTING) {
+            settings.apply_calling_convention =
+                bn_settings.get_bool(Self::APPLY_CALLING_CONVENTION_SETTING);
+        }
+        if bn_settings.contains(Self::APPLY_NO_RETURN_SETTING) {
+            settings.apply_no_return = bn_settings.get_bool(Self::APPLY_NO_RETURN_SETTING);
+        }
+        if bn_settings.contains(Self::USER_SIGNATURE_DIR_SETTING) {
+            let dir = bn_settings.get_string(Self::USER_SIGNATURE_DIR_SETTING);
+            if !dir.is_empty() {
+                settings.user_signature_dir = Some(PathBuf::from(dir.to_string()));
+            }
+        }
+        if bn_settings.contains(Self::MAXIMUM_ADDED_TYPE_DEPTH_SETTING) {
+            settings.maximum_added_type_depth =
+                bn_settings.get_integer(Self::MAXIMUM_ADDED_TYPE_DEPTH_SETTING) as usize;
+        }
+        if bn_settings.contains(Self::MAXIMUM_ADDED_TYPE_VISITS_SETTING) {
+            settings.maximum_added_type_visits =
+                bn_settings.get_integer(Self::MAXIMUM_ADDED_TYPE_VISITS_SETTING) as usize;
+        }
+        if bn_settings.contains(Self::SIGNATURE_BLACKLIST_SETTING) {
+            settings.signature_blacklist = bn_settings
+                .get_string_list(Self::SIGNATURE_BLACKLIST_SETTING)
+                .iter()
+                .map(PathBuf::from)
+                .collect();
+        }
         settings
     }
 }
@@ -495,6 +1472,16 @@ impl Default for MatcherSettings {
             minimum_matched_constraints: MatcherSettings::MINIMUM_MATCHED_CONSTRAINTS_DEFAULT,
             trivial_function_adjacent_allowed:
                 MatcherSettings::TRIVIAL_FUNCTION_ADJACENT_ALLOWED_DEFAULT,
+            maximum_signature_file_size: None,
+            match_confidence: MatcherSettings::MATCH_CONFIDENCE_DEFAULT,
+            user_signature_dir: None,
+            signature_blacklist: Vec::new(),
+            maximum_added_type_depth: MatcherSettings::MAXIMUM_ADDED_TYPE_DEPTH_DEFAULT,
+            maximum_added_type_visits: MatcherSettings::MAXIMUM_ADDED_TYPE_VISITS_DEFAULT,
+            prefer_user_signatures: MatcherSettings::PREFER_USER_SIGNATURES_DEFAULT,
+            preserve_user_symbols: MatcherSettings::PRESERVE_USER_SYMBOLS_DEFAULT,
+            apply_calling_convention: MatcherSettings::APPLY_CALLING_CONVENTION_DEFAULT,
+            apply_no_return: MatcherSettings::APPLY_NO_RETURN_DEFAULT,
         }
     }
 }
@@ -522,3 +1509,503 @@ impl From<Guard<'_, Platform>> for PlatformID {
         Self::from(value.as_ref())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::match_result::match_result_for;
+    use crate::test::{session, view_with_function};
+    use binaryninja::architecture::CoreArchitecture;
+    use binaryninja::file_metadata::FileMetadata;
+    use binaryninja::types::Type as BNType;
+    use warp::r#type::class::pointer::PointerAddressing;
+    use warp::r#type::class::{IntegerClass, PointerClass, ReferrerClass};
+    use warp::signature::basic_block::BasicBlockGUID;
+    use warp::signature::function::constraints::FunctionConstraints;
+    use warp::symbol::class::SymbolClass;
+    use warp::symbol::{Symbol, SymbolModifiers};
+
+    fn integer_type(width: u16) -> Type {
+        Type::builder::<String, _>()
+            .class(TypeClass::Integer(IntegerClass {
+                width: Some(width),
+                signed: false,
+            }))
+            .build()
+    }
+
+    fn pointer_to_guid(guid: TypeGUID) -> Type {
+        let referrer = Type::builder::<String, _>()
+            .class(TypeClass::Referrer(ReferrerClass::new(Some(guid), None)))
+            .build();
+        Type::builder::<String, _>()
+            .class(TypeClass::Pointer(PointerClass {
+                width: Some(64),
+                child_type: referrer,
+                addressing: PointerAddressing::Absolute,
+            }))
+            .build()
+    }
+
+    #[test]
+    fn cyclic_type_reference() {
+        let _session = session();
+        let file = FileMetadata::new();
+        let view = BinaryView::from_data(&file, &[0; 0x10]).expect("Failed to create view");
+        let arch = CoreArchitecture::by_name("x86_64").expect("Failed to get architecture");
+
+        // Construct two types which reference each other through their GUIDs.
+        let guid_a = TypeGUID::from(&integer_type(8));
+        let guid_b = TypeGUID::from(&integer_type(16));
+        let matcher = Matcher::default();
+        matcher.types.insert(guid_a, pointer_to_guid(guid_b));
+        matcher.types.insert(guid_b, pointer_to_guid(guid_a));
+
+        // This must terminate instead of recursing forever.
+        matcher.add_type_to_view(&view, &arch, &pointer_to_guid(guid_a));
+    }
+
+    #[test]
+    fn deep_type_chain_bounded() {
+        let _session = session();
+        let arch = CoreArchitecture::by_name("x86_64").expect("Failed to get architecture");
+        let pointer_to = |child_type: Type| {
+            Type::builder::<String, _>()
+                .class(TypeClass::Pointer(PointerClass {
+                    width: Some(64),
+                    child_type,
+                    addressing: PointerAddressing::Absolute,
+                }))
+                .build()
+        };
+        // A named type at the end of a pointer-to-pointer chain.
+        let mut leaf = Type::builder::<String, _>()
+            .class(TypeClass::Referrer(ReferrerClass::new(
+                None,
+                Some("leaf_struct".to_string()),
+            )))
+            .build();
+        leaf.name = Some("leaf".to_string());
+        let chain = |len: usize| (0..len).fold(leaf.clone(), |ty, _| pointer_to(ty));
+
+        let mut matcher = Matcher::default();
+        let file = FileMetadata::new();
+        let view = BinaryView::from_data(&file, &[0; 0x10]).expect("Failed to create view");
+        matcher.add_type_to_view(&view, &arch, &chain(8));
+        assert!(view.type_by_name("leaf").is_some());
+
+        // The leaf is nested deeper than the maximum depth.
+        matcher.settings.maximum_added_type_depth = 16;
+        let file = FileMetadata::new();
+        let view = BinaryView::from_data(&file, &[0; 0x10]).expect("Failed to create view");
+        let added = matcher.add_type_to_view(&view, &arch, &chain(200));
+        assert_eq!(added, AddedTypes::default());
+        assert!(view.type_by_name("leaf").is_none());
+
+        // The leaf is visited after the visit budget is used up.
+        matcher.settings.maximum_added_type_depth = usize::MAX;
+        matcher.settings.maximum_added_type_visits = 4;
+        let added = matcher.add_type_to_view(&view, &arch, &chain(8));
+        assert_eq!(added, AddedTypes::default());
+        assert!(view.type_by_name("leaf").is_none());
+    }
+
+    #[test]
+    fn conflicting_type_not_swallowed() {
+        let _session = session();
+        let arch = CoreArchitecture::by_name("x86_64").expect("Failed to get architecture");
+        let mut alias = Type::builder::<String, _>()
+            .class(TypeClass::Referrer(ReferrerClass::new(
+                None,
+                Some("my_struct".to_string()),
+            )))
+            .build();
+        alias.name = Some("my_alias".to_string());
+        let matcher = Matcher::default();
+
+        let file = FileMetadata::new();
+        let view = BinaryView::from_data(&file, &[0; 0x10]).expect("Failed to create view");
+        let added = matcher.add_type_to_view(&view, &arch, &alias);
+        assert_eq!(
+            added,
+            AddedTypes {
+                applied: 1,
+                failed: 0
+            }
+        );
+
+        // A user type with the same name already exists, the type cannot be defined under its name.
+        let file = FileMetadata::new();
+        let view = BinaryView::from_data(&file, &[0; 0x10]).expect("Failed to create view");
+        view.define_user_type("my_alias", &BNType::int(4, true));
+        let added = matcher.add_type_to_view(&view, &arch, &alias);
+        assert_eq!(
+            added,
+            AddedTypes {
+                applied: 0,
+                failed: 1
+            }
+        );
+    }
+
+    fn test_function(name: &str, ty: Type) -> Function {
+        Function {
+            guid: FunctionGUID::from_basic_blocks(&[BasicBlockGUID::from([0xc3].as_slice())]),
+            symbol: Symbol::new(
+                name.to_string(),
+                SymbolClass::Function,
+                SymbolModifiers::default(),
+            ),
+            ty,
+            constraints: FunctionConstraints {
+                adjacent: HashSet::new(),
+                call_sites: HashSet::new(),
+                caller_sites: HashSet::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn identical_functions_deduplicated() {
+        let _session = session();
+        let function = test_function("memcpy", integer_type(32));
+        let mut file_a = Data::default();
+        file_a.functions.push(function.clone());
+        let mut file_b = Data::default();
+        file_b.functions.push(function.clone());
+        // Same GUID and name, but a different type, this is a different function.
+        file_b
+            .functions
+            .push(test_function("memcpy", integer_type(64)));
+
+        let matcher = Matcher::from_data(Data::merge(vec![file_a, file_b]));
+        let bucket = matcher.functions.get(&function.guid).expect("Missing GUID");
+        assert_eq!(bucket.len(), 2);
+        assert_eq!(bucket.iter().filter(|f| **f == function).count(), 1);
+    }
+
+    #[test]
+    fn same_name_overloads_kept() {
+        let _session = session();
+        let overload_a = test_function("operator<<", integer_type(32));
+        let overload_b = test_function("operator<<", integer_type(64));
+        let mut data = Data::default();
+        data.functions.push(overload_a.clone());
+        data.functions.push(overload_b.clone());
+        data.functions.push(overload_a.clone());
+
+        let matcher = Matcher::from_data(data);
+        let bucket = matcher
+            .functions
+            .get(&overload_a.guid)
+            .expect("Missing GUID");
+        assert_eq!(bucket.len(), 2);
+        assert!(bucket.contains(&overload_a));
+        assert!(bucket.contains(&overload_b));
+    }
+
+    #[test]
+    fn user_signature_wins_tie() {
+        let _session = session();
+        let dir = std::env::temp_dir().join(format!("warp_sig_tie_{}", std::process::id()));
+        let core_dir = dir.join("core");
+        let user_dir = dir.join("user");
+        std::fs::create_dir_all(core_dir.join("linux-x86_64")).unwrap();
+        std::fs::create_dir_all(user_dir.join("linux-x86_64")).unwrap();
+        let core_function = test_function("core_memcpy", integer_type(32));
+        let user_function = test_function("user_memcpy", integer_type(32));
+        let mut core_data = Data::default();
+        core_data.functions.push(core_function.clone());
+        let mut user_data = Data::default();
+        user_data.functions.push(user_function.clone());
+        std::fs::write(
+            core_dir.join("linux-x86_64/libc.sbin"),
+            core_data.to_bytes(),
+        )
+        .unwrap();
+        std::fs::write(
+            user_dir.join("linux-x86_64/libc.sbin"),
+            user_data.to_bytes(),
+        )
+        .unwrap();
+
+        let mut matcher =
+            Matcher::from_sig_dirs(Some(core_dir), Some(user_dir), "linux-x86_64", None, &[]);
+        assert_eq!(
+            matcher.origin_of(&core_function),
+            Some(SignatureOrigin::Core)
+        );
+        assert_eq!(
+            matcher.origin_of(&user_function),
+            Some(SignatureOrigin::User)
+        );
+        let candidates = [&core_function, &user_function];
+        matcher.settings.prefer_user_signatures = true;
+        assert_eq!(matcher.resolve_tie(&candidates), Some(&user_function));
+        matcher.settings.prefer_user_signatures = false;
+        assert_eq!(matcher.resolve_tie(&candidates), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn match_from_one_file() {
+        let _session = session();
+        // xor eax, eax; ret
+        let (view, function) = view_with_function(&[0x31, 0xc0, 0xc3]);
+        let llil = function.low_level_il().expect("Function has no LLIL");
+        let mut signature = crate::build_function(&function, &llil, false);
+        signature.symbol = Symbol::new(
+            "return_zero".to_string(),
+            SymbolClass::Function,
+            SymbolModifiers::default(),
+        );
+
+        let dir = std::env::temp_dir().join(format!("warp_sig_files_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sig_path = dir.join("candidate.sbin");
+        let mut data = Data::default();
+        data.functions.push(signature.clone());
+        std::fs::write(&sig_path, data.to_bytes()).unwrap();
+
+        // Missing files are skipped.
+        let mut matcher = Matcher::from_files(&[sig_path.clone(), dir.join("missing.sbin")]);
+        assert_eq!(matcher.source_of(&signature), Some(sig_path.clone()));
+        assert_eq!(matcher.origin_of(&signature), Some(SignatureOrigin::User));
+        // The function is trivial, it would otherwise have to match on its constraints.
+        matcher.settings.trivial_function_len = 0;
+        matcher.match_function(&function);
+        let result = match_result_for(&view, 0).expect("Function was not matched");
+        assert_eq!(result.name, "return_zero");
+        assert_eq!(result.source, Some(sig_path));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn uncached_match_after_cached_miss() {
+        let _session = session();
+        // xor eax, eax; ret
+        let (view, function) = view_with_function(&[0x31, 0xc0, 0xc3]);
+        let llil = function.low_level_il().expect("Function has no LLIL");
+        let mut signature = crate::build_function(&function, &llil, false);
+        signature.symbol = Symbol::new(
+            "return_zero".to_string(),
+            SymbolClass::Function,
+            SymbolModifiers::default(),
+        );
+
+        // The function is trivial, it would otherwise have to match on its constraints.
+        let mut empty_matcher = Matcher::from_data(Data::default());
+        empty_matcher.settings.trivial_function_len = 0;
+        empty_matcher.match_function(&function);
+        assert_eq!(match_result_for(&view, 0), None);
+
+        let mut data = Data::default();
+        data.functions.push(signature);
+        let mut matcher = Matcher::from_data(data);
+        matcher.settings.trivial_function_len = 0;
+        // The miss of the first matcher is cached.
+        matcher.match_function(&function);
+        assert_eq!(match_result_for(&view, 0), None);
+        assert!(matcher.match_function_uncached(&function));
+        let result = match_result_for(&view, 0).expect("Function was not matched");
+        assert_eq!(result.name, "return_zero");
+    }
+
+    #[test]
+    fn match_falls_back_to_hlil_level() {
+        let _session = session();
+        // xor eax, eax; ret
+        let (view, function) = view_with_function(&[0x31, 0xc0, 0xc3]);
+        let mut signature = crate::build_hlil_function(&function).expect("Function has no HLIL");
+        signature.symbol = Symbol::new(
+            "return_zero".to_string(),
+            SymbolClass::Function,
+            SymbolModifiers::default(),
+        );
+
+        let mut data = Data::default();
+        data.functions.push(signature);
+        let mut matcher = Matcher::from_data(data);
+        // The function is trivial, it would otherwise have to match on its constraints.
+        matcher.settings.trivial_function_len = 0;
+        // Only the HLIL GUID is in the signatures, the LLIL GUID cannot match.
+        assert_eq!(
+            matcher.match_with_levels(&function, &[GuidLevel::Llil]),
+            None
+        );
+        assert_eq!(
+            matcher.match_with_levels(&function, &[GuidLevel::Llil, GuidLevel::Hlil]),
+            Some(GuidLevel::Hlil)
+        );
+        let result = match_result_for(&view, 0).expect("Function was not matched");
+        assert_eq!(result.name, "return_zero");
+        assert_eq!(result.level, Some(GuidLevel::Hlil));
+    }
+
+    #[test]
+    fn match_only_compares_same_level() {
+        let _session = session();
+        // xor eax, eax; ret
+        let (view, function) = view_with_function(&[0x31, 0xc0, 0xc3]);
+        let llil = function.low_level_il().expect("Function has no LLIL");
+        let mut signature = crate::build_hlil_function(&function).expect("Function has no HLIL");
+        // A HLIL signature with the LLIL GUID of the function, as if the GUIDs collided.
+        signature.guid = cached_function_guid(&function, &llil);
+        signature.symbol = Symbol::new(
+            "return_zero".to_string(),
+            SymbolClass::Function,
+            SymbolModifiers::default(),
+        );
+
+        let mut data = Data::default();
+        data.functions.push(signature);
+        let mut matcher = Matcher::from_data(data);
+        matcher.settings.trivial_function_len = 0;
+        assert!(!matcher.match_function_uncached(&function));
+        assert_eq!(
+            matcher.match_with_levels(&function, &[GuidLevel::Llil]),
+            None
+        );
+        assert_eq!(match_result_for(&view, 0), None);
+    }
+
+    #[test]
+    fn view_platform_matchers_built_up_front() {
+        let _session = session();
+        // xor eax, eax; ret
+        let (view, function) = view_with_function(&[0x31, 0xc0, 0xc3]);
+        build_view_platform_matchers(&view);
+        let platform_id = cached_platform_id(&function.platform());
+        let matcher_cache = PLAT_MATCHER_CACHE.get().expect("No matcher was built");
+        assert!(matcher_cache
+            .get(&platform_id)
+            .is_some_and(|cell| cell.get().is_some()));
+    }
+
+    #[test]
+    fn cached_platform_id_matches_name() {
+        let _session = session();
+        let platform = Platform::by_name("linux-x86_64").expect("Failed to get platform");
+        let other_platform = Platform::by_name("windows-x86_64").expect("Failed to get platform");
+        assert_eq!(
+            cached_platform_id(&platform),
+            PlatformID::from(platform.as_ref())
+        );
+        // Cached, the same ID is returned.
+        assert_eq!(
+            cached_platform_id(&platform),
+            PlatformID::from(platform.as_ref())
+        );
+        assert_ne!(
+            cached_platform_id(&platform),
+            cached_platform_id(&other_platform)
+        );
+        // A separate handle to a platform with the same name has the same ID.
+        let same_name_platform = Platform::by_name("linux-x86_64").expect("Failed to get platform");
+        assert_eq!(
+            cached_platform_id(&same_name_platform),
+            cached_platform_id(&platform)
+        );
+    }
+
+    #[test]
+    fn data_from_dir_only_signature_files() {
+        let dir = std::env::temp_dir().join(format!("warp_sig_dir_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sig_path = dir.join("valid.sbin");
+        std::fs::write(&sig_path, Data::default().to_bytes()).unwrap();
+        std::fs::write(dir.join("corrupt.sbin"), b"not a signature file").unwrap();
+        std::fs::write(dir.join("analysis.log"), b"not a signature file").unwrap();
+
+        let data = get_data_from_dir(&dir, None, &[]);
+        assert_eq!(data.keys().collect::<Vec<_>>(), vec![&sig_path]);
+        // Every file is above the maximum size.
+        assert!(get_data_from_dir(&dir, Some(0), &[]).is_empty());
+        // The blacklisted file is skipped, also when the blacklisted path is not the same path.
+        assert!(get_data_from_dir(&dir, None, &[sig_path.clone()]).is_empty());
+        let relative_sig_path = dir
+            .join("../")
+            .join(dir.file_name().unwrap())
+            .join("valid.sbin");
+        assert!(get_data_from_dir(&dir, None, &[relative_sig_path]).is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn data_from_missing_sig_dirs() {
+        let missing_dir = std::env::temp_dir().join("warp_missing_sig_dir");
+        let data = get_data_from_sig_dirs(&[None, Some(missing_dir)], "linux-x86_64", None, &[]);
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn signature_files_from_sig_dirs() {
+        let _session = session();
+        let dir = std::env::temp_dir().join(format!("warp_sig_list_{}", std::process::id()));
+        let core_dir = dir.join("core");
+        let user_dir = dir.join("user");
+        std::fs::create_dir_all(core_dir.join("linux-x86_64")).unwrap();
+        std::fs::create_dir_all(user_dir.join("linux-x86_64/libc")).unwrap();
+        let mut data = Data::default();
+        data.functions
+            .push(test_function("memcpy", integer_type(32)));
+        data.functions
+            .push(test_function("memset", integer_type(32)));
+        let core_path = core_dir.join("linux-x86_64/libc.sbin");
+        let user_path = user_dir.join("linux-x86_64/libc/libm.sbin");
+        std::fs::write(&core_path, data.to_bytes()).unwrap();
+        std::fs::write(&user_path, b"not a signature file").unwrap();
+        // Signature files directly in the signature directory belong to no platform.
+        std::fs::write(core_dir.join("stray.sbin"), data.to_bytes()).unwrap();
+
+        let files = get_signature_files_from_sig_dirs(Some(core_dir), Some(user_dir));
+        assert_eq!(
+            files,
+            vec![
+                SignatureFileInfo {
+                    path: core_path,
+                    platform: "linux-x86_64".to_string(),
+                    origin: SignatureOrigin::Core,
+                    function_count: Some(2),
+                    size: data.to_bytes().len() as u64,
+                },
+                SignatureFileInfo {
+                    path: user_path,
+                    platform: "linux-x86_64".to_string(),
+                    origin: SignatureOrigin::User,
+                    function_count: None,
+                    size: 20,
+                },
+            ]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn platforms_from_sig_dirs() {
+        let dir = std::env::temp_dir().join(format!("warp_sig_platforms_{}", std::process::id()));
+        let core_dir = dir.join("core");
+        let user_dir = dir.join("user");
+        std::fs::create_dir_all(core_dir.join("linux-x86_64")).unwrap();
+        std::fs::create_dir_all(core_dir.join("windows-x86")).unwrap();
+        std::fs::create_dir_all(user_dir.join("linux-x86_64/libc")).unwrap();
+        std::fs::create_dir_all(user_dir.join("mac-aarch64")).unwrap();
+        let sig_bytes = Data::default().to_bytes();
+        std::fs::write(core_dir.join("linux-x86_64/libc.sbin"), &sig_bytes).unwrap();
+        std::fs::write(user_dir.join("linux-x86_64/libc/libm.sbin"), &sig_bytes).unwrap();
+        std::fs::write(user_dir.join("mac-aarch64/libc.sbin"), &sig_bytes).unwrap();
+        // Not a signature file, the platform should not be listed.
+        std::fs::write(core_dir.join("windows-x86/notes.txt"), b"").unwrap();
+        // Signature files directly in the signature directory belong to no platform.
+        std::fs::write(core_dir.join("stray.sbin"), &sig_bytes).unwrap();
+
+        let platforms = get_platforms_from_sig_dirs(&[Some(core_dir), None, Some(user_dir)]);
+        assert_eq!(platforms, vec!["linux-x86_64", "mac-aarch64"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
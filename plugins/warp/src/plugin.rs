@@ -1,27 +1,42 @@
 use crate::cache::{
     register_cache_destructor, ViewID, FUNCTION_CACHE, GUID_CACHE, MATCHED_FUNCTION_CACHE,
 };
-use crate::convert::{to_bn_symbol_at_address, to_bn_type};
+use crate::convert::{to_bn_symbol_at_address, to_bn_type_with_confidence};
+use crate::debug::dump_function_guids;
+use crate::match_result::{
+    apply_function_attributes, should_apply_match, store_match_result, MatchResult,
+};
 use crate::matcher::{
     invalidate_function_matcher_cache, Matcher, MatcherSettings, PlatformID, PLAT_MATCHER_CACHE,
 };
-use crate::{build_function, cache};
+use crate::{
+    build_function, cache, is_analysis_complete, is_no_return, GenerationSettings, GuidOptions,
+    GuidString,
+};
+use binaryninja::background_task::BackgroundTask;
 use binaryninja::binary_view::{BinaryView, BinaryViewExt};
 use binaryninja::command::{Command, FunctionCommand};
+use binaryninja::confidence::{Conf, MAX_CONFIDENCE};
 use binaryninja::function::{Function, FunctionUpdateType};
+use binaryninja::interaction::{show_message_box, MessageBoxButtonSet, MessageBoxIcon};
 use binaryninja::logger::Logger;
 use binaryninja::rc::Ref;
+use binaryninja::string::BnStrCompatible;
 use binaryninja::tags::TagType;
 use binaryninja::ObjectDestructor;
 use log::LevelFilter;
+use std::sync::Mutex;
 use warp::signature::function::constraints::FunctionConstraint;
 use warp::signature::function::Function as WarpFunction;
 
 mod add;
+mod constraints;
 mod copy;
 mod create;
 mod find;
 mod load;
+mod regenerate;
+mod remove;
 mod types;
 mod workflow;
 
@@ -34,25 +49,120 @@ fn get_warp_tag_type(view: &BinaryView) -> Ref<TagType> {
         .unwrap_or_else(|| view.create_tag_type(TAG_NAME, TAG_ICON))
 }
 
+/// Begin a background task, `None` if the core could not create one (e.g. in some headless environments).
+///
+/// The work should still be done without the task, only the progress is not reported, see [`OptionalBackgroundTask`].
+fn optional_background_task<S: BnStrCompatible>(
+    initial_text: S,
+    can_cancel: bool,
+) -> Option<Ref<BackgroundTask>> {
+    let background_task = BackgroundTask::try_new(initial_text, can_cancel);
+    if background_task.is_none() {
+        log::debug!("Failed to create background task, progress will not be reported");
+    }
+    background_task
+}
+
+/// Progress reporting for a task from [`optional_background_task`], does nothing without a task.
+trait OptionalBackgroundTask {
+    fn set_progress<S: BnStrCompatible>(&self, text: S);
+
+    fn finish(&self);
+}
+
+impl OptionalBackgroundTask for Option<Ref<BackgroundTask>> {
+    fn set_progress<S: BnStrCompatible>(&self, text: S) {
+        if let Some(background_task) = self {
+            background_task.set_progress_text(text);
+        }
+    }
+
+    fn finish(&self) {
+        if let Some(background_task) = self {
+            background_task.finish();
+        }
+    }
+}
+
+/// Whether analysis of the view is complete, otherwise the user is told to wait for it.
+///
+/// Signatures are generated from the existing analysis only, a partially analyzed view produces
+/// GUIDs and constraints that will not match the fully analyzed function.
+fn check_analysis_complete(view: &BinaryView) -> bool {
+    if is_analysis_complete(view) {
+        return true;
+    }
+    let message =
+        "Analysis is not complete, wait for analysis to finish before generating signatures.";
+    log::error!("{}", message);
+    show_message_box(
+        "Analysis Not Complete",
+        message,
+        MessageBoxButtonSet::OKButtonSet,
+        MessageBoxIcon::WarningIcon,
+    );
+    false
+}
+
+// Functions are matched in parallel, the matched info is applied one function at a time so that the
+// symbol, type and tag of a function are applied together. The types of the matched function are
+// defined under the same lock, see `Matcher::find_function_match_for_guid`.
+pub(crate) static APPLY_MATCHED_LOCK: Mutex<()> = Mutex::new(());
+
 // What happens to the function when it is matched.
-// TODO: add user: bool
+// Matches below the maximum confidence are applied as auto so that they never clobber user work.
+// Functions the user renamed, or matched with a higher confidence before, are left as-is, see `should_apply_match`.
 // TODO: Rename to markup_function or something.
-pub fn on_matched_function(function: &Function, matched: &WarpFunction) {
+pub fn on_matched_function(
+    function: &Function,
+    matched: &WarpFunction,
+    result: &MatchResult,
+    settings: &MatcherSettings,
+) {
+    let _guard = APPLY_MATCHED_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if !should_apply_match(function, result, settings.preserve_user_symbols) {
+        log::debug!(
+            "Not applying match {} to function at 0x{:x}, it was renamed or matched with a higher confidence",
+            result.name,
+            function.start()
+        );
+        return;
+    }
+    let confidence = settings.match_confidence;
     let view = function.view();
     // TODO: Using user symbols here is problematic
     // TODO: For one they queue up a bunch of main thread actions
     // TODO: Secondly by queueing up those main thread actions if you attempt to save the file
     // TODO: Before the undo actions are done completing
-    view.define_user_symbol(&to_bn_symbol_at_address(
-        &view,
-        &matched.symbol,
-        function.symbol().address(),
-    ));
-    function.set_user_type(&to_bn_type(&function.arch(), &matched.ty));
-    // TODO: Add metadata. (both binja metadata and warp metadata)
+    let symbol = to_bn_symbol_at_address(&view, &matched.symbol, function.symbol().address());
+    let mut ty = to_bn_type_with_confidence(&function.arch(), &matched.ty, confidence);
+    if is_no_return(matched) {
+        ty = ty
+            .to_builder()
+            .set_can_return(Conf::new(false, confidence))
+            .finalize();
+    }
+    if confidence < MAX_CONFIDENCE {
+        view.define_auto_symbol(&symbol);
+        if !function.has_user_type() {
+            function.set_auto_type(&ty);
+        }
+    } else {
+        view.define_user_symbol(&symbol);
+        function.set_user_type(&ty);
+    }
+    apply_function_attributes(function, &ty, settings);
+    // Record where the match came from, this is stored in the view metadata so that it persists.
+    store_match_result(&view, function.start(), result);
+    cache::record_matched_address(&view, function.start());
     function.add_tag(
         &get_warp_tag_type(&view),
-        matched.guid.to_string(),
+        match result.level {
+            Some(level) => format!("{} ({})", GuidString(matched.guid), level),
+            None => GuidString(matched.guid).to_string(),
+        },
         None,
         true,
         None,
@@ -66,7 +176,7 @@ struct DebugFunction;
 impl FunctionCommand for DebugFunction {
     fn action(&self, _view: &BinaryView, func: &Function) {
         if let Ok(llil) = func.low_level_il() {
-            log::info!("{:#?}", build_function(func, &llil));
+            log::info!("{:#?}", build_function(func, &llil, false));
         }
     }
 
@@ -86,7 +196,7 @@ impl FunctionCommand for DebugMatcher {
         let platform = function.platform();
         // Build the matcher every time this is called to make sure we aren't in a bad state.
         let matcher = Matcher::from_platform(platform);
-        let func = build_function(function, &llil);
+        let func = build_function(function, &llil, false);
         // TODO: Clean this up.
         if let Some(possible_matches) = matcher.functions.get(&func.guid) {
             let print_constraint = |prefix: &str, constraint: &FunctionConstraint| {
@@ -100,12 +210,16 @@ impl FunctionCommand for DebugMatcher {
                         .unwrap_or("*".to_string()),
                     constraint
                         .guid
-                        .map(|g| g.to_string())
+                        .map(|g| GuidString(g).to_string())
                         .unwrap_or("*".to_string())
                 );
             };
             for possible_match in possible_matches.value() {
-                log::info!("{} ({})", possible_match.symbol.name, possible_match.guid);
+                log::info!(
+                    "{} ({})",
+                    possible_match.symbol.name,
+                    GuidString(possible_match.guid)
+                );
                 for constraint in &possible_match.constraints.call_sites {
                     print_constraint("CS", constraint);
                 }
@@ -149,7 +263,8 @@ impl Command for DebugCache {
         let plat_cache = PLAT_MATCHER_CACHE.get_or_init(Default::default);
         if let Some(plat) = view.default_platform() {
             let platform_id = PlatformID::from(plat);
-            if let Some(cache) = plat_cache.get(&platform_id) {
+            let plat_matcher = plat_cache.get(&platform_id);
+            if let Some(cache) = plat_matcher.as_ref().and_then(|cell| cell.get()) {
                 log::info!("Platform functions: {}", cache.functions.len());
                 log::info!("Platform types: {}", cache.types.len());
                 log::info!("Platform settings: {:?}", cache.settings);
@@ -162,6 +277,20 @@ impl Command for DebugCache {
     }
 }
 
+struct DebugFunctionGuids;
+
+impl Command for DebugFunctionGuids {
+    fn action(&self, view: &BinaryView) {
+        for (addr, name, guid) in dump_function_guids(view) {
+            log::info!("0x{:x} {} {}", addr, name, GuidString(guid));
+        }
+    }
+
+    fn valid(&self, _view: &BinaryView) -> bool {
+        true
+    }
+}
+
 struct DebugInvalidateCache;
 
 impl Command for DebugInvalidateCache {
@@ -182,13 +311,17 @@ impl Command for DebugInvalidateCache {
 pub extern "C" fn CorePluginInit() -> bool {
     Logger::new("WARP").with_level(LevelFilter::Debug).init();
 
-    // Register our matcher settings.
+    // Register our matcher and GUID settings.
     MatcherSettings::register();
+    GuidOptions::register();
+    GenerationSettings::register();
 
     // Make sure caches are flushed when the views get destructed.
     register_cache_destructor();
 
-    workflow::insert_workflow();
+    if let Err(e) = workflow::insert_workflow() {
+        log::error!("Failed to insert WARP workflow: {}", e);
+    }
 
     binaryninja::command::register_command(
         "WARP\\Run Matcher",
@@ -196,12 +329,24 @@ pub extern "C" fn CorePluginInit() -> bool {
         workflow::RunMatcher {},
     );
 
+    binaryninja::command::register_command_for_function(
+        "WARP\\Match This Function",
+        "Run the matcher on the selected function only",
+        workflow::MatchFunction {},
+    );
+
     binaryninja::command::register_command(
         "WARP\\Debug\\Cache",
         "Debug cache sizes... because...",
         DebugCache {},
     );
 
+    binaryninja::command::register_command(
+        "WARP\\Debug\\Function GUIDs",
+        "Print the address, name and GUID of every function, sorted by address",
+        DebugFunctionGuids {},
+    );
+
     binaryninja::command::register_command(
         "WARP\\Debug\\Invalidate Caches",
         "Invalidate all WARP caches",
@@ -226,18 +371,42 @@ pub extern "C" fn CorePluginInit() -> bool {
         types::LoadTypes {},
     );
 
+    binaryninja::command::register_command(
+        "WARP\\Regenerate GUIDs",
+        "Regenerate the GUIDs of all functions, use after editing functions",
+        regenerate::RegenerateGuids {},
+    );
+
+    binaryninja::command::register_command_for_function(
+        "WARP\\Regenerate Function GUID",
+        "Regenerate the GUID of the function, use after editing the function",
+        regenerate::RegenerateFunctionGuid {},
+    );
+
     binaryninja::command::register_command(
         "WARP\\Load Signature File",
         "Load file into the matcher, this does NOT kick off matcher analysis",
         load::LoadSignatureFile {},
     );
 
+    binaryninja::command::register_command(
+        "WARP\\Apply Signature File Now",
+        "Match the functions against a signature file right away, without loading it into the matcher",
+        load::ApplySignatureFile {},
+    );
+
     binaryninja::command::register_command_for_function(
         "WARP\\Copy Function GUID",
         "Copy the computed GUID for the function",
         copy::CopyFunctionGUID {},
     );
 
+    binaryninja::command::register_command_for_function(
+        "WARP\\Show Function Constraints",
+        "Print the constraints computed for the function, with the GUID and symbol of each",
+        constraints::ShowFunctionConstraints {},
+    );
+
     binaryninja::command::register_command(
         "WARP\\Find Function From GUID",
         "Locate the function in the view using a GUID",
@@ -256,5 +425,11 @@ pub extern "C" fn CorePluginInit() -> bool {
         add::AddFunctionSignature {},
     );
 
+    binaryninja::command::register_command_for_function(
+        "WARP\\Remove Function Signature from File",
+        "Removes the signatures with the functions GUID from the signature file",
+        remove::RemoveFunctionSignature {},
+    );
+
     true
 }
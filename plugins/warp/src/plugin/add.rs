@@ -1,5 +1,7 @@
 use crate::cache::{cached_function, cached_type_references};
+use crate::data::DataExt;
 use crate::matcher::invalidate_function_matcher_cache;
+use crate::plugin::check_analysis_complete;
 use crate::user_signature_dir;
 use binaryninja::binary_view::BinaryView;
 use binaryninja::command::FunctionCommand;
@@ -10,12 +12,20 @@ pub struct AddFunctionSignature;
 
 impl FunctionCommand for AddFunctionSignature {
     fn action(&self, view: &BinaryView, func: &Function) {
+        if !check_analysis_complete(view) {
+            return;
+        }
         let func_plat_name = func.platform().name().to_string();
-        let signature_dir = user_signature_dir().join(func_plat_name);
+        let Some(signature_dir) = user_signature_dir() else {
+            log::error!("No user signature directory, cannot add function signature.");
+            return;
+        };
+        let signature_dir = signature_dir.join(func_plat_name);
         let view = view.to_owned();
         let func = func.to_owned();
         thread::spawn(move || {
-            let Ok(llil) = func.low_level_il() else {
+            // Use the IL of the existing analysis, generating it here could produce a different GUID.
+            let Some(llil) = func.low_level_il_if_available() else {
                 log::error!("Could not get low level IL for function.");
                 return;
             };
@@ -42,17 +52,11 @@ impl FunctionCommand for AddFunctionSignature {
                 data = file_data;
             };
 
-            // Now add our function to the data.
-            data.functions.push(cached_function(&func, &llil));
+            // Now add our function to the data, replacing the previous signature of the function.
+            data.upsert_function(cached_function(&func, &llil));
 
             if let Some(ref_ty_cache) = cached_type_references(&view) {
-                let referenced_types = ref_ty_cache
-                    .cache
-                    .iter()
-                    .filter_map(|t| t.to_owned())
-                    .collect::<Vec<_>>();
-
-                data.types.extend(referenced_types);
+                data.types.extend(ref_ty_cache.computed_types());
             }
 
             match std::fs::write(&save_file, data.to_bytes()) {
@@ -0,0 +1,37 @@
+use binaryninja::binary_view::BinaryView;
+use binaryninja::command::FunctionCommand;
+use binaryninja::function::Function;
+
+use crate::cache::cached_function;
+use crate::{build_byte_function, function_constraints_report, GuidString};
+
+pub struct ShowFunctionConstraints;
+
+impl FunctionCommand for ShowFunctionConstraints {
+    fn action(&self, _view: &BinaryView, func: &Function) {
+        let function = match func.low_level_il() {
+            Ok(llil) => cached_function(func, &llil),
+            // No LLIL (e.g. import thunks), fallback to the byte-only GUID.
+            Err(_) => match build_byte_function(func) {
+                Some(function) => function,
+                None => {
+                    log::error!(
+                        "Could not build signature for function 0x{:x}",
+                        func.start()
+                    );
+                    return;
+                }
+            },
+        };
+        log::info!(
+            "Constraints for {} ({})...\n{}",
+            func.symbol().short_name().to_string(),
+            GuidString(function.guid),
+            function_constraints_report(&function.constraints)
+        );
+    }
+
+    fn valid(&self, _view: &BinaryView, _func: &Function) -> bool {
+        true
+    }
+}
@@ -3,23 +3,35 @@ use binaryninja::command::FunctionCommand;
 use binaryninja::function::Function;
 
 use crate::cache::cached_function_guid;
+use crate::{basic_block_guid_with_options, sorted_basic_blocks, GuidOptions, GuidString};
 
 pub struct CopyFunctionGUID;
 
 impl FunctionCommand for CopyFunctionGUID {
-    fn action(&self, _view: &BinaryView, func: &Function) {
+    fn action(&self, view: &BinaryView, func: &Function) {
         let Ok(llil) = func.low_level_il() else {
             log::error!("Could not get low level il for copied function");
             return;
         };
         let guid = cached_function_guid(func, &llil);
+        let guid_str = GuidString(guid).to_string();
         log::info!(
             "Function GUID for {}... {}",
             func.symbol().short_name().to_string(),
-            guid
+            guid_str
         );
+        // Show the basic block GUIDs as well, this helps figure out which block differs between two functions.
+        let options = GuidOptions::from_view(view);
+        for basic_block in sorted_basic_blocks(func) {
+            let bb_guid = basic_block_guid_with_options(&basic_block, &llil, &options);
+            log::info!(
+                "  Basic block {:#x}... {}",
+                basic_block.start_index(),
+                GuidString(bb_guid)
+            );
+        }
         if let Ok(mut clipboard) = arboard::Clipboard::new() {
-            let _ = clipboard.set_text(guid.to_string());
+            let _ = clipboard.set_text(guid_str);
         }
     }
 
@@ -1,10 +1,16 @@
 use crate::cache::{cached_function, cached_type_references};
-use crate::matcher::invalidate_function_matcher_cache;
-use crate::user_signature_dir;
+use crate::data::DataExt;
+use crate::matcher::{add_to_signature_blacklist, invalidate_function_matcher_cache};
+use crate::plugin::{check_analysis_complete, optional_background_task, OptionalBackgroundTask};
+use crate::{
+    build_byte_function, check_named_functions, should_signature_function, user_signature_dir,
+    GenerationSettings, MissingNamedFunctions,
+};
 use binaryninja::binary_view::{BinaryView, BinaryViewExt};
 use binaryninja::command::Command;
-use binaryninja::function::Function;
-use binaryninja::rc::Guard;
+use binaryninja::interaction::{
+    show_message_box, MessageBoxButtonResult, MessageBoxButtonSet, MessageBoxIcon,
+};
 use rayon::prelude::*;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering::Relaxed;
@@ -13,29 +19,57 @@ use std::time::Instant;
 
 pub struct CreateSignatureFile;
 
-// TODO: Prompt the user to add the newly created signature file to the signature blacklist (so that it doesn't keep getting applied)
-
 impl Command for CreateSignatureFile {
     fn action(&self, view: &BinaryView) {
-        let is_function_named = |f: &Guard<Function>| {
-            !f.symbol().short_name().as_str().contains("sub_") || f.has_user_annotations()
+        if !check_analysis_complete(view) {
+            return;
+        }
+        let Some(mut signature_dir) = user_signature_dir() else {
+            log::error!("No user signature directory, cannot create signature file.");
+            return;
         };
-        let mut signature_dir = user_signature_dir();
         if let Some(default_plat) = view.default_platform() {
             // If there is a default platform, put the signature in there.
             // TODO: We should instead use the platform of the function.
             signature_dir.push(default_plat.name().to_string());
         }
+        // The platform subdirectory might not exist yet, especially for a configured signature directory.
+        if let Err(e) = std::fs::create_dir_all(&signature_dir) {
+            log::warn!(
+                "Failed to create signature directory {:?}: {}",
+                signature_dir,
+                e
+            );
+        }
+        // Tell the user why, instead of writing a signature file without any functions.
+        match check_named_functions(view) {
+            Err(MissingNamedFunctions::NoFunctions) => {
+                log::error!("No functions found, wait for analysis to complete before generating signatures.");
+                return;
+            }
+            Err(MissingNamedFunctions::NoNamedFunctions(count)) => {
+                log::error!(
+                    "None of the {} functions are named (stripped binary?), name or annotate the functions to include them, see `{}`.",
+                    count,
+                    GenerationSettings::INCLUDE_SYMBOL_TYPES_SETTING
+                );
+                return;
+            }
+            Ok(_) => {}
+        }
+
         let view = view.to_owned();
         thread::spawn(move || {
             let total_functions = view.functions().len();
             let done_functions = AtomicUsize::default();
-            let background_task = binaryninja::background_task::BackgroundTask::new(
+            let background_task = optional_background_task(
                 format!("Generating signatures... ({}/{})", 0, total_functions),
                 true,
             );
 
             let start = Instant::now();
+            let generation_settings = GenerationSettings::from_view(&view);
+            let too_large_functions = AtomicUsize::default();
 
             let mut data = warp::signature::Data::default();
             data.functions.par_extend(
@@ -43,33 +77,50 @@ impl Command for CreateSignatureFile {
                     .par_iter()
                     .inspect(|_| {
                         done_functions.fetch_add(1, Relaxed);
-                        background_task.set_progress_text(format!(
+                        background_task.set_progress(format!(
                             "Generating signatures... ({}/{})",
                             done_functions.load(Relaxed),
                             total_functions
                         ))
                     })
-                    .filter(is_function_named)
-                    .filter(|f| !f.analysis_skipped())
-                    .filter_map(|func| {
-                        let llil = func.low_level_il().ok()?;
-                        Some(cached_function(&func, &llil))
+                    // Cheap predicates first so that LLIL is only retrieved for functions we keep.
+                    .filter(|f| should_signature_function(f, false, &generation_settings))
+                    .filter(|f| {
+                        let too_large = generation_settings.is_function_too_large(f);
+                        if too_large {
+                            too_large_functions.fetch_add(1, Relaxed);
+                        }
+                        !too_large
+                    })
+                    // Only the existing IL is used, this must never trigger analysis of the function.
+                    .filter_map(|func| match func.low_level_il_if_available() {
+                        Some(llil) => Some(cached_function(&func, &llil)),
+                        // No LLIL (e.g. import thunks), fallback to the byte-only GUID.
+                        None => build_byte_function(&func),
                     }),
             );
 
             if let Some(ref_ty_cache) = cached_type_references(&view) {
-                let referenced_types = ref_ty_cache
-                    .cache
-                    .iter()
-                    .filter_map(|t| t.to_owned())
-                    .collect::<Vec<_>>();
-
-                data.types.extend(referenced_types);
+                data.types.extend(ref_ty_cache.computed_types());
             }
 
             log::info!("Signature generation took {:?}", start.elapsed());
+            let too_large_functions = too_large_functions.load(Relaxed);
+            if too_large_functions > 0 {
+                log::info!(
+                    "Skipped {} functions larger than {} bytes, see `{}`",
+                    too_large_functions,
+                    generation_settings.max_function_size.unwrap_or_default(),
+                    GenerationSettings::MAX_FUNCTION_SIZE_SETTING
+                );
+            }
             background_task.finish();
 
+            if data.is_empty() {
+                log::warn!("No functions or types to save, not creating a signature file.");
+                return;
+            }
+
             // NOTE: Because we only can consume signatures from a specific directory, we don't need to use the interaction API.
             // If we did need to save signature files to a project than this would need to change.
             let Some(save_file) = rfd::FileDialog::new()
@@ -84,6 +135,16 @@ impl Command for CreateSignatureFile {
             match std::fs::write(&save_file, data.to_bytes()) {
                 Ok(_) => {
                     log::info!("Signature file saved successfully.");
+                    // Otherwise the new signatures are matched against the view they were created from.
+                    let blacklist = show_message_box(
+                        "Blacklist Signature File",
+                        "Exclude the new signature file from matching? Matching it against the binary it was created from only produces circular matches.",
+                        MessageBoxButtonSet::YesNoButtonSet,
+                        MessageBoxIcon::QuestionIcon,
+                    );
+                    if blacklist == MessageBoxButtonResult::YesButton {
+                        add_to_signature_blacklist(&save_file);
+                    }
                     // Force rebuild platform matcher.
                     invalidate_function_matcher_cache();
                 }
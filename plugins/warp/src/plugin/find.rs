@@ -1,4 +1,6 @@
 use crate::cache::try_cached_function_guid;
+use crate::plugin::{optional_background_task, OptionalBackgroundTask};
+use crate::GuidString;
 use binaryninja::binary_view::{BinaryView, BinaryViewExt};
 use binaryninja::command::Command;
 use binaryninja::function::Function as BNFunction;
@@ -18,16 +20,22 @@ impl Command for FindFunctionFromGUID {
             return;
         };
 
-        let Ok(searched_guid) = guid_str.parse::<FunctionGUID>() else {
+        let Ok(GuidString(searched_guid)) = guid_str.parse::<GuidString<FunctionGUID>>() else {
             log::error!("Failed to parse function guid... {}", guid_str);
             return;
         };
 
-        log::info!("Searching functions for GUID... {}", searched_guid);
+        log::info!(
+            "Searching functions for GUID... {}",
+            GuidString(searched_guid)
+        );
         let funcs = view.functions();
         thread::spawn(move || {
-            let background_task = binaryninja::background_task::BackgroundTask::new(
-                format!("Searching functions for GUID... {}", searched_guid),
+            let background_task = optional_background_task(
+                format!(
+                    "Searching functions for GUID... {}",
+                    GuidString(searched_guid)
+                ),
                 false,
             );
 
@@ -40,7 +48,7 @@ impl Command for FindFunctionFromGUID {
                 .collect::<Vec<BNGuard<BNFunction>>>();
 
             if matched.is_empty() {
-                log::info!("No matches found for GUID... {}", searched_guid);
+                log::info!("No matches found for GUID... {}", GuidString(searched_guid));
             } else {
                 for func in matched {
                     log::info!("Match found at function... 0x{:0x}", func.start());
@@ -1,6 +1,10 @@
-use crate::matcher::{Matcher, PlatformID, PLAT_MATCHER_CACHE};
+use crate::match_result::GuidLevel;
+use crate::matcher::{match_view_with_files, with_platform_matcher, Matcher};
+use crate::plugin::{optional_background_task, OptionalBackgroundTask};
 use binaryninja::binary_view::{BinaryView, BinaryViewExt};
 use binaryninja::command::Command;
+use std::thread;
+
 pub struct LoadSignatureFile;
 
 impl Command for LoadSignatureFile {
@@ -36,18 +40,54 @@ impl Command for LoadSignatureFile {
             new_matcher.functions.len(),
             new_matcher.types.len()
         );
-        let platform_id = PlatformID::from(platform.as_ref());
-        let matcher_cache = PLAT_MATCHER_CACHE.get_or_init(Default::default);
-        match matcher_cache.get_mut(&platform_id) {
-            Some(mut matcher) => matcher.extend_with_matcher(new_matcher),
-            None => {
-                // We still must uphold `from_platform` in case we are running this before the matcher workflow
-                // is kicked off. Other-wise we only will have the `new_matcher` data.
-                let mut matcher = Matcher::from_platform(platform);
-                matcher.extend_with_matcher(new_matcher);
-                matcher_cache.insert(platform_id, matcher);
-            }
-        }
+        // The platform matcher is built first in case we are running this before the matcher workflow
+        // is kicked off. Other-wise we only will have the `new_matcher` data.
+        with_platform_matcher(&platform, |matcher| {
+            matcher.extend_with_matcher(new_matcher)
+        });
+    }
+
+    fn valid(&self, _view: &BinaryView) -> bool {
+        true
+    }
+}
+
+/// Match the functions of the view against a single signature file, right away.
+///
+/// Unlike [`LoadSignatureFile`] the platform matcher is left as-is, the signature file is only used
+/// for this one run. Useful to check a freshly created signature file without copying it into the
+/// signature directory and re-running the matcher.
+pub struct ApplySignatureFile;
+
+impl Command for ApplySignatureFile {
+    fn action(&self, view: &BinaryView) {
+        // NOTE: Because we only can consume signatures from a specific directory, we don't need to use the interaction API.
+        // If we did need to load signature files from a project than this would need to change.
+        let Some(file) = rfd::FileDialog::new()
+            .add_filter("Signature Files", &["sbin"])
+            .pick_file()
+        else {
+            return;
+        };
+
+        let view = view.to_owned();
+        thread::spawn(move || {
+            let background_task = optional_background_task("Applying signature file...", false);
+            let undo_id = view.file().begin_undo_actions(true);
+            // The GUIDs are computed as needed, functions the platform matcher has not visited yet
+            // have no cached GUID. The HLIL GUID is left out, it is too slow to compute for every function.
+            let matched_functions =
+                match_view_with_files(&view, &[file.clone()], &[GuidLevel::Llil, GuidLevel::Bytes]);
+            view.file().commit_undo_actions(undo_id);
+            background_task.finish();
+            log::info!(
+                "Matched {} of {} functions against {:?}",
+                matched_functions,
+                view.functions().len(),
+                file
+            );
+            view.update_analysis();
+        });
     }
 
     fn valid(&self, _view: &BinaryView) -> bool {
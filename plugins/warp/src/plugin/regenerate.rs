@@ -0,0 +1,74 @@
+use crate::cache::{
+    cached_function_guid_or_bytes, invalidate_function_guid, invalidate_view_guids,
+};
+use crate::matcher::invalidate_function_matcher_cache;
+use crate::plugin::{optional_background_task, OptionalBackgroundTask};
+use binaryninja::binary_view::{BinaryView, BinaryViewExt};
+use binaryninja::command::{Command, FunctionCommand};
+use binaryninja::function::Function;
+use rayon::prelude::*;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
+use std::thread;
+use std::time::Instant;
+
+/// Regenerate the GUIDs of all functions, for when the cached GUIDs are stale after manual edits.
+///
+/// The GUID activity only runs once per function, so it will not pick up the edits by itself.
+pub struct RegenerateGuids;
+
+impl Command for RegenerateGuids {
+    fn action(&self, view: &BinaryView) {
+        let view = view.to_owned();
+        thread::spawn(move || {
+            invalidate_view_guids(&view);
+            let functions = view.functions();
+            let total_functions = functions.len();
+            let done_functions = AtomicUsize::default();
+            let background_task = optional_background_task(
+                format!("Regenerating GUIDs... ({}/{})", 0, total_functions),
+                false,
+            );
+            let start = Instant::now();
+            functions.par_iter().for_each(|function| {
+                regenerate_function_guid(&function);
+                done_functions.fetch_add(1, Relaxed);
+                background_task.set_progress(format!(
+                    "Regenerating GUIDs... ({}/{})",
+                    done_functions.load(Relaxed),
+                    total_functions
+                ));
+            });
+            // Matches must be recomputed against the new GUIDs.
+            invalidate_function_matcher_cache();
+            log::info!("GUID regeneration took {:?}", start.elapsed());
+            background_task.finish();
+        });
+    }
+
+    fn valid(&self, _view: &BinaryView) -> bool {
+        true
+    }
+}
+
+/// Regenerate the GUID of the selected function, see [`RegenerateGuids`].
+pub struct RegenerateFunctionGuid;
+
+impl FunctionCommand for RegenerateFunctionGuid {
+    fn action(&self, _view: &BinaryView, func: &Function) {
+        invalidate_function_guid(func);
+        regenerate_function_guid(func);
+        invalidate_function_matcher_cache();
+    }
+
+    fn valid(&self, _view: &BinaryView, _func: &Function) -> bool {
+        true
+    }
+}
+
+/// Compute the GUID of the function and cache it, the previous GUID must be invalidated first.
+pub(crate) fn regenerate_function_guid(function: &Function) {
+    // No LLIL (e.g. import thunks), fallback to the byte-only GUID.
+    let llil = function.low_level_il().ok();
+    cached_function_guid_or_bytes(function, llil.as_deref());
+}
@@ -0,0 +1,87 @@
+use crate::build_byte_function;
+use crate::cache::cached_function;
+use crate::data::DataExt;
+use crate::matcher::invalidate_function_matcher_cache;
+use crate::{read_signature_file, user_signature_dir, GuidString};
+use binaryninja::binary_view::BinaryView;
+use binaryninja::command::FunctionCommand;
+use binaryninja::function::Function;
+use std::thread;
+
+pub struct RemoveFunctionSignature;
+
+impl FunctionCommand for RemoveFunctionSignature {
+    fn action(&self, _view: &BinaryView, func: &Function) {
+        let func_plat_name = func.platform().name().to_string();
+        let Some(signature_dir) = user_signature_dir() else {
+            log::error!("No user signature directory, cannot remove function signature.");
+            return;
+        };
+        let signature_dir = signature_dir.join(func_plat_name);
+        let func = func.to_owned();
+        thread::spawn(move || {
+            // Use the IL of the existing analysis, generating it here could produce a different GUID.
+            let function = match func.low_level_il_if_available() {
+                Some(llil) => cached_function(&func, &llil),
+                // No LLIL (e.g. import thunks), fallback to the byte-only GUID.
+                None => match build_byte_function(&func) {
+                    Some(function) => function,
+                    None => {
+                        log::error!("Could not build signature for function.");
+                        return;
+                    }
+                },
+            };
+            let (guid, name) = (function.guid, function.symbol.name);
+
+            let Some(sig_file) = rfd::FileDialog::new()
+                .add_filter("Signature Files", &["sbin"])
+                .set_directory(signature_dir)
+                .pick_file()
+            else {
+                return;
+            };
+
+            let mut data = match read_signature_file(&sig_file) {
+                Ok(Some(data)) => data,
+                Ok(None) => {
+                    log::error!("Could not get data from signature file: {:?}", sig_file);
+                    return;
+                }
+                Err(e) => {
+                    log::error!("Could not read signature file: {:?}", e);
+                    return;
+                }
+            };
+
+            // Only the signature of this function, colliding functions with another name are kept.
+            let removed = data.remove_function(guid, &name);
+            if removed == 0 {
+                log::warn!(
+                    "No function signature for {} with GUID {} in {:?}",
+                    name,
+                    GuidString(guid),
+                    sig_file
+                );
+                return;
+            }
+
+            match std::fs::write(&sig_file, data.to_bytes()) {
+                Ok(_) => {
+                    log::info!(
+                        "Removed {} function signature(s) from {:?}",
+                        removed,
+                        sig_file
+                    );
+                    // Force rebuild platform matcher.
+                    invalidate_function_matcher_cache();
+                }
+                Err(e) => log::error!("Failed to write data to signature file: {:?}", e),
+            }
+        });
+    }
+
+    fn valid(&self, _view: &BinaryView, _func: &Function) -> bool {
+        true
+    }
+}
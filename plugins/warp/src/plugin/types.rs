@@ -1,4 +1,5 @@
 use crate::convert::to_bn_type;
+use crate::plugin::{optional_background_task, OptionalBackgroundTask};
 use binaryninja::binary_view::{BinaryView, BinaryViewExt};
 use binaryninja::command::Command;
 use std::time::Instant;
@@ -34,10 +35,8 @@ impl Command for LoadTypes {
 
         let view = view.to_owned();
         std::thread::spawn(move || {
-            let background_task = binaryninja::background_task::BackgroundTask::new(
-                format!("Applying {} types...", data.types.len()),
-                true,
-            );
+            let background_task =
+                optional_background_task(format!("Applying {} types...", data.types.len()), true);
 
             let start = Instant::now();
             for comp_ty in data.types {
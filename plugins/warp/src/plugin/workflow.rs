@@ -1,11 +1,18 @@
-use crate::cache::cached_function_guid;
-use crate::matcher::cached_function_matcher;
-use binaryninja::background_task::BackgroundTask;
+use crate::cache::{
+    cached_function_guid_or_bytes, invalidate_function_guid, try_cached_function_match,
+};
+use crate::match_result_for;
+use crate::matcher::{build_view_platform_matchers, cached_function_matcher};
+use crate::plugin::regenerate::regenerate_function_guid;
+use crate::plugin::{optional_background_task, OptionalBackgroundTask};
 use binaryninja::binary_view::{BinaryView, BinaryViewExt};
-use binaryninja::command::Command;
+use binaryninja::command::{Command, FunctionCommand};
+use binaryninja::function::Function;
 use binaryninja::low_level_il::function::RegularNonSSA;
 use binaryninja::workflow::{Activity, AnalysisContext, Workflow};
+use rayon::prelude::*;
 use std::time::Instant;
+use thiserror::Error;
 
 pub const MATCHER_ACTIVITY_NAME: &str = "analysis.warp.matcher";
 const MATCHER_ACTIVITY_CONFIG: &str = r#"{
@@ -35,65 +42,129 @@ impl Command for RunMatcher {
     fn action(&self, view: &BinaryView) {
         let view = view.to_owned();
         // TODO: Check to see if the GUID cache is empty and ask the user if they want to regenerate the guids.
+        std::thread::spawn(move || run_matcher(&view));
+    }
+
+    fn valid(&self, _view: &BinaryView) -> bool {
+        true
+    }
+}
+
+/// Match only the selected function, much faster than [`RunMatcher`] when iterating on a single function.
+///
+/// The GUID of the function is regenerated first, so that edits to the function are picked up and
+/// the function can be matched before the GUID activity has run.
+pub struct MatchFunction;
+
+impl FunctionCommand for MatchFunction {
+    fn action(&self, view: &BinaryView, func: &Function) {
+        let view = view.to_owned();
+        let func = func.to_owned();
+        // Building the platform matcher the first time can take a while.
         std::thread::spawn(move || {
+            invalidate_function_guid(&func);
+            regenerate_function_guid(&func);
             let undo_id = view.file().begin_undo_actions(true);
-            let background_task = BackgroundTask::new("Matching on functions...", false);
-            let start = Instant::now();
-            view.functions()
-                .iter()
-                .for_each(|function| cached_function_matcher(&function));
-            log::info!("Function matching took {:?}", start.elapsed());
-            background_task.finish();
+            cached_function_matcher(&func);
             view.file().commit_undo_actions(undo_id);
-            // Now we want to trigger re-analysis.
-            view.update_analysis();
+            match try_cached_function_match(&func) {
+                Some(matched) => {
+                    let confidence = match_result_for(&view, func.start())
+                        .map(|result| result.confidence.to_string())
+                        .unwrap_or_else(|| "unknown".to_string());
+                    log::info!(
+                        "Function at 0x{:x} matched {} (confidence {})",
+                        func.start(),
+                        matched.symbol.name,
+                        confidence
+                    );
+                    view.update_analysis();
+                }
+                None => log::info!("Function at 0x{:x} has no match", func.start()),
+            }
         });
     }
 
-    fn valid(&self, _view: &BinaryView) -> bool {
+    fn valid(&self, _view: &BinaryView, _func: &Function) -> bool {
         true
     }
 }
 
-pub fn insert_workflow() {
+/// Match on all functions in the view, then trigger re-analysis.
+///
+/// Functions are matched in parallel, each function is independent and the shared matcher state
+/// is behind a [`dashmap::DashMap`], applying the matched info to the view is serialized in
+/// [`crate::plugin::on_matched_function`]. The matchers are built before, outside the rayon pool.
+pub fn run_matcher(view: &BinaryView) {
+    let undo_id = view.file().begin_undo_actions(true);
+    let background_task = optional_background_task("Matching on functions...", false);
+    build_view_platform_matchers(view);
+    let start = Instant::now();
+    view.functions()
+        .par_iter()
+        .for_each(|function| cached_function_matcher(&function));
+    log::info!("Function matching took {:?}", start.elapsed());
+    background_task.finish();
+    view.file().commit_undo_actions(undo_id);
+    // Now we want to trigger re-analysis.
+    view.update_analysis();
+}
+
+/// Why the WARP activities could not be inserted, see [`insert_workflow`].
+#[derive(Error, Debug)]
+pub enum WorkflowError {
+    #[error("Failed to register activity {0}")]
+    RegisterActivity(&'static str),
+    #[error("Failed to register workflow {0}")]
+    RegisterWorkflow(&'static str),
+}
+
+/// Insert the WARP activities into the function and module meta-analysis workflows.
+///
+/// If an activity is already part of its workflow (e.g. the plugin was reloaded) it is left as-is
+/// instead of being registered again.
+pub fn insert_workflow() -> Result<(), WorkflowError> {
     let matcher_activity = |ctx: &AnalysisContext| {
-        let view = ctx.view();
-        let undo_id = view.file().begin_undo_actions(true);
-        let background_task = BackgroundTask::new("Matching on functions...", false);
-        let start = Instant::now();
-        view.functions()
-            .iter()
-            .for_each(|function| cached_function_matcher(&function));
-        log::info!("Function matching took {:?}", start.elapsed());
-        background_task.finish();
-        view.file().commit_undo_actions(undo_id);
-        // Now we want to trigger re-analysis.
-        view.update_analysis();
+        run_matcher(&ctx.view());
     };
 
     let guid_activity = |ctx: &AnalysisContext| {
         let function = ctx.function();
         // TODO: Returning RegularNonSSA means we cant modify the il (the lifting code was written just for lifted il, that needs to be fixed)
-        if let Some(llil) = unsafe { ctx.llil_function::<RegularNonSSA>() } {
-            cached_function_guid(&function, &llil);
-        }
+        let llil = unsafe { ctx.llil_function::<RegularNonSSA>() };
+        // No LLIL (e.g. import thunks), fallback to the byte-only GUID.
+        cached_function_guid_or_bytes(&function, llil.as_deref());
     };
 
     let old_function_meta_workflow = Workflow::instance("core.function.metaAnalysis");
-    let function_meta_workflow = old_function_meta_workflow.clone("core.function.metaAnalysis");
-    let guid_activity = Activity::new_with_action(GUID_ACTIVITY_CONFIG, guid_activity);
-    function_meta_workflow
-        .register_activity(&guid_activity)
-        .unwrap();
-    function_meta_workflow.insert("core.function.runFunctionRecognizers", [GUID_ACTIVITY_NAME]);
-    function_meta_workflow.register().unwrap();
+    if old_function_meta_workflow.contains(GUID_ACTIVITY_NAME) {
+        log::debug!("{} already registered, skipping...", GUID_ACTIVITY_NAME);
+    } else {
+        let function_meta_workflow = old_function_meta_workflow.clone("core.function.metaAnalysis");
+        let guid_activity = Activity::new_with_action(GUID_ACTIVITY_CONFIG, guid_activity);
+        function_meta_workflow
+            .register_activity(&guid_activity)
+            .map_err(|_| WorkflowError::RegisterActivity(GUID_ACTIVITY_NAME))?;
+        function_meta_workflow.insert("core.function.runFunctionRecognizers", [GUID_ACTIVITY_NAME]);
+        function_meta_workflow
+            .register()
+            .map_err(|_| WorkflowError::RegisterWorkflow("core.function.metaAnalysis"))?;
+    }
 
     let old_module_meta_workflow = Workflow::instance("core.module.metaAnalysis");
-    let module_meta_workflow = old_module_meta_workflow.clone("core.module.metaAnalysis");
-    let matcher_activity = Activity::new_with_action(MATCHER_ACTIVITY_CONFIG, matcher_activity);
-    module_meta_workflow
-        .register_activity(&matcher_activity)
-        .unwrap();
-    module_meta_workflow.insert("core.module.notifyCompletion", [MATCHER_ACTIVITY_NAME]);
-    module_meta_workflow.register().unwrap();
+    if old_module_meta_workflow.contains(MATCHER_ACTIVITY_NAME) {
+        log::debug!("{} already registered, skipping...", MATCHER_ACTIVITY_NAME);
+    } else {
+        let module_meta_workflow = old_module_meta_workflow.clone("core.module.metaAnalysis");
+        let matcher_activity = Activity::new_with_action(MATCHER_ACTIVITY_CONFIG, matcher_activity);
+        module_meta_workflow
+            .register_activity(&matcher_activity)
+            .map_err(|_| WorkflowError::RegisterActivity(MATCHER_ACTIVITY_NAME))?;
+        module_meta_workflow.insert("core.module.notifyCompletion", [MATCHER_ACTIVITY_NAME]);
+        module_meta_workflow
+            .register()
+            .map_err(|_| WorkflowError::RegisterWorkflow("core.module.metaAnalysis"))?;
+    }
+
+    Ok(())
 }
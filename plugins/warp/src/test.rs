@@ -0,0 +1,95 @@
+//! Helpers for testing signatures end-to-end, enabled with the `test` feature.
+
+use crate::cache::cached_function;
+use crate::matcher::Matcher;
+use crate::{should_signature_function, GenerationSettings};
+use binaryninja::binary_view::{BinaryView, BinaryViewExt};
+use binaryninja::file_metadata::FileMetadata;
+use binaryninja::function::Function as BNFunction;
+use binaryninja::headless::Session;
+use binaryninja::platform::Platform;
+use binaryninja::rc::Ref as BNRef;
+use std::sync::OnceLock;
+use warp::signature::Data;
+
+static SESSION: OnceLock<Session> = OnceLock::new();
+
+/// The headless session shared by all tests, initialized by the first test to use it.
+pub fn session() -> &'static Session {
+    SESSION.get_or_init(|| Session::new().expect("Failed to initialize session"))
+}
+
+/// Create a `linux-x86_64` view of `code` with a user function at each of `addresses`.
+///
+/// Analysis is complete on return, the functions are returned in the order of `addresses`.
+pub fn view_with_functions<const N: usize>(
+    code: &[u8],
+    addresses: [u64; N],
+) -> (BNRef<BinaryView>, [BNRef<BNFunction>; N]) {
+    let view = BinaryView::from_data(&FileMetadata::new(), code).expect("Failed to create view");
+    let platform = Platform::by_name("linux-x86_64").expect("Failed to get platform");
+    let functions = addresses.map(|address| {
+        view.create_user_function(&platform, address)
+            .expect("Failed to create function")
+    });
+    view.update_analysis_and_wait();
+    (view, functions)
+}
+
+/// Same as [`view_with_functions`] with a single function at the start of `code`.
+pub fn view_with_function(code: &[u8]) -> (BNRef<BinaryView>, BNRef<BNFunction>) {
+    let (view, [function]) = view_with_functions(code, [0]);
+    (view, function)
+}
+
+/// Generate signatures for the named functions of `view`, write and read them back and check that
+/// every named function matches itself.
+///
+/// A function matches itself if its GUID resolves to a function with the same name. When there
+/// are multiple candidates for the GUID the constraints must not pick another function, a tie is
+/// not considered a mismatch. Every mismatch is logged, so the caller only has to assert the result.
+pub fn roundtrip(view: &BinaryView) -> bool {
+    let generation_settings = GenerationSettings::from_view(view);
+    let functions: Vec<_> = view
+        .functions()
+        .iter()
+        .filter(|f| should_signature_function(f, false, &generation_settings))
+        .filter_map(|f| {
+            let llil = f.low_level_il().ok()?;
+            Some((f.to_owned(), cached_function(&f, &llil)))
+        })
+        .collect();
+
+    let mut data = Data::default();
+    data.functions = functions.iter().map(|(_, func)| func.to_owned()).collect();
+    let Some(data) = Data::from_bytes(&data.to_bytes()) else {
+        log::error!(
+            "Failed to read back the signatures of {}",
+            view.file().filename()
+        );
+        return false;
+    };
+    let matcher = Matcher::from_data(data);
+
+    let mut matched = true;
+    for (bn_func, func) in &functions {
+        let name = &func.symbol.name;
+        let Some(candidates) = matcher.functions.get(&func.guid) else {
+            log::error!("No signature for {} at 0x{:x}", name, bn_func.start());
+            matched = false;
+            continue;
+        };
+        let is_self_matched = match candidates.as_slice() {
+            [candidate] => &candidate.symbol.name == name,
+            _ if !candidates.iter().any(|c| &c.symbol.name == name) => false,
+            _ => matcher
+                .match_function_from_constraints(bn_func, &candidates)
+                .is_none_or(|c| &c.symbol.name == name),
+        };
+        if !is_self_matched {
+            log::error!("{} at 0x{:x} did not match itself", name, bn_func.start());
+            matched = false;
+        }
+    }
+    matched
+}
@@ -0,0 +1,39 @@
+use binaryninja::disassembly::{
+    DisassemblyTextLine, InstructionTextToken, InstructionTextTokenKind,
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::sync::Arc;
+
+/// Build a line similar to a struct heavy HLIL line, e.g. `a->b.c->d.e = f->g.h;`
+fn struct_heavy_line(field_count: usize) -> DisassemblyTextLine {
+    let mut tokens = Vec::with_capacity(field_count * 2);
+    for i in 0..field_count {
+        tokens.push(InstructionTextToken::new(
+            "->",
+            InstructionTextTokenKind::Text,
+        ));
+        tokens.push(InstructionTextToken::new(
+            format!("field_{}", i),
+            InstructionTextTokenKind::FieldName {
+                offset: i as u64 * 8,
+                type_names: Arc::from([format!("struct_{}", i), format!("field_{}", i)]),
+            },
+        ));
+    }
+    DisassemblyTextLine::new(tokens)
+}
+
+pub fn disassembly_benchmark(c: &mut Criterion) {
+    let line = struct_heavy_line(64);
+    c.bench_function("clone struct heavy line", |b| {
+        b.iter(|| line.clone());
+    });
+
+    let lines = (0..256).map(|_| struct_heavy_line(16)).collect::<Vec<_>>();
+    c.bench_function("clone struct heavy lines", |b| {
+        b.iter(|| lines.to_vec());
+    });
+}
+
+criterion_group!(benches, disassembly_benchmark);
+criterion_main!(benches);
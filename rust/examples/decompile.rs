@@ -11,7 +11,8 @@ fn decompile_to_c(view: &BinaryView, func: &Function) {
     settings.set_option(DisassemblyOption::ShowCollapseIndicators, false);
     settings.set_option(DisassemblyOption::ShowFunctionHeader, false);
 
-    let linear_view = LinearViewObject::language_representation(view, &settings, "Pseudo C");
+    let linear_view = LinearViewObject::language_representation(view, &settings, "Pseudo C")
+        .expect("Pseudo C language representation is not registered");
 
     let mut cursor = linear_view.create_cursor();
     cursor.seek_to_address(func.highest_address());
@@ -43,12 +43,22 @@ impl BackgroundTask {
         Self { handle }
     }
 
+    /// Begin a new background task.
+    ///
+    /// Panics if the core fails to create the task, use [`BackgroundTask::try_new`] to handle that.
     pub fn new<S: BnStrCompatible>(initial_text: S, can_cancel: bool) -> Ref<Self> {
+        // We should always be returned a valid task.
+        Self::try_new(initial_text, can_cancel).expect("Failed to create background task")
+    }
+
+    /// Begin a new background task, `None` if the core fails to create the task.
+    pub fn try_new<S: BnStrCompatible>(initial_text: S, can_cancel: bool) -> Option<Ref<Self>> {
         let text = initial_text.into_bytes_with_nul();
         let handle = unsafe { BNBeginBackgroundTask(text.as_ref().as_ptr() as *mut _, can_cancel) };
-        // We should always be returned a valid task.
-        assert!(!handle.is_null());
-        unsafe { Ref::new(Self { handle }) }
+        match handle.is_null() {
+            false => Some(unsafe { Ref::new(Self { handle }) }),
+            true => None,
+        }
     }
 
     pub fn can_cancel(&self) -> bool {
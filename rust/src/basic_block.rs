@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::architecture::CoreArchitecture;
+use crate::disassembly::{DisassemblySettings, DisassemblyTextLine};
 use crate::function::Function;
 use crate::rc::*;
 use crate::BranchType;
@@ -33,6 +34,13 @@ pub struct Edge<'a, C: 'a + BlockContext> {
     target: Guard<'a, BasicBlock<C>>,
 }
 
+impl<'a, C: 'a + BlockContext> Edge<'a, C> {
+    /// The basic block the edge goes to.
+    pub fn target(&self) -> &BasicBlock<C> {
+        &self.target
+    }
+}
+
 impl<'a, C: 'a + fmt::Debug + BlockContext> fmt::Debug for Edge<'a, C> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -194,6 +202,15 @@ impl<C: BlockContext> BasicBlock<C> {
         }
     }
 
+    /// The lines of the basic block as shown in the graph view.
+    pub fn disassembly_text(&self, settings: &DisassemblySettings) -> Array<DisassemblyTextLine> {
+        unsafe {
+            let mut count = 0;
+            let lines = BNGetBasicBlockDisassemblyText(self.handle, settings.handle, &mut count);
+            Array::new(lines, count, ())
+        }
+    }
+
     // is this valid for il blocks? (it looks like up to MLIL it is)
     pub fn has_undetermined_outgoing_edges(&self) -> bool {
         unsafe { BNBasicBlockHasUndeterminedOutgoingEdges(self.handle) }
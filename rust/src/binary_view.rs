@@ -1029,6 +1029,22 @@ pub trait BinaryViewExt: BinaryViewBase {
         }
     }
 
+    /// The disassembly text of the single instruction at `addr`, the text of its tokens joined.
+    ///
+    /// The instruction is disassembled with the architecture of the function containing `addr`,
+    /// or the default architecture if `addr` is executable but not in a function. `None` if `addr`
+    /// is not code or does not decode to an instruction.
+    fn instruction_text_at(&self, addr: u64) -> Option<String> {
+        let arch = match self.functions_containing(addr).iter().next() {
+            Some(function) => function.arch(),
+            None if self.offset_executable(addr) => self.default_arch()?,
+            None => return None,
+        };
+        let data = self.read_vec(addr, arch.max_instr_len());
+        let (_, tokens) = arch.instruction_text(&data, addr)?;
+        Some(tokens.iter().map(|token| token.text.as_str()).collect())
+    }
+
     fn function_at(&self, platform: &Platform, addr: u64) -> Option<Ref<Function>> {
         unsafe {
             let raw_func_ptr = BNGetAnalysisFunction(self.as_ref().handle, platform.handle, addr);
@@ -19,14 +19,144 @@ use crate::string::{raw_to_string, strings_to_string_list, BnString};
 
 use crate::rc::*;
 
+use crate::binary_view::{BinaryView, BinaryViewExt};
 use crate::confidence::MAX_CONFIDENCE;
 use crate::function::HighlightColor;
 use crate::tags::Tag;
-use crate::types::Type;
+use crate::types::{StructureMember, Type};
 use std::convert::From;
 use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use thiserror::Error;
 
+/// A boolean option controlling how disassembly is rendered, see [`DisassemblyOptionGroup`] for
+/// the options grouped by what they affect.
 pub type DisassemblyOption = BNDisassemblyOption;
+
+/// Names for [`DisassemblyOption`], implemented as an extension trait as the option is a core enum.
+pub trait DisassemblyOptionExt: Sized {
+    /// The option with the name, see [`DisassemblyOptionExt::name`]. Names are case-sensitive.
+    fn from_name(name: &str) -> Option<Self>;
+
+    /// The name of the option, the same as the variant name, e.g. `"ShowAddress"`.
+    fn name(&self) -> &'static str;
+}
+
+impl DisassemblyOptionExt for DisassemblyOption {
+    fn from_name(name: &str) -> Option<Self> {
+        DisassemblyOptionGroup::ALL
+            .iter()
+            .flat_map(|group| group.options())
+            .copied()
+            .find(|option| option.name() == name)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            DisassemblyOption::ShowAddress => "ShowAddress",
+            DisassemblyOption::ShowOpcode => "ShowOpcode",
+            DisassemblyOption::ExpandLongOpcode => "ExpandLongOpcode",
+            DisassemblyOption::ShowVariablesAtTopOfGraph => "ShowVariablesAtTopOfGraph",
+            DisassemblyOption::ShowVariableTypesWhenAssigned => "ShowVariableTypesWhenAssigned",
+            DisassemblyOption::ShowRegisterHighlight => "ShowRegisterHighlight",
+            DisassemblyOption::ShowFunctionAddress => "ShowFunctionAddress",
+            DisassemblyOption::ShowFunctionHeader => "ShowFunctionHeader",
+            DisassemblyOption::ShowTypeCasts => "ShowTypeCasts",
+            DisassemblyOption::GroupLinearDisassemblyFunctions => "GroupLinearDisassemblyFunctions",
+            DisassemblyOption::HighLevelILLinearDisassembly => "HighLevelILLinearDisassembly",
+            DisassemblyOption::WaitForIL => "WaitForIL",
+            DisassemblyOption::IndentHLILBody => "IndentHLILBody",
+            DisassemblyOption::DisableLineFormatting => "DisableLineFormatting",
+            DisassemblyOption::ShowFlagUsage => "ShowFlagUsage",
+            DisassemblyOption::ShowStackPointer => "ShowStackPointer",
+            DisassemblyOption::ShowILTypes => "ShowILTypes",
+            DisassemblyOption::ShowILOpcodes => "ShowILOpcodes",
+            DisassemblyOption::ShowCollapseIndicators => "ShowCollapseIndicators",
+        }
+    }
+}
+
+/// Options which require another option to be set to have any effect, as `(option, required)`.
+const REQUIRED_DISASSEMBLY_OPTIONS: &[(DisassemblyOption, DisassemblyOption)] = &[
+    // Long opcodes can only be expanded if opcodes are shown.
+    (
+        DisassemblyOption::ExpandLongOpcode,
+        DisassemblyOption::ShowOpcode,
+    ),
+];
+
+/// The group a [`DisassemblyOption`] belongs to.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum DisassemblyOptionGroup {
+    /// Options affecting every view, such as showing addresses, opcodes and type casts.
+    General,
+    /// Options only affecting linear views, such as waiting for IL or indenting the HLIL body.
+    Linear,
+    /// Options for debugging analysis, such as showing the stack pointer or IL types.
+    Debugging,
+}
+
+impl DisassemblyOptionGroup {
+    /// Every group, in the order of their option values.
+    pub const ALL: [DisassemblyOptionGroup; 3] = [
+        DisassemblyOptionGroup::General,
+        DisassemblyOptionGroup::Linear,
+        DisassemblyOptionGroup::Debugging,
+    ];
+
+    /// The options belonging to this group.
+    pub fn options(&self) -> &'static [DisassemblyOption] {
+        match self {
+            DisassemblyOptionGroup::General => &[
+                DisassemblyOption::ShowAddress,
+                DisassemblyOption::ShowOpcode,
+                DisassemblyOption::ExpandLongOpcode,
+                DisassemblyOption::ShowVariablesAtTopOfGraph,
+                DisassemblyOption::ShowVariableTypesWhenAssigned,
+                DisassemblyOption::ShowRegisterHighlight,
+                DisassemblyOption::ShowFunctionAddress,
+                DisassemblyOption::ShowFunctionHeader,
+                DisassemblyOption::ShowTypeCasts,
+            ],
+            DisassemblyOptionGroup::Linear => &[
+                DisassemblyOption::GroupLinearDisassemblyFunctions,
+                DisassemblyOption::HighLevelILLinearDisassembly,
+                DisassemblyOption::WaitForIL,
+                DisassemblyOption::IndentHLILBody,
+                DisassemblyOption::DisableLineFormatting,
+            ],
+            DisassemblyOptionGroup::Debugging => &[
+                DisassemblyOption::ShowFlagUsage,
+                DisassemblyOption::ShowStackPointer,
+                DisassemblyOption::ShowILTypes,
+                DisassemblyOption::ShowILOpcodes,
+                DisassemblyOption::ShowCollapseIndicators,
+            ],
+        }
+    }
+}
+
+impl From<DisassemblyOption> for DisassemblyOptionGroup {
+    fn from(option: DisassemblyOption) -> Self {
+        // The core reserves a range of values for each group of options.
+        match option as u32 {
+            0..=63 => DisassemblyOptionGroup::General,
+            64..=127 => DisassemblyOptionGroup::Linear,
+            _ => DisassemblyOptionGroup::Debugging,
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum DisassemblyOptionError {
+    #[error("option {option:?} requires option {required:?} to be set")]
+    MissingRequiredOption {
+        option: DisassemblyOption,
+        required: DisassemblyOption,
+    },
+    #[error("unknown option {0:?}")]
+    UnknownOption(String),
+}
 pub type InstructionTextTokenType = BNInstructionTextTokenType;
 pub type StringType = BNStringType;
 
@@ -41,6 +171,17 @@ pub struct DisassemblyTextLine {
     pub type_info: DisassemblyTextLineTypeInfo,
 }
 
+/// Raw lines created with [DisassemblyTextLine::into_raw], freed with [DisassemblyTextLine::free_raw] on drop.
+struct RawDisassemblyTextLines(Vec<BNDisassemblyTextLine>);
+
+impl Drop for RawDisassemblyTextLines {
+    fn drop(&mut self) {
+        for raw_line in self.0.drain(..) {
+            DisassemblyTextLine::free_raw(raw_line);
+        }
+    }
+}
+
 impl DisassemblyTextLine {
     pub(crate) fn from_raw(value: &BNDisassemblyTextLine) -> Self {
         let raw_tokens = unsafe { std::slice::from_raw_parts(value.tokens, value.count) };
@@ -119,12 +260,298 @@ impl DisassemblyTextLine {
         DisassemblyTextLineTypeInfo::free_raw(value.typeInfo);
     }
 
+    /// Call `f` with the raw [BNDisassemblyTextLine] of this line, the raw line is freed once `f`
+    /// returns, even if `f` panics.
+    ///
+    /// NOTE: The raw line must not be used after `f` returns, the core must copy anything it keeps.
+    pub fn with_raw<R>(self, f: impl FnOnce(&mut BNDisassemblyTextLine) -> R) -> R {
+        Self::with_raw_lines([self], |raw_lines| f(&mut raw_lines[0]))
+    }
+
+    /// Call `f` with the raw [BNDisassemblyTextLine]'s of `lines`, see [Self::with_raw].
+    pub(crate) fn with_raw_lines<R>(
+        lines: impl IntoIterator<Item = Self>,
+        f: impl FnOnce(&mut [BNDisassemblyTextLine]) -> R,
+    ) -> R {
+        // Lines are converted one at a time so that already converted lines are freed if `lines` panics.
+        let mut raw_lines = RawDisassemblyTextLines(Vec::new());
+        for line in lines {
+            raw_lines.0.push(Self::into_raw(line));
+        }
+        f(&mut raw_lines.0)
+    }
+
     pub fn new(tokens: Vec<InstructionTextToken>) -> Self {
         Self {
             tokens,
             ..Default::default()
         }
     }
+
+    pub fn new_with_address(address: u64, tokens: Vec<InstructionTextToken>) -> Self {
+        Self {
+            address,
+            tokens,
+            ..Default::default()
+        }
+    }
+
+    /// Set the highlight of the line, e.g. `DisassemblyTextLine::new_with_address(addr, tokens).with_highlight(color)`.
+    pub fn with_highlight(mut self, highlight: HighlightColor) -> Self {
+        self.highlight = highlight;
+        self
+    }
+
+    /// The text of a single [`InstructionTextTokenKind::Indentation`] token, the core emits one per level.
+    pub const INDENTATION: &'static str = "    ";
+
+    /// An empty line indented by `level`, build the rest of the line with [`DisassemblyTextLine::push_token`]
+    /// and [`DisassemblyTextLine::push_text`].
+    ///
+    /// Each level is a separate [`InstructionTextTokenKind::Indentation`] token, like the lines of the core.
+    pub fn with_indent(level: usize) -> Self {
+        let tokens = (0..level)
+            .map(|_| {
+                InstructionTextToken::new(Self::INDENTATION, InstructionTextTokenKind::Indentation)
+            })
+            .collect();
+        Self::new(tokens)
+    }
+
+    /// Name the targets of the [`InstructionTextTokenKind::GotoLabel`] and [`InstructionTextTokenKind::Comment`]
+    /// tokens with the symbol at the target address, e.g. `goto sub_401000` reads `goto parse_header`.
+    ///
+    /// The text of a goto label is replaced with the symbol name, the name is appended to the text
+    /// of a comment that does not already include it. Tokens without a symbol at the target are unchanged.
+    pub fn resolve_targets(&mut self, view: &BinaryView) {
+        for token in &mut self.tokens {
+            let (target, is_comment) = match token.kind {
+                InstructionTextTokenKind::GotoLabel { target } => (target, false),
+                InstructionTextTokenKind::Comment { target } => (target, true),
+                _ => continue,
+            };
+            let Some(symbol) = view.symbol_by_address(target) else {
+                continue;
+            };
+            let name = symbol.full_name().to_string();
+            if !is_comment {
+                token.text = name;
+            } else if !token.text.contains(&name) {
+                token.text = format!("{} ({})", token.text, name);
+            }
+        }
+    }
+
+    /// The number of leading [`InstructionTextTokenKind::Indentation`] tokens.
+    pub fn indent_level(&self) -> usize {
+        self.tokens
+            .iter()
+            .take_while(|token| token.kind == InstructionTextTokenKind::Indentation)
+            .count()
+    }
+
+    /// Append the token to the line.
+    ///
+    /// Adjacent text tokens are not merged and no separator is added, use [`DisassemblyTextLine::push_text`]
+    /// for the spacing between tokens.
+    pub fn push_token(&mut self, token: InstructionTextToken) -> &mut Self {
+        self.tokens.push(token);
+        self
+    }
+
+    /// Append a [`InstructionTextTokenKind::Text`] token with `text`, e.g. a separator.
+    pub fn push_text(&mut self, text: impl Into<String>) -> &mut Self {
+        self.push_token(InstructionTextToken::new(
+            text,
+            InstructionTextTokenKind::Text,
+        ))
+    }
+
+    /// Build a line in the same layout as the hex view: `width` byte values followed by their ASCII text.
+    ///
+    /// Each byte is emitted as an [`InstructionTextTokenKind::HexDumpByteValue`] token, if `bytes` is shorter
+    /// than `width` the remaining columns are padded so that the text column still lines up. Bytes that are not
+    /// printable ASCII are shown as `.` in the text column.
+    pub fn hex_dump(address: u64, bytes: &[u8], width: usize) -> Self {
+        let mut tokens = Vec::with_capacity(width * 2 + 2);
+        for (i, byte) in bytes.iter().enumerate() {
+            if i != 0 {
+                tokens.push(InstructionTextToken::new(
+                    " ",
+                    InstructionTextTokenKind::Text,
+                ));
+            }
+            tokens.push(InstructionTextToken::new_with_address(
+                address + i as u64,
+                format!("{:02x}", byte),
+                InstructionTextTokenKind::HexDumpByteValue { value: *byte },
+            ));
+        }
+        // Pad out the missing byte columns so the text column is aligned with full lines.
+        let missing_columns = width.saturating_sub(bytes.len());
+        if missing_columns > 0 {
+            tokens.push(InstructionTextToken::new(
+                "   ".repeat(missing_columns),
+                InstructionTextTokenKind::Text,
+            ));
+        }
+        tokens.push(InstructionTextToken::new(
+            "  ",
+            InstructionTextTokenKind::Text,
+        ));
+        let text: String = bytes
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        tokens.push(InstructionTextToken::new_with_address(
+            address,
+            text,
+            InstructionTextTokenKind::HexDumpText {
+                width: width as u64,
+            },
+        ));
+        Self {
+            address,
+            tokens,
+            ..Default::default()
+        }
+    }
+
+    /// Whether any of the lines tags have a [`crate::tags::TagType`] with the name `type_name`.
+    pub fn has_tag_of_type(&self, type_name: &str) -> bool {
+        self.tags
+            .iter()
+            .any(|tag| tag.ty().name().as_str() == type_name)
+    }
+
+    /// The lines tags with a [`crate::tags::TagType`] with the name `type_name`.
+    pub fn tags_of_type(&self, type_name: &str) -> Vec<Ref<Tag>> {
+        self.tags
+            .iter()
+            .filter(|tag| tag.ty().name().as_str() == type_name)
+            .cloned()
+            .collect()
+    }
+
+    /// Whether both lines render the same content, regardless of where they are.
+    ///
+    /// Unlike `==`, which compares every field, only the text and kind of the tokens are compared,
+    /// the address, instruction index, tags and highlight of the line (and the address of each token)
+    /// are ignored. Compare [`DisassemblyTextLine::highlight`] separately if it is relevant.
+    pub fn content_eq(&self, other: &Self) -> bool {
+        self.tokens.len() == other.tokens.len()
+            && self
+                .tokens
+                .iter()
+                .zip(&other.tokens)
+                .all(|(token, other_token)| {
+                    token.text == other_token.text && token.kind == other_token.kind
+                })
+    }
+
+    /// Hash the rendered content of the line, lines which are [`DisassemblyTextLine::content_eq`]
+    /// have the same content hash.
+    pub fn content_hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        use std::hash::Hash;
+        self.tokens.len().hash(state);
+        for token in &self.tokens {
+            token.text.hash(state);
+            std::mem::discriminant(&token.kind).hash(state);
+        }
+    }
+
+    /// The tokens of each memory operand in the line, such as `[rax+0x8]`, without the
+    /// [`InstructionTextTokenKind::BeginMemoryOperand`] and [`InstructionTextTokenKind::EndMemoryOperand`] markers.
+    ///
+    /// Nested memory operands are part of the operand they are nested in, including their markers.
+    /// Unbalanced markers are logged and the affected tokens skipped.
+    pub fn memory_operands(&self) -> Vec<Vec<InstructionTextToken>> {
+        let mut operands = Vec::new();
+        let mut current = Vec::new();
+        let mut depth = 0usize;
+        for token in &self.tokens {
+            match token.kind {
+                InstructionTextTokenKind::BeginMemoryOperand => {
+                    if depth > 0 {
+                        current.push(token.clone());
+                    }
+                    depth += 1;
+                }
+                InstructionTextTokenKind::EndMemoryOperand if depth == 0 => {
+                    log::warn!(
+                        "Unbalanced end of memory operand in line at {:#x}",
+                        self.address
+                    );
+                }
+                InstructionTextTokenKind::EndMemoryOperand => {
+                    depth -= 1;
+                    match depth {
+                        0 => operands.push(std::mem::take(&mut current)),
+                        _ => current.push(token.clone()),
+                    }
+                }
+                _ if depth > 0 => current.push(token.clone()),
+                _ => {}
+            }
+        }
+        if depth > 0 {
+            log::warn!("Unterminated memory operand in line at {:#x}", self.address);
+        }
+        operands
+    }
+
+    /// Snapshot the line as plain data, without any core handles.
+    ///
+    /// Use this to buffer lines past the lifetime of the view, types are replaced by their string
+    /// form and tags by their tag type name and data.
+    pub fn into_plain(self) -> PlainDisassemblyLine {
+        PlainDisassemblyLine {
+            address: self.address,
+            instruction_index: self.instruction_index,
+            tokens: self.tokens,
+            highlight: self.highlight,
+            tags: self
+                .tags
+                .iter()
+                .map(|tag| (tag.ty().name().to_string(), tag.data().to_string()))
+                .collect(),
+            has_type_info: self.type_info.has_type_info,
+            parent_type: self.type_info.parent_type.map(|ty| ty.to_string()),
+            field_index: self.type_info.field_index,
+            offset: self.type_info.offset,
+        }
+    }
+}
+
+/// A [`DisassemblyTextLine`] holding no core handles, created with [`DisassemblyTextLine::into_plain`].
+#[derive(Clone, PartialEq, Debug, Default, Eq)]
+pub struct PlainDisassemblyLine {
+    pub address: u64,
+    pub instruction_index: usize,
+    pub tokens: Vec<InstructionTextToken>,
+    pub highlight: HighlightColor,
+    /// The tags of the line as `(tag type name, tag data)`.
+    pub tags: Vec<(String, String)>,
+    pub has_type_info: bool,
+    /// The string form of [`DisassemblyTextLineTypeInfo::parent_type`].
+    pub parent_type: Option<String>,
+    pub field_index: usize,
+    pub offset: u64,
+}
+
+impl Display for PlainDisassemblyLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for token in &self.tokens {
+            write!(f, "{}", token)?;
+        }
+        Ok(())
+    }
 }
 
 impl From<&str> for DisassemblyTextLine {
@@ -179,6 +606,19 @@ pub struct DisassemblyTextLineTypeInfo {
 }
 
 impl DisassemblyTextLineTypeInfo {
+    /// The structure member displayed by the line, the member at [`Self::field_index`] of the
+    /// [`Self::parent_type`] structure.
+    ///
+    /// Returns `None` if the line has no type info, the parent type is not a structure or the
+    /// field index is out of range.
+    pub fn resolved_member(&self) -> Option<StructureMember> {
+        if !self.has_type_info {
+            return None;
+        }
+        let structure = self.parent_type.as_ref()?.get_structure()?;
+        structure.members().into_iter().nth(self.field_index)
+    }
+
     pub(crate) fn from_raw(value: &BNDisassemblyTextLineTypeInfo) -> Self {
         Self {
             has_type_info: value.hasTypeInfo,
@@ -261,9 +701,9 @@ impl InstructionTextToken {
     pub(crate) fn into_raw(value: Self) -> BNInstructionTextToken {
         let bn_text = BnString::new(value.text);
         // These can be gathered from value.kind
-        let kind_value = value.kind.try_value().unwrap_or(0);
-        let operand = value.kind.try_operand().unwrap_or(0);
-        let size = value.kind.try_size().unwrap_or(0);
+        let kind_value = value.kind.value().unwrap_or(0);
+        let operand = value.kind.operand().unwrap_or(0);
+        let size = value.kind.size().unwrap_or(0);
         let type_names = value.kind.try_type_names().unwrap_or_default();
         BNInstructionTextToken {
             type_: value.kind.into(),
@@ -416,7 +856,9 @@ pub enum InstructionTextTokenKind {
         ///
         /// This is typically just the members name.
         /// For example MyStructure.my_field will have type_names be \["my_field"\].
-        type_names: Vec<String>,
+        ///
+        /// NOTE: This is shared so that cloning the token does not re-allocate the names.
+        type_names: Arc<[String]>,
     },
     NameSpace,
     NameSpaceSeparator,
@@ -426,7 +868,7 @@ pub enum InstructionTextTokenKind {
         offset: u64,
         // TODO: This makes no sense for struct offset, they dont have types?
         /// Stores the type names for the referenced field name.
-        type_names: Vec<String>,
+        type_names: Arc<[String]>,
     },
     // TODO: Unused?
     StructOffsetByteValue,
@@ -577,7 +1019,11 @@ impl InstructionTextTokenKind {
                     1 => StringType::Utf8String,
                     2 => StringType::Utf16String,
                     3 => StringType::Utf32String,
-                    _ => unreachable!(),
+                    unknown => {
+                        // Do not panic on string types added by a newer core.
+                        log::warn!("Unknown string type {}, treating as ascii", unknown);
+                        StringType::AsciiString
+                    }
                 },
             },
             BNInstructionTextTokenType::CharacterConstantToken => Self::CharacterConstant,
@@ -683,8 +1129,20 @@ impl InstructionTextTokenKind {
         }
     }
 
-    /// Mapping to the [`BNInstructionTextTokenType::value`] field.
-    fn try_value(&self) -> Option<u64> {
+    /// The value the token carries, `None` if the kind has no value.
+    ///
+    /// What the value is depends on the kind, for example:
+    ///
+    /// - The integer or address for [`InstructionTextTokenKind::Integer`], [`InstructionTextTokenKind::PossibleAddress`]
+    ///   and the symbol kinds (e.g. [`InstructionTextTokenKind::CodeSymbol`]).
+    /// - The target address for [`InstructionTextTokenKind::GotoLabel`], [`InstructionTextTokenKind::Comment`]
+    ///   and the import kinds.
+    /// - The offset for [`InstructionTextTokenKind::FieldName`] and [`InstructionTextTokenKind::StructOffset`].
+    /// - The variable identifier for [`InstructionTextTokenKind::LocalVariable`] and [`InstructionTextTokenKind::StackVariable`].
+    /// - The width for the hex dump text kinds.
+    ///
+    /// This is the value stored in the `value` field of the core token.
+    pub fn value(&self) -> Option<u64> {
         // TODO: Double check to make sure these are correct.
         match self {
             InstructionTextTokenKind::Integer { value, .. } => Some(*value),
@@ -717,8 +1175,13 @@ impl InstructionTextTokenKind {
         }
     }
 
-    /// Mapping to the [`BNInstructionTextTokenType::size`] field.
-    fn try_size(&self) -> Option<usize> {
+    /// The size in bytes of what the token refers to, `None` if the kind has no size or it is unknown.
+    ///
+    /// Only the numeric kinds (e.g. [`InstructionTextTokenKind::Integer`]) and the symbol kinds have
+    /// a size, for [`InstructionTextTokenKind::IndirectImport`] it is the size of the instruction.
+    ///
+    /// This is the value stored in the `size` field of the core token.
+    pub fn size(&self) -> Option<usize> {
         match self {
             InstructionTextTokenKind::Integer { size, .. } => *size,
             InstructionTextTokenKind::FloatingPoint { size, .. } => *size,
@@ -731,8 +1194,13 @@ impl InstructionTextTokenKind {
         }
     }
 
-    /// Mapping to the [`BNInstructionTextTokenType::operand`] field.
-    fn try_operand(&self) -> Option<usize> {
+    /// The operand the token carries, `None` if the kind has no operand.
+    ///
+    /// This is the SSA version for [`InstructionTextTokenKind::LocalVariable`] and the source operand
+    /// for [`InstructionTextTokenKind::IndirectImport`].
+    ///
+    /// This is the value stored in the `operand` field of the core token.
+    pub fn operand(&self) -> Option<usize> {
         match self {
             InstructionTextTokenKind::LocalVariable { ssa_version, .. } => Some(*ssa_version),
             InstructionTextTokenKind::IndirectImport { source_operand, .. } => {
@@ -743,12 +1211,12 @@ impl InstructionTextTokenKind {
     }
 
     /// Mapping to the [`BNInstructionTextTokenType::typeNames`] field.
-    fn try_type_names(&self) -> Option<Vec<String>> {
+    fn try_type_names(&self) -> Option<Arc<[String]>> {
         match self {
             InstructionTextTokenKind::FieldName { type_names, .. } => Some(type_names.clone()),
             InstructionTextTokenKind::StructOffset { type_names, .. } => Some(type_names.clone()),
             InstructionTextTokenKind::EnumerationMember { type_id, .. } => {
-                Some(vec![type_id.clone()?])
+                Some(Arc::from([type_id.clone()?]))
             }
             _ => None,
         }
@@ -912,6 +1380,12 @@ pub enum InstructionTextTokenContext {
     CollapsiblePadding,
 }
 
+impl From<&BNInstructionTextToken> for InstructionTextToken {
+    fn from(value: &BNInstructionTextToken) -> Self {
+        Self::from_raw(value)
+    }
+}
+
 impl From<BNInstructionTextTokenContext> for InstructionTextTokenContext {
     fn from(value: BNInstructionTextTokenContext) -> Self {
         match value {
@@ -984,6 +1458,67 @@ impl DisassemblySettings {
     pub fn is_option_set(&self, option: DisassemblyOption) -> bool {
         unsafe { BNIsDisassemblySettingsOptionSet(self.handle, option) }
     }
+
+    /// Set all the given options, leaving other options untouched.
+    ///
+    /// If an option requires another option which is neither given nor already set, no options
+    /// are set and an error is returned.
+    pub fn set_options(&self, options: &[DisassemblyOption]) -> Result<(), DisassemblyOptionError> {
+        for &(option, required) in REQUIRED_DISASSEMBLY_OPTIONS {
+            if options.contains(&option)
+                && !options.contains(&required)
+                && !self.is_option_set(required)
+            {
+                return Err(DisassemblyOptionError::MissingRequiredOption { option, required });
+            }
+        }
+        for &option in options {
+            self.set_option(option, true);
+        }
+        Ok(())
+    }
+
+    /// Set all the options with the given names, see [`DisassemblySettings::set_options`].
+    ///
+    /// If any name is not a known option, see [`DisassemblyOptionExt::from_name`], no options are set.
+    pub fn set_options_by_name<S: AsRef<str>>(
+        &self,
+        names: &[S],
+    ) -> Result<(), DisassemblyOptionError> {
+        let options = names
+            .iter()
+            .map(|name| {
+                let name = name.as_ref();
+                DisassemblyOption::from_name(name)
+                    .ok_or_else(|| DisassemblyOptionError::UnknownOption(name.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.set_options(&options)
+    }
+
+    /// The maximum width of a rendered line, a width of `0` means unlimited.
+    pub fn width(&self) -> usize {
+        unsafe { BNGetDisassemblyWidth(self.handle) }
+    }
+
+    /// Set the maximum width of a rendered line, pass `0` for unlimited.
+    ///
+    /// Useful for producing fixed-width output, such as when diffing two listings side-by-side.
+    pub fn set_width(&self, width: usize) {
+        unsafe { BNSetDisassemblyWidth(self.handle, width) }
+    }
+
+    /// The maximum width of a rendered symbol, a width of `0` means unlimited.
+    pub fn maximum_symbol_width(&self) -> usize {
+        unsafe { BNGetDisassemblyMaximumSymbolWidth(self.handle) }
+    }
+
+    /// Set the maximum width of a rendered symbol, pass `0` for unlimited.
+    ///
+    /// Symbols wider than this will be truncated by the core when rendered.
+    pub fn set_maximum_symbol_width(&self, width: usize) {
+        unsafe { BNSetDisassemblyMaximumSymbolWidth(self.handle, width) }
+    }
 }
 
 impl ToOwned for DisassemblySettings {
@@ -14,20 +14,33 @@
 
 //! Interfaces for creating and displaying pretty CFGs in Binary Ninja.
 
-use crate::disassembly::DisassemblyTextLine;
+use crate::disassembly::{DisassemblySettings, DisassemblyTextLine};
 use binaryninjacore_sys::*;
 
 use crate::rc::*;
 
 use crate::basic_block::{BasicBlock, BlockContext};
-use crate::function::HighlightColor;
+use crate::function::{Function, HighlightColor};
 use crate::render_layer::CoreRenderLayer;
+use std::collections::HashMap;
 
 pub type BranchType = BNBranchType;
 pub type EdgePenStyle = BNEdgePenStyle;
 pub type ThemeColor = BNThemeColor;
 pub type FlowGraphOption = BNFlowGraphOption;
 
+/// Every [`FlowGraphOption`] known to these bindings.
+const FLOW_GRAPH_OPTIONS: &[FlowGraphOption] = &[
+    FlowGraphOption::FlowGraphUsesBlockHighlights,
+    FlowGraphOption::FlowGraphUsesInstructionHighlights,
+    FlowGraphOption::FlowGraphIncludesUserComments,
+    FlowGraphOption::FlowGraphAllowsPatching,
+    FlowGraphOption::FlowGraphAllowsInlineInstructionEditing,
+    FlowGraphOption::FlowGraphShowsSecondaryRegisterHighlighting,
+    FlowGraphOption::FlowGraphIsAddressable,
+    FlowGraphOption::FlowGraphIsWorkflowGraph,
+];
+
 #[derive(PartialEq, Eq, Hash)]
 pub struct FlowGraph {
     pub(crate) handle: *mut BNFlowGraph,
@@ -52,6 +65,69 @@ impl FlowGraph {
         unsafe { Array::new(nodes_ptr, count, ()) }
     }
 
+    /// Build a graph of the native basic blocks of the function.
+    ///
+    /// Each node is associated with its basic block, see [`FlowGraphNode::basic_block_start`], so
+    /// that the nodes of the graph can be navigated to.
+    pub fn from_function(function: &Function, settings: &DisassemblySettings) -> Ref<Self> {
+        let graph = FlowGraph::new();
+        let blocks = function.basic_blocks();
+        let mut block_nodes: HashMap<u64, Ref<FlowGraphNode>> = HashMap::new();
+        for block in &blocks {
+            let node = FlowGraphNode::new(&graph);
+            node.set_basic_block(Some(block.as_ref()));
+            node.set_lines(&block.disassembly_text(settings));
+            graph.append(&node);
+            block_nodes.insert(block.start_index(), node);
+        }
+        for block in &blocks {
+            let source = &block_nodes[&block.start_index()];
+            for edge in &block.outgoing_edges() {
+                if let Some(target) = block_nodes.get(&edge.target().start_index()) {
+                    let style = EdgeStyle::for_branch_type(edge.branch);
+                    source.add_outgoing_edge(edge.branch, target, style);
+                }
+            }
+        }
+        graph
+    }
+
+    /// Render the graph as plain text, for quick inspection of graphs in a terminal.
+    ///
+    /// Each node is printed in insertion order with a `Node N @ address` header (the address is
+    /// omitted when the node has no basic block), followed by its lines and outgoing edges:
+    ///
+    /// ```text
+    /// Node 0 @ 0x1000
+    ///     0x1000  push    rbp
+    ///     -> Node 1 (TrueBranch)
+    /// ```
+    pub fn to_text(&self) -> String {
+        use std::fmt::Write;
+        let nodes = self.nodes();
+        let mut text = String::new();
+        for (index, node) in nodes.iter().enumerate() {
+            match node.basic_block_start() {
+                Some(address) => writeln!(text, "Node {} @ {:#x}", index, address),
+                None => writeln!(text, "Node {}", index),
+            }
+            .unwrap();
+            for line in &node.lines() {
+                writeln!(text, "    {:#x}  {}", line.address, line).unwrap();
+            }
+            for edge in &node.outgoing_edges() {
+                match nodes.iter().position(|n| n.handle == edge.target.handle) {
+                    Some(target) => {
+                        writeln!(text, "    -> Node {} ({:?})", target, edge.branch_type)
+                    }
+                    None => writeln!(text, "    -> ? ({:?})", edge.branch_type),
+                }
+                .unwrap();
+            }
+        }
+        text
+    }
+
     pub fn get_node(&self, i: usize) -> Option<Ref<FlowGraphNode>> {
         let node_ptr = unsafe { BNGetFlowGraphNode(self.handle, i) };
         if node_ptr.is_null() {
@@ -89,6 +165,48 @@ impl FlowGraph {
         unsafe { BNIsFlowGraphOptionSet(self.handle, option) }
     }
 
+    /// All the options set on this graph.
+    ///
+    /// Only the options known to these bindings are checked, options added to the core later are skipped.
+    pub fn options(&self) -> Vec<FlowGraphOption> {
+        FLOW_GRAPH_OPTIONS
+            .iter()
+            .copied()
+            .filter(|&option| self.is_option_set(option))
+            .collect()
+    }
+
+    /// Reapply the style of every edge in the graph, using `f` to pick the style for each [`BranchType`].
+    ///
+    /// NOTE: The core has no way to modify an existing edge, so every node is recreated with the
+    /// restyled edges and replaced in the graph. Edge points are not carried over, the graph must be
+    /// laid out again.
+    pub fn restyle_edges(&self, f: impl Fn(BranchType) -> EdgeStyle) {
+        let nodes = self.nodes();
+        let node_indices: HashMap<*mut BNFlowGraphNode, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.handle, i))
+            .collect();
+        let new_nodes: Vec<Ref<FlowGraphNode>> =
+            nodes.iter().map(|node| node.duplicate_node(self)).collect();
+        for (node, new_node) in nodes.iter().zip(&new_nodes) {
+            for edge in &node.outgoing_edges() {
+                let Some(&target_idx) = node_indices.get(&edge.target.handle) else {
+                    continue;
+                };
+                new_node.add_outgoing_edge(
+                    edge.branch_type,
+                    &new_nodes[target_idx],
+                    f(edge.branch_type),
+                );
+            }
+        }
+        for (i, new_node) in new_nodes.iter().enumerate() {
+            self.replace(i, new_node);
+        }
+    }
+
     /// A list of the currently applied [`CoreRenderLayer`]'s
     pub fn render_layers(&self) -> Array<CoreRenderLayer> {
         let mut count: usize = 0;
@@ -149,6 +267,15 @@ impl FlowGraphNode {
         unsafe { FlowGraphNode::ref_from_raw(BNCreateFlowGraphNode(graph.handle)) }
     }
 
+    /// The graph this node was created in, edges can only be added between nodes of the same graph.
+    pub fn graph(&self) -> Option<Ref<FlowGraph>> {
+        let graph_ptr = unsafe { BNGetFlowGraphNodeOwner(self.handle) };
+        match graph_ptr.is_null() {
+            false => Some(unsafe { FlowGraph::ref_from_raw(graph_ptr) }),
+            true => None,
+        }
+    }
+
     pub fn basic_block<C: BlockContext>(&self, context: C) -> Option<Ref<BasicBlock<C>>> {
         let block_ptr = unsafe { BNGetFlowGraphBasicBlock(self.handle) };
         if block_ptr.is_null() {
@@ -164,6 +291,19 @@ impl FlowGraphNode {
         }
     }
 
+    /// The start address of the basic block associated with the node, see [`FlowGraphNode::set_basic_block`].
+    pub fn basic_block_start(&self) -> Option<u64> {
+        unsafe {
+            let block_ptr = BNGetFlowGraphBasicBlock(self.handle);
+            if block_ptr.is_null() {
+                return None;
+            }
+            let start = BNGetBasicBlockStart(block_ptr);
+            BNFreeBasicBlock(block_ptr);
+            Some(start)
+        }
+    }
+
     pub fn lines(&self) -> Array<DisassemblyTextLine> {
         let mut count = 0;
         let result = unsafe { BNGetFlowGraphNodeLines(self.handle, &mut count) };
@@ -172,17 +312,10 @@ impl FlowGraphNode {
     }
 
     pub fn set_lines(&self, lines: impl IntoIterator<Item = DisassemblyTextLine>) {
-        // NOTE: This will create allocations and increment tag refs, we must call DisassemblyTextLine::free_raw
-        let mut raw_lines: Vec<BNDisassemblyTextLine> = lines
-            .into_iter()
-            .map(DisassemblyTextLine::into_raw)
-            .collect();
-        unsafe {
-            BNSetFlowGraphNodeLines(self.handle, raw_lines.as_mut_ptr(), raw_lines.len());
-            for raw_line in raw_lines {
-                DisassemblyTextLine::free_raw(raw_line);
-            }
-        }
+        // NOTE: The raw lines are freed once the core has copied them.
+        DisassemblyTextLine::with_raw_lines(lines, |raw_lines| unsafe {
+            BNSetFlowGraphNodeLines(self.handle, raw_lines.as_mut_ptr(), raw_lines.len())
+        });
     }
 
     /// Returns the graph position of the node in X, Y form.
@@ -207,18 +340,62 @@ impl FlowGraphNode {
         unsafe { BNSetFlowGraphNodeHighlight(self.handle, highlight.into()) };
     }
 
-    // TODO: Add getters and setters for edges
+    /// Create a new node in `graph` with the same contents as this node, without any edges.
+    fn duplicate_node(&self, graph: &FlowGraph) -> Ref<FlowGraphNode> {
+        let new_node = FlowGraphNode::new(graph);
+        new_node.set_lines(self.lines().iter());
+        new_node.set_highlight_color(self.highlight_color());
+        unsafe {
+            let block_ptr = BNGetFlowGraphBasicBlock(self.handle);
+            if !block_ptr.is_null() {
+                BNSetFlowGraphBasicBlock(new_node.handle, block_ptr);
+                BNFreeBasicBlock(block_ptr);
+            }
+        }
+        new_node
+    }
+
+    pub fn outgoing_edges(&self) -> Array<FlowGraphEdge> {
+        let mut count = 0;
+        let result = unsafe { BNGetFlowGraphNodeOutgoingEdges(self.handle, &mut count) };
+        assert!(!result.is_null());
+        unsafe { Array::new(result, count, ()) }
+    }
 
+    pub fn incoming_edges(&self) -> Array<FlowGraphEdge> {
+        let mut count = 0;
+        let result = unsafe { BNGetFlowGraphNodeIncomingEdges(self.handle, &mut count) };
+        assert!(!result.is_null());
+        unsafe { Array::new(result, count, ()) }
+    }
+
+    /// Add an edge from this node to `target`.
+    ///
+    /// Both nodes must belong to the same [`FlowGraph`], in debug builds this is checked.
     pub fn add_outgoing_edge(
         &self,
         type_: BranchType,
         target: &FlowGraphNode,
         edge_style: EdgeStyle,
     ) {
+        debug_assert!(
+            self.graph() == target.graph(),
+            "Adding an edge to a node of a different FlowGraph is undefined"
+        );
         unsafe {
             BNAddFlowGraphNodeOutgoingEdge(self.handle, type_, target.handle, edge_style.into())
         }
     }
+
+    /// Add an edge from this node for every `(branch type, target, style)`, see [`FlowGraphNode::add_outgoing_edge`].
+    pub fn add_edges<'a>(
+        &self,
+        edges: impl IntoIterator<Item = (BranchType, &'a FlowGraphNode, EdgeStyle)>,
+    ) {
+        for (type_, target, edge_style) in edges {
+            self.add_outgoing_edge(type_, target, edge_style);
+        }
+    }
 }
 
 unsafe impl RefCountable for FlowGraphNode {
@@ -257,6 +434,65 @@ unsafe impl CoreArrayProviderInner for FlowGraphNode {
     }
 }
 
+/// An edge between two [`FlowGraphNode`]'s.
+///
+/// For outgoing edges the `target` is the node the edge points to, for incoming edges it is the
+/// node the edge originates from.
+pub struct FlowGraphEdge {
+    pub branch_type: BranchType,
+    pub target: Ref<FlowGraphNode>,
+    pub points: Vec<(f32, f32)>,
+    pub back_edge: bool,
+    pub style: EdgeStyle,
+}
+
+impl FlowGraphEdge {
+    pub(crate) unsafe fn from_raw(value: &BNFlowGraphEdge) -> Self {
+        let points = match value.points.is_null() {
+            true => Vec::new(),
+            false => std::slice::from_raw_parts(value.points, value.pointCount)
+                .iter()
+                .map(|point| (point.x, point.y))
+                .collect(),
+        };
+        Self {
+            branch_type: value.type_,
+            target: FlowGraphNode::from_raw(value.target).to_owned(),
+            points,
+            back_edge: value.backEdge,
+            style: value.style.into(),
+        }
+    }
+}
+
+impl CoreArrayProvider for FlowGraphEdge {
+    type Raw = BNFlowGraphEdge;
+    type Context = ();
+    type Wrapped<'a> = FlowGraphEdge;
+}
+
+unsafe impl CoreArrayProviderInner for FlowGraphEdge {
+    unsafe fn free(raw: *mut Self::Raw, count: usize, _: &Self::Context) {
+        BNFreeFlowGraphNodeEdgeList(raw, count);
+    }
+
+    unsafe fn wrap_raw<'a>(raw: &'a Self::Raw, _context: &'a Self::Context) -> Self::Wrapped<'a> {
+        FlowGraphEdge::from_raw(raw)
+    }
+}
+
+/// The style an edge is drawn with.
+///
+/// Styles are built with [`EdgeStyle::new`] or by adjusting a preset:
+///
+/// ```no_run
+/// use binaryninja::flowgraph::{EdgePenStyle, EdgeStyle, ThemeColor};
+///
+/// let style = EdgeStyle::default()
+///     .with_style(EdgePenStyle::DotLine)
+///     .with_width(2)
+///     .with_color(ThemeColor::BlueStandardHighlightColor);
+/// ```
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct EdgeStyle {
     style: EdgePenStyle,
@@ -265,6 +501,12 @@ pub struct EdgeStyle {
 }
 
 impl EdgeStyle {
+    /// The width that tells the renderer to use the width of the current theme.
+    pub const DEFAULT_WIDTH: usize = 0;
+
+    /// Create a new style.
+    ///
+    /// A `width` of [`EdgeStyle::DEFAULT_WIDTH`] (zero) uses the width of the current theme.
     pub fn new(style: EdgePenStyle, width: usize, color: ThemeColor) -> Self {
         Self {
             style,
@@ -272,11 +514,100 @@ impl EdgeStyle {
             color,
         }
     }
+
+    pub fn with_style(mut self, style: EdgePenStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Set the width of the edge.
+    ///
+    /// A `width` of [`EdgeStyle::DEFAULT_WIDTH`] (zero) uses the width of the current theme.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn with_color(mut self, color: ThemeColor) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn style(&self) -> EdgePenStyle {
+        self.style
+    }
+
+    /// The width of the edge, [`EdgeStyle::DEFAULT_WIDTH`] if the theme width is used.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn color(&self) -> ThemeColor {
+        self.color
+    }
+
+    /// Style for the edge taken when a conditional branch is true.
+    pub fn true_branch() -> Self {
+        Self::new(
+            EdgePenStyle::SolidLine,
+            Self::DEFAULT_WIDTH,
+            ThemeColor::TrueBranchColor,
+        )
+    }
+
+    /// Style for the edge taken when a conditional branch is false.
+    pub fn false_branch() -> Self {
+        Self::new(
+            EdgePenStyle::SolidLine,
+            Self::DEFAULT_WIDTH,
+            ThemeColor::FalseBranchColor,
+        )
+    }
+
+    /// Style for an edge that is always taken.
+    pub fn unconditional() -> Self {
+        Self::new(
+            EdgePenStyle::SolidLine,
+            Self::DEFAULT_WIDTH,
+            ThemeColor::UnconditionalBranchColor,
+        )
+    }
+
+    /// Style for an edge to a call destination, dashed to distinguish it from intra-function control flow.
+    pub fn call() -> Self {
+        Self::new(
+            EdgePenStyle::DashLine,
+            Self::DEFAULT_WIDTH,
+            ThemeColor::AltUnconditionalBranchColor,
+        )
+    }
+
+    /// Pick the conventional style for an edge of the given [`BranchType`].
+    ///
+    /// Branch types without a dedicated style are drawn as [`EdgeStyle::unconditional`].
+    pub fn for_branch_type(branch_type: BranchType) -> Self {
+        match branch_type {
+            BranchType::TrueBranch => Self::true_branch(),
+            BranchType::FalseBranch => Self::false_branch(),
+            BranchType::CallDestination => Self::call(),
+            _ => Self::unconditional(),
+        }
+    }
 }
 
 impl Default for EdgeStyle {
     fn default() -> Self {
-        Self::new(EdgePenStyle::SolidLine, 0, ThemeColor::AddressColor)
+        Self::new(
+            EdgePenStyle::SolidLine,
+            Self::DEFAULT_WIDTH,
+            ThemeColor::AddressColor,
+        )
+    }
+}
+
+impl From<BranchType> for EdgeStyle {
+    fn from(branch_type: BranchType) -> Self {
+        Self::for_branch_type(branch_type)
     }
 }
 
@@ -22,6 +22,7 @@ use crate::{
     component::Component,
     disassembly::{DisassemblySettings, DisassemblyTextLine},
     flowgraph::FlowGraph,
+    linear_view::LinearViewObject,
     medium_level_il::FunctionGraphType,
     platform::Platform,
     references::CodeReference,
@@ -1526,6 +1527,27 @@ impl Function {
         unsafe { Array::new(tags, count, ()) }
     }
 
+    /// Every tag shown on the disassembly lines of the function, with the address of the line.
+    ///
+    /// Unlike [`Function::tags`] this walks the rendered lines, so only the tags visible with the
+    /// given `settings` are returned, a tag shown on multiple lines is returned once per line.
+    pub fn all_disassembly_tags(&self, settings: &DisassemblySettings) -> Vec<(u64, Ref<Tag>)> {
+        let object = LinearViewObject::single_function_disassembly(self, settings);
+        let mut cursor = object.create_cursor();
+        cursor.seek_to_start();
+        let mut tags = Vec::new();
+        while !cursor.after_end() {
+            for line in &cursor.lines() {
+                let address = line.contents.address;
+                tags.extend(line.contents.tags.iter().map(|tag| (address, tag.clone())));
+            }
+            if !cursor.next() {
+                break;
+            }
+        }
+        tags
+    }
+
     /// List of indirect branches
     pub fn indirect_branches(&self) -> Array<IndirectBranchInfo> {
         let mut count = 0;
@@ -1640,10 +1662,7 @@ impl Function {
     /// ```no_run
     /// # use binaryninja::function::{HighlightColor, HighlightStandardColor};
     /// # let function: binaryninja::function::Function = todo!();
-    /// let color = HighlightColor::StandardHighlightColor {
-    ///     color: HighlightStandardColor::RedHighlightColor,
-    ///     alpha: u8::MAX,
-    /// };
+    /// let color = HighlightColor::standard(HighlightStandardColor::RedHighlightColor);
     /// function.set_user_instr_highlight(0x1337, color, None);
     /// ```
     pub fn set_user_instr_highlight(
@@ -2673,6 +2692,29 @@ pub enum HighlightColor {
     },
 }
 
+impl HighlightColor {
+    /// A standard highlight color at full opacity.
+    pub fn standard(color: HighlightStandardColor) -> Self {
+        Self::StandardHighlightColor {
+            color,
+            alpha: u8::MAX,
+        }
+    }
+
+    /// A custom RGB highlight color with the given alpha.
+    pub fn custom(r: u8, g: u8, b: u8, alpha: u8) -> Self {
+        Self::CustomHighlightColor { r, g, b, alpha }
+    }
+
+    /// No highlight, this is the same as [`HighlightColor::default`].
+    pub fn none() -> Self {
+        Self::StandardHighlightColor {
+            color: HighlightStandardColor::NoHighlightColor,
+            alpha: 0,
+        }
+    }
+}
+
 impl From<BNHighlightColor> for HighlightColor {
     fn from(value: BNHighlightColor) -> Self {
         match value.style {
@@ -2732,10 +2774,7 @@ impl From<HighlightColor> for BNHighlightColor {
 
 impl Default for HighlightColor {
     fn default() -> Self {
-        Self::StandardHighlightColor {
-            color: HighlightStandardColor::NoHighlightColor,
-            alpha: 0,
-        }
+        Self::none()
     }
 }
 
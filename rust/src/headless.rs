@@ -274,7 +274,7 @@ pub fn license_location() -> Option<LicenseLocation> {
         Ok(_) => Some(LicenseLocation::EnvironmentVariable),
         Err(_) => {
             // Check the license_path to see if a file is there.
-            if license_path().exists() {
+            if license_path().is_ok_and(|path| path.exists()) {
                 Some(LicenseLocation::File)
             } else {
                 None
@@ -106,6 +106,7 @@ pub use binaryninjacore_sys::BNBranchType as BranchType;
 pub use binaryninjacore_sys::BNDataFlowQueryOption as DataFlowQueryOption;
 pub use binaryninjacore_sys::BNEndianness as Endianness;
 pub use binaryninjacore_sys::BNILBranchDependence as ILBranchDependence;
+pub use disassembly::DisassemblyOptionExt;
 
 pub const BN_FULL_CONFIDENCE: u8 = u8::MAX;
 pub const BN_INVALID_EXPR: usize = usize::MAX;
@@ -270,11 +271,13 @@ where
     }
 }
 
-pub fn install_directory() -> PathBuf {
-    let install_dir_ptr: *mut c_char = unsafe { BNGetInstallDirectory() };
-    assert!(!install_dir_ptr.is_null());
-    let bn_install_dir = unsafe { BnString::from_raw(install_dir_ptr) };
-    PathBuf::from(bn_install_dir.to_string())
+/// The directory Binary Ninja is installed in, this can fail in embedded or unusual headless environments.
+pub fn install_directory() -> Result<PathBuf, ()> {
+    let s: *mut c_char = unsafe { BNGetInstallDirectory() };
+    if s.is_null() {
+        return Err(());
+    }
+    Ok(PathBuf::from(unsafe { BnString::from_raw(s) }.to_string()))
 }
 
 pub fn bundled_plugin_directory() -> Result<PathBuf, ()> {
@@ -290,11 +293,13 @@ pub fn set_bundled_plugin_directory(new_dir: impl AsRef<Path>) {
     unsafe { BNSetBundledPluginDirectory(new_dir.as_ptr() as *const c_char) };
 }
 
-pub fn user_directory() -> PathBuf {
-    let user_dir_ptr: *mut c_char = unsafe { BNGetUserDirectory() };
-    assert!(!user_dir_ptr.is_null());
-    let bn_user_dir = unsafe { BnString::from_raw(user_dir_ptr) };
-    PathBuf::from(bn_user_dir.to_string())
+/// The user directory, this can fail if there is no user directory (e.g. user settings are disabled).
+pub fn user_directory() -> Result<PathBuf, ()> {
+    let s: *mut c_char = unsafe { BNGetUserDirectory() };
+    if s.is_null() {
+        return Err(());
+    }
+    Ok(PathBuf::from(unsafe { BnString::from_raw(s) }.to_string()))
 }
 
 pub fn user_plugin_directory() -> Result<PathBuf, ()> {
@@ -471,8 +476,8 @@ pub fn licensed_user_email() -> BnString {
     unsafe { BnString::from_raw(BNGetLicensedUserEmail()) }
 }
 
-pub fn license_path() -> PathBuf {
-    user_directory().join("license.dat")
+pub fn license_path() -> Result<PathBuf, ()> {
+    Ok(user_directory()?.join("license.dat"))
 }
 
 pub fn license_count() -> i32 {
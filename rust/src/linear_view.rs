@@ -18,7 +18,7 @@ use binaryninjacore_sys::*;
 
 use crate::binary_view::BinaryView;
 use crate::disassembly::{DisassemblySettings, DisassemblyTextLine};
-use crate::function::{Function, NativeBlock};
+use crate::function::{Function, FunctionViewType, NativeBlock};
 
 use crate::basic_block::BasicBlock;
 use crate::rc::*;
@@ -98,20 +98,26 @@ impl LinearViewObject {
         }
     }
 
+    /// Create a linear view object for the language representation named `language`.
+    ///
+    /// Returns `None` if no language representation with that name is registered, see
+    /// [`language_representation_names`] for the available names.
     pub fn language_representation(
         view: &BinaryView,
         settings: &DisassemblySettings,
         language: &str,
-    ) -> Ref<Self> {
+    ) -> Option<Ref<Self>> {
+        let language = std::ffi::CString::new(language).ok()?;
+        if !is_language_representation_registered(&language) {
+            return None;
+        }
         unsafe {
-            let language = std::ffi::CString::new(language).unwrap();
             let handle = BNCreateLinearViewLanguageRepresentation(
                 view.handle,
                 settings.handle,
                 language.as_ptr(),
             );
-
-            Self::ref_from_raw(handle)
+            Some(Self::ref_from_raw(handle))
         }
     }
 
@@ -178,22 +184,109 @@ impl LinearViewObject {
         }
     }
 
+    /// Create a linear view object for the language representation named `language` of a single function.
+    ///
+    /// Returns `None` if no language representation with that name is registered, see
+    /// [`language_representation_names`] for the available names.
     pub fn single_function_language_representation(
         function: &Function,
         settings: &DisassemblySettings,
         language: &str,
-    ) -> Ref<Self> {
+    ) -> Option<Ref<Self>> {
+        let language = std::ffi::CString::new(language).ok()?;
+        if !is_language_representation_registered(&language) {
+            return None;
+        }
         unsafe {
-            let language = std::ffi::CString::new(language).unwrap();
             let handle = BNCreateLinearViewSingleFunctionLanguageRepresentation(
                 function.handle,
                 settings.handle,
                 language.as_ptr(),
             );
-            Self::ref_from_raw(handle)
+            Some(Self::ref_from_raw(handle))
+        }
+    }
+
+    /// Create a linear view object of a single function in the given [`FunctionViewType`].
+    ///
+    /// Returns `None` if the view type is a language representation which is not registered.
+    pub fn single_function(
+        function: &Function,
+        settings: &DisassemblySettings,
+        view_type: &FunctionViewType,
+    ) -> Option<Ref<Self>> {
+        let create = match view_type {
+            FunctionViewType::Normal => BNCreateLinearViewSingleFunctionDisassembly,
+            FunctionViewType::LowLevelIL => BNCreateLinearViewSingleFunctionLowLevelIL,
+            FunctionViewType::LiftedIL => BNCreateLinearViewSingleFunctionLiftedIL,
+            FunctionViewType::LowLevelILSSAForm => {
+                BNCreateLinearViewSingleFunctionLowLevelILSSAForm
+            }
+            FunctionViewType::MediumLevelIL => BNCreateLinearViewSingleFunctionMediumLevelIL,
+            FunctionViewType::MediumLevelILSSAForm => {
+                BNCreateLinearViewSingleFunctionMediumLevelILSSAForm
+            }
+            FunctionViewType::MappedMediumLevelIL => {
+                BNCreateLinearViewSingleFunctionMappedMediumLevelIL
+            }
+            FunctionViewType::MappedMediumLevelILSSAForm => {
+                BNCreateLinearViewSingleFunctionMappedMediumLevelILSSAForm
+            }
+            FunctionViewType::HighLevelIL => BNCreateLinearViewSingleFunctionHighLevelIL,
+            FunctionViewType::HighLevelILSSAForm => {
+                BNCreateLinearViewSingleFunctionHighLevelILSSAForm
+            }
+            FunctionViewType::HighLevelLanguageRepresentation(language) => {
+                return Self::single_function_language_representation(function, settings, language)
+            }
+        };
+        unsafe {
+            let handle = create(function.handle, settings.handle);
+            Some(Self::ref_from_raw(handle))
+        }
+    }
+
+    /// Get the lines of a single function in the given [`FunctionViewType`] for the address `range`.
+    ///
+    /// Only the lines within the range are generated, so a slice of a huge function can be rendered
+    /// without generating the entire function, see [`LinearViewCursor::lines_in_range`].
+    ///
+    /// Returns `None` if the view type is a language representation which is not registered.
+    pub fn single_function_range(
+        function: &Function,
+        settings: &DisassemblySettings,
+        range: std::ops::Range<u64>,
+        view_type: &FunctionViewType,
+    ) -> Option<Vec<LinearDisassemblyLine>> {
+        let object = Self::single_function(function, settings, view_type)?;
+        let mut cursor = object.create_cursor();
+        Some(cursor.lines_in_range(range))
+    }
+
+    /// The `[start, end)` addresses covered by this object, `None` if it covers no addresses.
+    ///
+    /// For an object over the whole view this is the span from the lowest to the highest mapped
+    /// address, for binaries with multiple regions it includes the gaps between them. Use this to
+    /// check an address before [`LinearViewCursor::seek_to_address`], so the cursor does not end up
+    /// [`LinearViewCursor::before_begin`] or [`LinearViewCursor::after_end`].
+    pub fn address_range(&self) -> Option<std::ops::Range<u64>> {
+        let start = unsafe { BNGetLinearViewObjectStart(self.handle) };
+        let end = unsafe { BNGetLinearViewObjectEnd(self.handle) };
+        match start < end {
+            true => Some(start..end),
+            false => None,
         }
     }
 
+    /// The total size of the ordering index space for this object.
+    ///
+    /// The core does not know how many lines an object will produce until they are generated, use
+    /// this as the denominator when reporting progress over the object, see
+    /// [`LinearViewCursor::ordering_index_total`] for an example.
+    pub fn ordering_index_total(&self) -> u64 {
+        unsafe { BNGetLinearViewObjectOrderingIndexTotal(self.handle) }
+    }
+
     pub fn create_cursor(&self) -> Ref<LinearViewCursor> {
         unsafe {
             let handle = BNCreateLinearViewCursor(self.handle);
@@ -202,6 +295,30 @@ impl LinearViewObject {
     }
 }
 
+/// The names of all registered language representations, such as "Pseudo C".
+///
+/// These are the valid names to pass to [`LinearViewObject::language_representation`].
+pub fn language_representation_names() -> Vec<String> {
+    let mut count = 0;
+    unsafe {
+        let types = BNGetLanguageRepresentationFunctionTypeList(&mut count);
+        if types.is_null() {
+            return Vec::new();
+        }
+        let names = std::slice::from_raw_parts(types, count)
+            .iter()
+            .map(|&ty| BnString::from_raw(BNGetLanguageRepresentationFunctionTypeName(ty)))
+            .map(|name| name.to_string())
+            .collect();
+        BNFreeLanguageRepresentationFunctionTypeList(types);
+        names
+    }
+}
+
+fn is_language_representation_registered(language: &std::ffi::CStr) -> bool {
+    unsafe { !BNGetLanguageRepresentationFunctionTypeByName(language.as_ptr()).is_null() }
+}
+
 unsafe impl RefCountable for LinearViewObject {
     unsafe fn inc_ref(handle: &Self) -> Ref<Self> {
         Ref::new(Self {
@@ -265,6 +382,11 @@ impl LinearViewObjectIdentifier {
 }
 
 // TODO: Impl iterator?
+/// A position within a [`LinearViewObject`], created with [`LinearViewObject::create_cursor`].
+///
+/// NOTE: Cursors are only ordered relative to other cursors of the same root [`LinearViewObject`],
+/// the core does not define the result of comparing cursors of different objects. Use
+/// [`LinearViewCursor::same_object`] to check before comparing cursors of unknown origin.
 #[derive(Eq)]
 pub struct LinearViewCursor {
     pub(crate) handle: *mut BNLinearViewCursor,
@@ -276,6 +398,29 @@ impl LinearViewCursor {
         Ref::new(Self { handle })
     }
 
+    /// Gets the root [`LinearViewObject`] this cursor was created from.
+    pub fn root_object(&self) -> Option<Ref<LinearViewObject>> {
+        let mut count = 0;
+        unsafe {
+            let handles = BNGetLinearViewCursorPathObjects(self.handle, &mut count);
+            if handles.is_null() {
+                return None;
+            }
+            let root = (count > 0)
+                .then(|| LinearViewObject::ref_from_raw(BNNewLinearViewObjectReference(*handles)));
+            BNFreeLinearViewCursorPathObjects(handles, count);
+            root
+        }
+    }
+
+    /// Whether both cursors walk the same root [`LinearViewObject`], only then is comparing them meaningful.
+    pub fn same_object(&self, other: &LinearViewCursor) -> bool {
+        match (self.root_object(), other.root_object()) {
+            (Some(root), Some(other_root)) => root.handle == other_root.handle,
+            _ => false,
+        }
+    }
+
     /// Gets the current [`LinearViewObject`] associated with this cursor.
     pub fn current_object(&self) -> Ref<LinearViewObject> {
         unsafe {
@@ -322,6 +467,32 @@ impl LinearViewCursor {
         }
     }
 
+    /// The total size of the ordering index space for the cursor's root object.
+    ///
+    /// There is no cheap way to count the lines of a linear view without generating them, instead
+    /// the ordering index total is the intended denominator for progress reporting, with
+    /// [`LinearViewCursor::ordering_index`] being the numerator.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use binaryninja::disassembly::DisassemblySettings;
+    /// # use binaryninja::linear_view::LinearViewObject;
+    /// # let view: binaryninja::binary_view::BinaryView = todo!();
+    /// let settings = DisassemblySettings::new();
+    /// let object = LinearViewObject::disassembly(&view, &settings);
+    /// let mut cursor = object.create_cursor();
+    /// let total = cursor.ordering_index_total();
+    /// while !cursor.after_end() {
+    ///     let progress = cursor.ordering_index().start;
+    ///     println!("{}/{}", progress, total);
+    ///     for line in &cursor.lines() {
+    ///         println!("{}", line);
+    ///     }
+    ///     if !cursor.next() {
+    ///         break;
+    ///     }
+    /// }
+    /// ```
     pub fn ordering_index_total(&self) -> u64 {
         unsafe { BNGetLinearViewCursorOrderingIndexTotal(self.handle) }
     }
@@ -341,6 +512,10 @@ impl LinearViewCursor {
         unsafe { BNLinearViewCursorNext(self.handle) }
     }
 
+    /// The lines at the cursor position, in the array returned by the core.
+    ///
+    /// Each line is copied out of the array as it is accessed, use [`LinearViewCursor::lines_owned`]
+    /// to buffer the lines of multiple cursor positions.
     pub fn lines(&self) -> Array<LinearDisassemblyLine> {
         let mut count: usize = 0;
         unsafe {
@@ -349,6 +524,44 @@ impl LinearViewCursor {
         }
     }
 
+    /// The lines at the cursor position, copied out of the core array.
+    ///
+    /// The lines hold their own reference to the function and basic block, so they stay valid after
+    /// the cursor moves. This is the supported way to buffer cursor output.
+    pub fn lines_owned(&self) -> Vec<LinearDisassemblyLine> {
+        self.lines().iter().collect()
+    }
+
+    /// Seek to the start of `range` and collect the lines with an address within the range.
+    ///
+    /// Iteration stops at the first position with no lines in the range and a line past the end of
+    /// it. IL lines are not strictly ordered by address, lines outside the range that are interleaved
+    /// with lines in the range are skipped.
+    pub fn lines_in_range(&mut self, range: std::ops::Range<u64>) -> Vec<LinearDisassemblyLine> {
+        let mut range_lines = Vec::new();
+        self.seek_to_address(range.start);
+        while !self.after_end() {
+            let lines = self.lines();
+            let mut past_end = false;
+            let mut in_range = false;
+            for line in &lines {
+                if range.contains(&line.contents.address) {
+                    in_range = true;
+                    range_lines.push(line);
+                } else if line.contents.address >= range.end {
+                    past_end = true;
+                }
+            }
+            if past_end && !in_range {
+                break;
+            }
+            if !self.next() {
+                break;
+            }
+        }
+        range_lines
+    }
+
     /// A list of the currently applied [`CoreRenderLayer`]'s
     pub fn render_layers(&self) -> Array<CoreRenderLayer> {
         let mut count: usize = 0;
@@ -373,6 +586,10 @@ impl LinearViewCursor {
 
 impl PartialEq for LinearViewCursor {
     fn eq(&self, other: &Self) -> bool {
+        debug_assert!(
+            self.same_object(other),
+            "Comparing cursors of different LinearViewObject's is undefined"
+        );
         unsafe { BNCompareLinearViewCursors(self.handle, other.handle) == 0 }
     }
 }
@@ -385,6 +602,10 @@ impl PartialOrd for LinearViewCursor {
 
 impl Ord for LinearViewCursor {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        debug_assert!(
+            self.same_object(other),
+            "Comparing cursors of different LinearViewObject's is undefined"
+        );
         match unsafe { BNCompareLinearViewCursors(self.handle, other.handle) } {
             i if i < 0 => std::cmp::Ordering::Less,
             i if i > 0 => std::cmp::Ordering::Greater,
@@ -416,15 +637,152 @@ impl ToOwned for LinearViewCursor {
 unsafe impl Send for LinearViewCursor {}
 unsafe impl Sync for LinearViewCursor {}
 
+/// The kind of a [`LinearDisassemblyLine`], a typed form of [`LinearDisassemblyLineType`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum LinearLineKind {
+    Blank,
+    Basic,
+    Code,
+    DataVariable,
+    HexDump,
+    FunctionHeader,
+    FunctionHeaderStart,
+    FunctionHeaderEnd,
+    FunctionContinuation,
+    LocalVariable,
+    LocalVariableListEnd,
+    FunctionEnd,
+    NoteStart,
+    Note,
+    NoteEnd,
+    SectionStart,
+    SectionEnd,
+    SectionSeparator,
+    NonContiguousSeparator,
+    AnalysisWarning,
+    CollapsedFunctionEnd,
+}
+
+impl LinearLineKind {
+    /// Whether the line is a disassembled instruction (or IL) of a function.
+    pub fn is_code(&self) -> bool {
+        matches!(self, LinearLineKind::Code)
+    }
+
+    /// Whether the line is part of the header listed before the code of a function, including
+    /// the local variable list.
+    pub fn is_function_header(&self) -> bool {
+        matches!(
+            self,
+            LinearLineKind::FunctionHeader
+                | LinearLineKind::FunctionHeaderStart
+                | LinearLineKind::FunctionHeaderEnd
+                | LinearLineKind::LocalVariable
+                | LinearLineKind::LocalVariableListEnd
+        )
+    }
+
+    /// Whether the line describes data rather than code.
+    pub fn is_data(&self) -> bool {
+        matches!(self, LinearLineKind::DataVariable | LinearLineKind::HexDump)
+    }
+
+    /// Whether the line marks the start or end of a section.
+    pub fn is_section_marker(&self) -> bool {
+        matches!(
+            self,
+            LinearLineKind::SectionStart
+                | LinearLineKind::SectionEnd
+                | LinearLineKind::SectionSeparator
+        )
+    }
+
+    /// Whether the line only separates other lines, it has no content of its own.
+    pub fn is_separator(&self) -> bool {
+        matches!(
+            self,
+            LinearLineKind::Blank
+                | LinearLineKind::SectionSeparator
+                | LinearLineKind::NonContiguousSeparator
+        )
+    }
+}
+
+impl From<LinearDisassemblyLineType> for LinearLineKind {
+    fn from(ty: LinearDisassemblyLineType) -> Self {
+        use self::BNLinearDisassemblyLineType::*;
+
+        match ty {
+            BlankLineType => LinearLineKind::Blank,
+            BasicLineType => LinearLineKind::Basic,
+            CodeDisassemblyLineType => LinearLineKind::Code,
+            DataVariableLineType => LinearLineKind::DataVariable,
+            HexDumpLineType => LinearLineKind::HexDump,
+            FunctionHeaderLineType => LinearLineKind::FunctionHeader,
+            FunctionHeaderStartLineType => LinearLineKind::FunctionHeaderStart,
+            FunctionHeaderEndLineType => LinearLineKind::FunctionHeaderEnd,
+            FunctionContinuationLineType => LinearLineKind::FunctionContinuation,
+            LocalVariableLineType => LinearLineKind::LocalVariable,
+            LocalVariableListEndLineType => LinearLineKind::LocalVariableListEnd,
+            FunctionEndLineType => LinearLineKind::FunctionEnd,
+            NoteStartLineType => LinearLineKind::NoteStart,
+            NoteLineType => LinearLineKind::Note,
+            NoteEndLineType => LinearLineKind::NoteEnd,
+            SectionStartLineType => LinearLineKind::SectionStart,
+            SectionEndLineType => LinearLineKind::SectionEnd,
+            SectionSeparatorLineType => LinearLineKind::SectionSeparator,
+            NonContiguousSeparatorLineType => LinearLineKind::NonContiguousSeparator,
+            AnalysisWarningLineType => LinearLineKind::AnalysisWarning,
+            CollapsedFunctionEndLineType => LinearLineKind::CollapsedFunctionEnd,
+        }
+    }
+}
+
+impl From<LinearLineKind> for LinearDisassemblyLineType {
+    fn from(kind: LinearLineKind) -> Self {
+        use self::BNLinearDisassemblyLineType::*;
+
+        match kind {
+            LinearLineKind::Blank => BlankLineType,
+            LinearLineKind::Basic => BasicLineType,
+            LinearLineKind::Code => CodeDisassemblyLineType,
+            LinearLineKind::DataVariable => DataVariableLineType,
+            LinearLineKind::HexDump => HexDumpLineType,
+            LinearLineKind::FunctionHeader => FunctionHeaderLineType,
+            LinearLineKind::FunctionHeaderStart => FunctionHeaderStartLineType,
+            LinearLineKind::FunctionHeaderEnd => FunctionHeaderEndLineType,
+            LinearLineKind::FunctionContinuation => FunctionContinuationLineType,
+            LinearLineKind::LocalVariable => LocalVariableLineType,
+            LinearLineKind::LocalVariableListEnd => LocalVariableListEndLineType,
+            LinearLineKind::FunctionEnd => FunctionEndLineType,
+            LinearLineKind::NoteStart => NoteStartLineType,
+            LinearLineKind::Note => NoteLineType,
+            LinearLineKind::NoteEnd => NoteEndLineType,
+            LinearLineKind::SectionStart => SectionStartLineType,
+            LinearLineKind::SectionEnd => SectionEndLineType,
+            LinearLineKind::SectionSeparator => SectionSeparatorLineType,
+            LinearLineKind::NonContiguousSeparator => NonContiguousSeparatorLineType,
+            LinearLineKind::AnalysisWarning => AnalysisWarningLineType,
+            LinearLineKind::CollapsedFunctionEnd => CollapsedFunctionEndLineType,
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Eq)]
 pub struct LinearDisassemblyLine {
     pub ty: LinearDisassemblyLineType,
+    /// The function the line belongs to, `None` for lines outside of a function (e.g. data variables).
     pub function: Option<Ref<Function>>,
     pub basic_block: Option<Ref<BasicBlock<NativeBlock>>>,
     pub contents: DisassemblyTextLine,
 }
 
 impl LinearDisassemblyLine {
+    /// The kind of the line, see [`LinearLineKind`].
+    pub fn kind(&self) -> LinearLineKind {
+        self.ty.into()
+    }
+
     pub(crate) unsafe fn from_raw(value: &BNLinearDisassemblyLine) -> Self {
         let function = if !value.function.is_null() {
             Some(unsafe { Function::from_raw(value.function).to_owned() })
@@ -469,7 +827,13 @@ impl LinearDisassemblyLine {
     }
 
     pub(crate) fn free_raw(value: BNLinearDisassemblyLine) {
-        let _ = unsafe { Function::ref_from_raw(value.function) };
+        // Lines outside of a function (e.g. data variables and section headers) have no function or block.
+        if !value.function.is_null() {
+            let _ = unsafe { Function::ref_from_raw(value.function) };
+        }
+        if !value.block.is_null() {
+            let _ = unsafe { BasicBlock::ref_from_raw(value.block, NativeBlock::new()) };
+        }
         DisassemblyTextLine::free_raw(value.contents);
     }
 }
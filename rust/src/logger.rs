@@ -41,9 +41,18 @@ use log::LevelFilter;
 use std::ffi::{CStr, CString};
 use std::os::raw::{c_char, c_void};
 use std::ptr::NonNull;
+use thiserror::Error;
 
 const LOGGER_DEFAULT_SESSION_ID: usize = 0;
 
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum LoggerError {
+    #[error("logger name {0:?} contains a nul byte")]
+    InvalidName(String),
+    #[error("core failed to create logger {0:?}")]
+    CreationFailed(String),
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Logger {
     handle: NonNull<BNLogger>,
@@ -56,13 +65,28 @@ impl Logger {
     }
 
     pub fn new_with_session(name: &str, session_id: usize) -> Ref<Logger> {
-        let name_raw = CString::new(name).unwrap();
+        Self::try_new_with_session(name, session_id).expect("Failed to create logger")
+    }
+
+    /// Create the logger, returning an error instead of panicking if the name is not a valid
+    /// C string or the core fails to create the logger.
+    ///
+    /// Use this over [`Logger::new`] when the name is not known ahead of time, e.g. read from a config file.
+    pub fn try_new(name: &str) -> Result<Ref<Logger>, LoggerError> {
+        Self::try_new_with_session(name, LOGGER_DEFAULT_SESSION_ID)
+    }
+
+    pub fn try_new_with_session(name: &str, session_id: usize) -> Result<Ref<Logger>, LoggerError> {
+        let name_raw =
+            CString::new(name).map_err(|_| LoggerError::InvalidName(name.to_string()))?;
         let handle = unsafe { BNLogCreateLogger(name_raw.as_ptr(), session_id) };
+        let handle =
+            NonNull::new(handle).ok_or_else(|| LoggerError::CreationFailed(name.to_string()))?;
         unsafe {
-            Ref::new(Logger {
-                handle: NonNull::new(handle).unwrap(),
+            Ok(Ref::new(Logger {
+                handle,
                 level: LevelFilter::Debug,
-            })
+            }))
         }
     }
 
@@ -169,6 +193,18 @@ pub struct LogGuard<L: LogListener> {
     ctxt: *mut L,
 }
 
+impl<L: LogListener> LogGuard<L> {
+    /// The registered listener, useful for reading state captured by the listener without unregistering it.
+    ///
+    /// The core may call [`LogListener::log`] on another thread while this reference is held, only a
+    /// shared reference is ever handed out and `L` is required to be [`Sync`], so any state the
+    /// listener mutates must use interior mutability (e.g. a `Mutex`).
+    pub fn listener(&self) -> &L {
+        // SAFETY: The listener is boxed and only freed once the guard is dropped.
+        unsafe { &*self.ctxt }
+    }
+}
+
 impl<L: LogListener> Drop for LogGuard<L> {
     fn drop(&mut self) {
         use binaryninjacore_sys::BNUnregisterLogListener;
@@ -1620,6 +1620,15 @@ impl Structure {
         unsafe { BNGetStructureWidth(self.handle) }
     }
 
+    pub fn alignment(&self) -> usize {
+        unsafe { BNGetStructureAlignment(self.handle) }
+    }
+
+    /// Whether the structure is packed, the alignment of a packed structure is `1`.
+    pub fn is_packed(&self) -> bool {
+        unsafe { BNIsStructurePacked(self.handle) }
+    }
+
     pub fn structure_type(&self) -> StructureType {
         unsafe { BNGetStructureType(self.handle) }
     }
@@ -24,6 +24,14 @@ fn test_background_task_registered(_session: &Session) {
     assert!(!still_running, "Task still running");
 }
 
+#[rstest]
+fn test_background_task_try_new(_session: &Session) {
+    let task = BackgroundTask::try_new("test try new", true).expect("Failed to create task");
+    assert!(task.can_cancel());
+    task.finish();
+    assert!(task.is_finished());
+}
+
 #[rstest]
 fn test_background_task_cancellable(_session: &Session) {
     let task_progress = "test cancellable";
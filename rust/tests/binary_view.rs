@@ -21,6 +21,21 @@ fn test_binary_loading(_session: &Session) {
     assert_eq!(view.file().is_database_backed(), false);
 }
 
+#[rstest]
+fn test_instruction_text_at(_session: &Session) {
+    let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
+    let view = binaryninja::load(out_dir.join("atox.obj")).expect("Failed to create view");
+    let function = view
+        .entry_point_function()
+        .expect("Failed to get entry point function");
+    let text = view
+        .instruction_text_at(function.start())
+        .expect("No instruction at function start");
+    assert!(!text.trim().is_empty());
+    // Past the end of the view is never code.
+    assert_eq!(view.instruction_text_at(view.end() + 0x1000), None);
+}
+
 #[rstest]
 fn test_binary_saving(_session: &Session) {
     let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
@@ -0,0 +1,428 @@
+use binaryninja::architecture::{Architecture, CoreArchitecture};
+use binaryninja::binary_view::{BinaryView, BinaryViewExt};
+use binaryninja::disassembly::{
+    DisassemblyOption, DisassemblyOptionError, DisassemblyOptionExt, DisassemblyOptionGroup,
+    DisassemblySettings, DisassemblyTextLine, DisassemblyTextLineTypeInfo, InstructionTextToken,
+    InstructionTextTokenKind, PlainDisassemblyLine, StringType,
+};
+use binaryninja::file_metadata::FileMetadata;
+use binaryninja::function::{HighlightColor, HighlightStandardColor};
+use binaryninja::headless::Session;
+use binaryninja::symbol::{SymbolBuilder, SymbolType};
+use binaryninja::types::{MemberAccess, MemberScope, StructureBuilder, Type};
+use binaryninjacore_sys::{
+    BNInstructionTextToken, BNInstructionTextTokenContext, BNInstructionTextTokenType,
+};
+use rstest::*;
+use std::ffi::CString;
+
+#[fixture]
+#[once]
+fn session() -> Session {
+    Session::new().expect("Failed to initialize session")
+}
+
+#[rstest]
+fn test_disassembly_settings_width(_session: &Session) {
+    let settings = DisassemblySettings::new();
+    settings.set_width(80);
+    assert_eq!(settings.width(), 80);
+    settings.set_maximum_symbol_width(32);
+    assert_eq!(settings.maximum_symbol_width(), 32);
+    // Zero is unlimited.
+    settings.set_width(0);
+    assert_eq!(settings.width(), 0);
+}
+
+#[rstest]
+fn test_disassembly_settings_set_options(_session: &Session) {
+    let settings = DisassemblySettings::new();
+    settings.set_option(DisassemblyOption::ShowOpcode, false);
+    settings.set_option(DisassemblyOption::ExpandLongOpcode, false);
+    assert_eq!(
+        settings.set_options(&[DisassemblyOption::ExpandLongOpcode]),
+        Err(DisassemblyOptionError::MissingRequiredOption {
+            option: DisassemblyOption::ExpandLongOpcode,
+            required: DisassemblyOption::ShowOpcode,
+        })
+    );
+    assert!(!settings.is_option_set(DisassemblyOption::ExpandLongOpcode));
+
+    let options = [
+        DisassemblyOption::ShowOpcode,
+        DisassemblyOption::ExpandLongOpcode,
+        DisassemblyOption::WaitForIL,
+    ];
+    assert_eq!(settings.set_options(&options), Ok(()));
+    for option in options {
+        assert!(settings.is_option_set(option));
+    }
+}
+
+#[rstest]
+fn test_disassembly_option_groups() {
+    for group in [
+        DisassemblyOptionGroup::General,
+        DisassemblyOptionGroup::Linear,
+        DisassemblyOptionGroup::Debugging,
+    ] {
+        for &option in group.options() {
+            assert_eq!(DisassemblyOptionGroup::from(option), group);
+        }
+    }
+}
+
+#[rstest]
+fn test_disassembly_option_names() {
+    for group in DisassemblyOptionGroup::ALL {
+        for &option in group.options() {
+            let name = option.name();
+            assert_eq!(DisassemblyOption::from_name(name), Some(option));
+        }
+    }
+    assert_eq!(
+        DisassemblyOption::from_name("ShowAddress"),
+        Some(DisassemblyOption::ShowAddress)
+    );
+    assert_eq!(DisassemblyOption::from_name("showaddress"), None);
+    assert_eq!(DisassemblyOption::from_name("NotAnOption"), None);
+}
+
+#[rstest]
+fn test_disassembly_settings_set_options_by_name(_session: &Session) {
+    let settings = DisassemblySettings::new();
+    settings.set_option(DisassemblyOption::ShowAddress, false);
+    settings.set_option(DisassemblyOption::ShowOpcode, false);
+    assert_eq!(
+        settings.set_options_by_name(&["ShowAddress", "NotAnOption"]),
+        Err(DisassemblyOptionError::UnknownOption(
+            "NotAnOption".to_string()
+        ))
+    );
+    assert!(!settings.is_option_set(DisassemblyOption::ShowAddress));
+
+    settings
+        .set_options_by_name(&["ShowAddress", "ShowOpcode"])
+        .expect("Failed to set options");
+    assert!(settings.is_option_set(DisassemblyOption::ShowAddress));
+    assert!(settings.is_option_set(DisassemblyOption::ShowOpcode));
+}
+
+#[rstest]
+fn test_hex_dump_line(_session: &Session) {
+    let bytes = b"Hello,\x00World!\xff\x01\x02";
+    let line = DisassemblyTextLine::hex_dump(0x1000, bytes, 16);
+    assert_eq!(line.address, 0x1000);
+    // 16 byte values, 15 separators, the text column separator and the text column.
+    assert_eq!(line.tokens.len(), 33);
+
+    let byte_tokens: Vec<_> = line
+        .tokens
+        .iter()
+        .filter(|t| matches!(t.kind, InstructionTextTokenKind::HexDumpByteValue { .. }))
+        .collect();
+    assert_eq!(byte_tokens.len(), 16);
+    for (i, token) in byte_tokens.iter().enumerate() {
+        assert_eq!(token.address, 0x1000 + i as u64);
+        assert_eq!(
+            token.kind,
+            InstructionTextTokenKind::HexDumpByteValue { value: bytes[i] }
+        );
+        assert_eq!(token.text, format!("{:02x}", bytes[i]));
+    }
+
+    let text_token = line.tokens.last().unwrap();
+    assert_eq!(
+        text_token.kind,
+        InstructionTextTokenKind::HexDumpText { width: 16 }
+    );
+    assert_eq!(text_token.text, "Hello,.World!...");
+    assert_eq!(
+        line.to_string(),
+        "48 65 6c 6c 6f 2c 00 57 6f 72 6c 64 21 ff 01 02  Hello,.World!..."
+    );
+}
+
+#[rstest]
+fn test_disassembly_text_line_with_raw(_session: &Session) {
+    let line = DisassemblyTextLine::hex_dump(0x1000, b"abcd", 4);
+    let token_count = line.tokens.len();
+    let (address, count) = line.with_raw(|raw| (raw.addr, raw.count));
+    assert_eq!(address, 0x1000);
+    assert_eq!(count, token_count);
+}
+
+#[rstest]
+fn test_disassembly_text_line_into_plain(_session: &Session) {
+    let line = DisassemblyTextLine::hex_dump(0x1000, b"abcd", 4);
+    let text = line.to_string();
+    let tokens = line.tokens.clone();
+    let plain = line.into_plain();
+    assert_eq!(plain.address, 0x1000);
+    assert_eq!(plain.tokens, tokens);
+    assert_eq!(plain.to_string(), text);
+    assert!(plain.tags.is_empty());
+    assert_eq!(plain.parent_type, None);
+
+    // Plain lines can be moved to other threads and outlive the view.
+    let handle = std::thread::spawn(move || plain.to_string());
+    assert_eq!(handle.join().unwrap(), text);
+    fn assert_owned<T: Send + Sync + 'static>() {}
+    assert_owned::<PlainDisassemblyLine>();
+}
+
+#[rstest]
+fn test_disassembly_text_line_memory_operands(_session: &Session) {
+    let arch = CoreArchitecture::by_name("x86_64").expect("Failed to get architecture");
+    // mov rax, qword [rax+0x8]
+    let (_, tokens) = arch
+        .instruction_text(&[0x48, 0x8b, 0x40, 0x08], 0x1000)
+        .expect("Failed to disassemble");
+    let line = DisassemblyTextLine::new(tokens);
+    let operands = line.memory_operands();
+    assert_eq!(operands.len(), 1);
+    let operand_text: String = operands[0].iter().map(|t| t.text.as_str()).collect();
+    assert!(operand_text.contains("rax+0x8"), "{}", operand_text);
+
+    // Nested operands stay in the outer operand, unbalanced markers are skipped.
+    let begin = || InstructionTextToken::new("", InstructionTextTokenKind::BeginMemoryOperand);
+    let end = || InstructionTextToken::new("", InstructionTextTokenKind::EndMemoryOperand);
+    let text = |text: &str| InstructionTextToken::new(text, InstructionTextTokenKind::Text);
+    let line = DisassemblyTextLine::new(vec![
+        end(),
+        begin(),
+        text("a"),
+        begin(),
+        text("b"),
+        end(),
+        end(),
+        text("c"),
+        begin(),
+        text("d"),
+    ]);
+    let operands = line.memory_operands();
+    assert_eq!(operands.len(), 1);
+    assert_eq!(operands[0], vec![text("a"), begin(), text("b"), end()]);
+}
+
+#[rstest]
+fn test_disassembly_text_line_content_eq(_session: &Session) {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+    let content_hash = |line: &DisassemblyTextLine| {
+        let mut hasher = DefaultHasher::new();
+        line.content_hash(&mut hasher);
+        hasher.finish()
+    };
+
+    let line = DisassemblyTextLine::from("xor eax, eax");
+    let mut moved_line = line.clone();
+    moved_line.address = 0x1000;
+    moved_line.instruction_index = 4;
+    assert_ne!(line, moved_line);
+    assert!(line.content_eq(&moved_line));
+    assert_eq!(content_hash(&line), content_hash(&moved_line));
+
+    let other_line = DisassemblyTextLine::from("ret");
+    assert!(!line.content_eq(&other_line));
+    assert_ne!(content_hash(&line), content_hash(&other_line));
+}
+
+#[rstest]
+fn test_disassembly_text_line_indent(_session: &Session) {
+    let mut line = DisassemblyTextLine::with_indent(2);
+    line.push_text("guid:")
+        .push_text(" ")
+        .push_token(InstructionTextToken::new(
+            "memcpy",
+            InstructionTextTokenKind::CodeSymbol { value: 0, size: 0 },
+        ));
+    assert_eq!(line.indent_level(), 2);
+    let tokens: Vec<_> = line
+        .tokens
+        .iter()
+        .map(|token| (token.text.as_str(), token.kind.clone()))
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            (
+                DisassemblyTextLine::INDENTATION,
+                InstructionTextTokenKind::Indentation
+            ),
+            (
+                DisassemblyTextLine::INDENTATION,
+                InstructionTextTokenKind::Indentation
+            ),
+            ("guid:", InstructionTextTokenKind::Text),
+            (" ", InstructionTextTokenKind::Text),
+            (
+                "memcpy",
+                InstructionTextTokenKind::CodeSymbol { value: 0, size: 0 }
+            ),
+        ]
+    );
+    assert_eq!(line.to_string(), "        guid: memcpy");
+    assert_eq!(DisassemblyTextLine::with_indent(0).indent_level(), 0);
+}
+
+#[test]
+fn test_disassembly_text_line_with_address() {
+    let tokens = vec![InstructionTextToken::new(
+        "ret",
+        InstructionTextTokenKind::Instruction,
+    )];
+    let highlight = HighlightColor::standard(HighlightStandardColor::RedHighlightColor);
+    let line =
+        DisassemblyTextLine::new_with_address(0x401000, tokens.clone()).with_highlight(highlight);
+    assert_eq!(line.address, 0x401000);
+    assert_eq!(line.tokens, tokens);
+    assert_eq!(line.highlight, highlight);
+    assert_eq!(
+        DisassemblyTextLine::new_with_address(0x401000, tokens.clone()).highlight,
+        DisassemblyTextLine::new(tokens).highlight
+    );
+}
+
+#[rstest]
+fn test_disassembly_text_line_resolve_targets(_session: &Session) {
+    let view =
+        BinaryView::from_data(&FileMetadata::new(), &[0xc3; 0x20]).expect("Failed to create view");
+    let symbol = SymbolBuilder::new(SymbolType::Function, "parse_header", 0x10).create();
+    view.define_user_symbol(&symbol);
+
+    let mut line = DisassemblyTextLine::new(vec![
+        InstructionTextToken::new("goto", InstructionTextTokenKind::Keyword),
+        InstructionTextToken::new(" ", InstructionTextTokenKind::Text),
+        InstructionTextToken::new(
+            "sub_10",
+            InstructionTextTokenKind::GotoLabel { target: 0x10 },
+        ),
+        InstructionTextToken::new(
+            "  // jumps to sub_10",
+            InstructionTextTokenKind::Comment { target: 0x10 },
+        ),
+        // No symbol at the target.
+        InstructionTextToken::new(
+            "label_18",
+            InstructionTextTokenKind::GotoLabel { target: 0x18 },
+        ),
+    ]);
+    line.resolve_targets(&view);
+    assert_eq!(line.tokens[2].text, "parse_header");
+    assert_eq!(line.tokens[3].text, "  // jumps to sub_10 (parse_header)");
+    assert_eq!(line.tokens[4].text, "label_18");
+    assert_eq!(
+        line.to_string(),
+        "goto parse_header  // jumps to sub_10 (parse_header)label_18"
+    );
+    // The name is not added to a comment again.
+    line.resolve_targets(&view);
+    assert_eq!(line.tokens[3].text, "  // jumps to sub_10 (parse_header)");
+}
+
+#[test]
+fn test_instruction_text_token_kind_values() {
+    let integer = InstructionTextTokenKind::Integer {
+        value: 0x1337,
+        size: Some(4),
+    };
+    assert_eq!(integer.value(), Some(0x1337));
+    assert_eq!(integer.size(), Some(4));
+    assert_eq!(integer.operand(), None);
+
+    let text = InstructionTextTokenKind::Text;
+    assert_eq!(
+        (text.value(), text.size(), text.operand()),
+        (None, None, None)
+    );
+
+    let import = InstructionTextTokenKind::IndirectImport {
+        target: 0x401000,
+        size: 6,
+        source_operand: 1,
+    };
+    assert_eq!(import.value(), Some(0x401000));
+    assert_eq!(import.size(), Some(6));
+    assert_eq!(import.operand(), Some(1));
+}
+
+#[rstest]
+fn test_type_info_resolved_member(_session: &Session) {
+    let mut builder = StructureBuilder::new();
+    builder.insert(
+        &Type::int(4, true),
+        "field_1",
+        0,
+        false,
+        MemberAccess::PublicAccess,
+        MemberScope::NoScope,
+    );
+    builder.insert(
+        &Type::int(8, false),
+        "field_2",
+        8,
+        false,
+        MemberAccess::PublicAccess,
+        MemberScope::NoScope,
+    );
+    let structure_type = Type::structure(&builder.finalize());
+
+    let mut line = DisassemblyTextLine::from("    uint64_t field_2;");
+    line.type_info = DisassemblyTextLineTypeInfo {
+        has_type_info: true,
+        parent_type: Some(structure_type.clone()),
+        field_index: 1,
+        offset: 8,
+    };
+    let member = line
+        .type_info
+        .resolved_member()
+        .expect("Failed to resolve member");
+    assert_eq!(member.name, "field_2");
+    assert_eq!(member.offset, 8);
+
+    let out_of_range = DisassemblyTextLineTypeInfo {
+        field_index: 2,
+        ..line.type_info.clone()
+    };
+    assert_eq!(out_of_range.resolved_member(), None);
+    let not_structure = DisassemblyTextLineTypeInfo {
+        parent_type: Some(Type::int(4, true)),
+        ..line.type_info.clone()
+    };
+    assert_eq!(not_structure.resolved_member(), None);
+    let no_type_info = DisassemblyTextLineTypeInfo {
+        has_type_info: false,
+        ..line.type_info
+    };
+    assert_eq!(no_type_info.resolved_member(), None);
+}
+
+#[rstest]
+fn test_unknown_string_type(_session: &Session) {
+    let text = CString::new("\"hello\"").unwrap();
+    // A string token with a string type unknown to this version, e.g. one added by a newer core.
+    let raw_token = BNInstructionTextToken {
+        type_: BNInstructionTextTokenType::StringToken,
+        text: text.as_ptr() as *mut _,
+        value: 0xff,
+        width: 0,
+        size: 0,
+        operand: 0,
+        context: BNInstructionTextTokenContext::NoTokenContext,
+        confidence: u8::MAX,
+        address: 0,
+        typeNames: std::ptr::null_mut(),
+        namesCount: 0,
+        exprIndex: 0,
+    };
+    let token = InstructionTextToken::from(&raw_token);
+    assert_eq!(token.text, "\"hello\"");
+    assert_eq!(
+        token.kind,
+        InstructionTextTokenKind::String {
+            ty: StringType::AsciiString
+        }
+    );
+}
@@ -0,0 +1,207 @@
+use binaryninja::binary_view::BinaryViewExt;
+use binaryninja::disassembly::{
+    DisassemblySettings, DisassemblyTextLine, InstructionTextToken, InstructionTextTokenKind,
+};
+use binaryninja::flowgraph::{
+    BranchType, EdgePenStyle, EdgeStyle, FlowGraph, FlowGraphNode, FlowGraphOption, ThemeColor,
+};
+use binaryninja::headless::Session;
+use rstest::*;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+#[fixture]
+#[once]
+fn session() -> Session {
+    Session::new().expect("Failed to initialize session")
+}
+
+#[rstest]
+fn test_restyle_edges(_session: &Session) {
+    let graph = FlowGraph::new();
+    let node_a = FlowGraphNode::new(&graph);
+    let node_b = FlowGraphNode::new(&graph);
+    let node_c = FlowGraphNode::new(&graph);
+    graph.append(&node_a);
+    graph.append(&node_b);
+    graph.append(&node_c);
+    node_a.add_outgoing_edge(BranchType::TrueBranch, &node_b, EdgeStyle::default());
+    node_a.add_outgoing_edge(BranchType::FalseBranch, &node_c, EdgeStyle::default());
+    node_b.add_outgoing_edge(
+        BranchType::UnconditionalBranch,
+        &node_c,
+        EdgeStyle::default(),
+    );
+
+    let dotted = EdgeStyle::new(
+        EdgePenStyle::DotLine,
+        2,
+        ThemeColor::BlueStandardHighlightColor,
+    );
+    graph.restyle_edges(|branch_type| match branch_type {
+        BranchType::TrueBranch => EdgeStyle::true_branch(),
+        _ => dotted,
+    });
+
+    assert_eq!(graph.get_node_count(), 3);
+    let new_a = graph.get_node(0).expect("Missing node");
+    let new_b = graph.get_node(1).expect("Missing node");
+    let new_c = graph.get_node(2).expect("Missing node");
+    let edges_a = new_a.outgoing_edges();
+    assert_eq!(edges_a.len(), 2);
+    for edge in &edges_a {
+        match edge.branch_type {
+            BranchType::TrueBranch => {
+                assert!(edge.target == new_b);
+                assert_eq!(edge.style, EdgeStyle::true_branch());
+            }
+            _ => {
+                assert!(edge.target == new_c);
+                assert_eq!(edge.style, dotted);
+            }
+        }
+    }
+    assert_eq!(new_b.outgoing_edges().len(), 1);
+    assert_eq!(new_c.incoming_edges().len(), 2);
+}
+
+#[rstest]
+fn test_edge_style_width(_session: &Session) {
+    let style = EdgeStyle::default();
+    assert_eq!(style.width(), EdgeStyle::DEFAULT_WIDTH);
+    assert_eq!(style.with_width(0), style);
+    assert_eq!(style.with_width(3).width(), 3);
+    let wide = EdgeStyle::new(EdgePenStyle::DotLine, 12, ThemeColor::AddressColor);
+    assert_eq!(wide.width(), 12);
+    assert_eq!(wide.style(), EdgePenStyle::DotLine);
+
+    assert_eq!(
+        EdgeStyle::from(BranchType::TrueBranch),
+        EdgeStyle::true_branch()
+    );
+    assert_eq!(
+        EdgeStyle::from(BranchType::IndirectBranch),
+        EdgeStyle::unconditional()
+    );
+}
+
+#[rstest]
+fn test_options(_session: &Session) {
+    let graph = FlowGraph::new();
+    for option in graph.options() {
+        graph.set_option(option, false);
+    }
+    assert!(graph.options().is_empty());
+    graph.set_option(FlowGraphOption::FlowGraphIsAddressable, true);
+    graph.set_option(FlowGraphOption::FlowGraphIncludesUserComments, true);
+    assert_eq!(
+        graph.options(),
+        vec![
+            FlowGraphOption::FlowGraphIncludesUserComments,
+            FlowGraphOption::FlowGraphIsAddressable,
+        ]
+    );
+}
+
+#[rstest]
+fn test_add_edges(_session: &Session) {
+    let graph = FlowGraph::new();
+    let node_a = FlowGraphNode::new(&graph);
+    let node_b = FlowGraphNode::new(&graph);
+    let node_c = FlowGraphNode::new(&graph);
+    graph.append(&node_a);
+    graph.append(&node_b);
+    graph.append(&node_c);
+    assert!(node_a.graph().is_some_and(|g| g == graph));
+    node_a.add_edges([
+        (
+            BranchType::TrueBranch,
+            node_b.as_ref(),
+            EdgeStyle::true_branch(),
+        ),
+        (
+            BranchType::FalseBranch,
+            node_c.as_ref(),
+            EdgeStyle::default(),
+        ),
+    ]);
+    assert_eq!(node_a.outgoing_edges().len(), 2);
+    assert_eq!(node_b.incoming_edges().len(), 1);
+    assert_eq!(node_c.incoming_edges().len(), 1);
+}
+
+#[rstest]
+fn test_to_text(_session: &Session) {
+    let graph = FlowGraph::new();
+    let node_a = FlowGraphNode::new(&graph);
+    let node_b = FlowGraphNode::new(&graph);
+    node_a.set_lines([DisassemblyTextLine::new_with_address(
+        0x1000,
+        vec![InstructionTextToken::new(
+            "nop",
+            InstructionTextTokenKind::Instruction,
+        )],
+    )]);
+    graph.append(&node_a);
+    graph.append(&node_b);
+    node_a.add_outgoing_edge(BranchType::TrueBranch, &node_b, EdgeStyle::default());
+    assert_eq!(
+        graph.to_text(),
+        "Node 0\n    0x1000  nop\n    -> Node 1 (TrueBranch)\nNode 1\n"
+    );
+    // Output is stable across calls.
+    assert_eq!(graph.to_text(), graph.to_text());
+}
+
+#[cfg(debug_assertions)]
+#[rstest]
+#[should_panic(expected = "different FlowGraph")]
+fn test_edge_across_graphs(_session: &Session) {
+    let graph_a = FlowGraph::new();
+    let graph_b = FlowGraph::new();
+    let node_a = FlowGraphNode::new(&graph_a);
+    let node_b = FlowGraphNode::new(&graph_b);
+    graph_a.append(&node_a);
+    graph_b.append(&node_b);
+    node_a.add_outgoing_edge(
+        BranchType::UnconditionalBranch,
+        &node_b,
+        EdgeStyle::default(),
+    );
+}
+
+#[rstest]
+fn test_from_function(_session: &Session) {
+    let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
+    let view = binaryninja::load(out_dir.join("atox.obj")).expect("Failed to create view");
+    let function = view
+        .functions()
+        .iter()
+        .max_by_key(|f| f.basic_blocks().len())
+        .map(|f| f.to_owned())
+        .expect("No functions in view");
+    let block_starts: HashSet<u64> = function
+        .basic_blocks()
+        .iter()
+        .map(|bb| bb.start_index())
+        .collect();
+
+    let settings = DisassemblySettings::new();
+    let graph = FlowGraph::from_function(&function, &settings);
+    assert_eq!(graph.get_node_count(), block_starts.len());
+    let node_starts: HashSet<u64> = graph
+        .nodes()
+        .iter()
+        .map(|node| node.basic_block_start().expect("Node has no basic block"))
+        .collect();
+    assert_eq!(node_starts, block_starts);
+
+    // Nodes created by hand have no basic block until one is set.
+    let node = FlowGraphNode::new(&graph);
+    assert_eq!(node.basic_block_start(), None);
+    let entry_block = function
+        .basic_block_containing(function.start(), None)
+        .expect("No entry block");
+    node.set_basic_block(Some(entry_block.as_ref()));
+    assert_eq!(node.basic_block_start(), Some(function.start()));
+}
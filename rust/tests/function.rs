@@ -0,0 +1,36 @@
+use binaryninja::function::{HighlightColor, HighlightStandardColor};
+use binaryninjacore_sys::{BNHighlightColor, BNHighlightColorStyle};
+
+#[test]
+fn test_highlight_color_round_trip() {
+    let standard = HighlightColor::standard(HighlightStandardColor::RedHighlightColor);
+    let raw_standard = BNHighlightColor::from(standard);
+    assert_eq!(
+        raw_standard.style,
+        BNHighlightColorStyle::StandardHighlightColor
+    );
+    assert_eq!(
+        raw_standard.color,
+        HighlightStandardColor::RedHighlightColor
+    );
+    assert_eq!(raw_standard.alpha, u8::MAX);
+    assert_eq!(HighlightColor::from(raw_standard), standard);
+
+    let custom = HighlightColor::custom(0x10, 0x20, 0x30, 0x80);
+    let raw_custom = BNHighlightColor::from(custom);
+    assert_eq!(
+        raw_custom.style,
+        BNHighlightColorStyle::CustomHighlightColor
+    );
+    assert_eq!(
+        (raw_custom.r, raw_custom.g, raw_custom.b, raw_custom.alpha),
+        (0x10, 0x20, 0x30, 0x80)
+    );
+    assert_eq!(HighlightColor::from(raw_custom), custom);
+
+    let none = HighlightColor::none();
+    let raw_none = BNHighlightColor::from(none);
+    assert_eq!(raw_none.color, HighlightStandardColor::NoHighlightColor);
+    assert_eq!(HighlightColor::from(raw_none), none);
+    assert_eq!(none, HighlightColor::default());
+}
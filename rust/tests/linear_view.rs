@@ -0,0 +1,224 @@
+use binaryninja::binary_view::BinaryViewExt;
+use binaryninja::disassembly::DisassemblySettings;
+use binaryninja::function::FunctionViewType;
+use binaryninja::headless::Session;
+use binaryninja::linear_view::{
+    language_representation_names, LinearDisassemblyLineType, LinearLineKind, LinearViewObject,
+};
+use rstest::*;
+use std::path::PathBuf;
+
+#[fixture]
+#[once]
+fn session() -> Session {
+    Session::new().expect("Failed to initialize session")
+}
+
+#[rstest]
+fn test_language_representation(_session: &Session) {
+    let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
+    let view = binaryninja::load(out_dir.join("atox.obj")).expect("Failed to create view");
+    let settings = DisassemblySettings::new();
+
+    let names = language_representation_names();
+    assert!(names.iter().any(|name| name == "Pseudo C"));
+    assert!(LinearViewObject::language_representation(&view, &settings, "Pseudo C").is_some());
+    assert!(
+        LinearViewObject::language_representation(&view, &settings, "Not A Language").is_none()
+    );
+}
+
+#[rstest]
+fn test_cursor_same_object(_session: &Session) {
+    let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
+    let view = binaryninja::load(out_dir.join("atox.obj")).expect("Failed to create view");
+    let settings = DisassemblySettings::new();
+
+    let disassembly = LinearViewObject::disassembly(&view, &settings);
+    let cursor = disassembly.create_cursor();
+    let other_cursor = disassembly.create_cursor();
+    assert!(cursor.same_object(&other_cursor));
+    assert!(cursor == other_cursor);
+
+    let hlil = LinearViewObject::hlil(&view, &settings);
+    let hlil_cursor = hlil.create_cursor();
+    assert!(!cursor.same_object(&hlil_cursor));
+}
+
+#[rstest]
+fn test_address_range(_session: &Session) {
+    let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
+    let view = binaryninja::load(out_dir.join("atox.obj")).expect("Failed to create view");
+    let settings = DisassemblySettings::new();
+    let function = view
+        .functions()
+        .iter()
+        .next()
+        .map(|f| f.to_owned())
+        .expect("No functions in view");
+
+    let disassembly = LinearViewObject::disassembly(&view, &settings);
+    let view_range = disassembly.address_range().expect("No address range");
+    assert!(view_range.contains(&function.start()));
+
+    let single_function = LinearViewObject::single_function_disassembly(&function, &settings);
+    let function_range = single_function.address_range().expect("No address range");
+    assert!(function_range.contains(&function.start()));
+    assert!(view_range.start <= function_range.start && function_range.end <= view_range.end);
+}
+
+#[rstest]
+fn test_single_function_range(_session: &Session) {
+    let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
+    let view = binaryninja::load(out_dir.join("atox.obj")).expect("Failed to create view");
+    let settings = DisassemblySettings::new();
+    let function = view
+        .functions()
+        .iter()
+        .max_by_key(|f| f.highest_address() - f.start())
+        .map(|f| f.to_owned())
+        .expect("No functions in view");
+
+    let range = function.start()..function.start() + 0x10;
+    for view_type in [FunctionViewType::Normal, FunctionViewType::HighLevelIL] {
+        let lines = LinearViewObject::single_function_range(
+            &function,
+            &settings,
+            range.clone(),
+            &view_type,
+        )
+        .expect("Failed to get function lines");
+        assert!(!lines.is_empty());
+        assert!(lines
+            .iter()
+            .all(|line| range.contains(&line.contents.address)));
+    }
+
+    let language = FunctionViewType::HighLevelLanguageRepresentation("Not A Language".into());
+    assert!(
+        LinearViewObject::single_function_range(&function, &settings, range, &language).is_none()
+    );
+}
+
+#[rstest]
+fn test_all_disassembly_tags(_session: &Session) {
+    let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
+    let view = binaryninja::load(out_dir.join("atox.obj")).expect("Failed to create view");
+    let settings = DisassemblySettings::new();
+    let function = view
+        .functions()
+        .iter()
+        .next()
+        .map(|f| f.to_owned())
+        .expect("No functions in view");
+
+    let tag_type = view.create_tag_type("Important", "!");
+    function.add_tag(&tag_type, "entry", Some(function.start()), true, None);
+    let tags = function.all_disassembly_tags(&settings);
+    assert!(tags
+        .iter()
+        .any(|(addr, tag)| *addr == function.start() && tag.data().as_str() == "entry"));
+}
+
+#[rstest]
+fn test_lines_owned(_session: &Session) {
+    let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
+    let view = binaryninja::load(out_dir.join("atox.obj")).expect("Failed to create view");
+    let settings = DisassemblySettings::new();
+    let function = view
+        .functions()
+        .iter()
+        .next()
+        .map(|f| f.to_owned())
+        .expect("No functions in view");
+
+    let object = LinearViewObject::single_function_disassembly(&function, &settings);
+    let mut cursor = object.create_cursor();
+    cursor.seek_to_start();
+    let mut buffered = Vec::new();
+    while !cursor.after_end() {
+        let lines = cursor.lines_owned();
+        assert_eq!(lines.len(), cursor.lines().len());
+        buffered.extend(lines);
+        if !cursor.next() {
+            break;
+        }
+    }
+    // The buffered lines outlive the cursor and the arrays they were copied from.
+    drop(cursor);
+    drop(object);
+    assert!(!buffered.is_empty());
+    assert!(buffered
+        .iter()
+        .filter_map(|line| line.function.as_ref())
+        .all(|line_function| line_function.start() == function.start()));
+    assert!(buffered.iter().any(|line| !line.to_string().is_empty()));
+}
+
+#[rstest]
+fn test_line_kind(_session: &Session) {
+    let code = LinearLineKind::from(LinearDisassemblyLineType::CodeDisassemblyLineType);
+    assert_eq!(code, LinearLineKind::Code);
+    assert!(code.is_code());
+    assert!(!code.is_function_header());
+    assert_eq!(
+        LinearDisassemblyLineType::from(code),
+        LinearDisassemblyLineType::CodeDisassemblyLineType
+    );
+
+    let header = LinearLineKind::from(LinearDisassemblyLineType::LocalVariableLineType);
+    assert!(header.is_function_header() && !header.is_code());
+    let section = LinearLineKind::from(LinearDisassemblyLineType::SectionStartLineType);
+    assert!(section.is_section_marker() && !section.is_separator());
+    let separator = LinearLineKind::from(LinearDisassemblyLineType::SectionSeparatorLineType);
+    assert!(separator.is_section_marker() && separator.is_separator());
+    let data = LinearLineKind::from(LinearDisassemblyLineType::HexDumpLineType);
+    assert!(data.is_data() && !data.is_code());
+
+    let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
+    let view = binaryninja::load(out_dir.join("atox.obj")).expect("Failed to create view");
+    let settings = DisassemblySettings::new();
+    let function = view
+        .functions()
+        .iter()
+        .next()
+        .map(|f| f.to_owned())
+        .expect("No functions in view");
+    let object = LinearViewObject::single_function_disassembly(&function, &settings);
+    let mut cursor = object.create_cursor();
+    cursor.seek_to_start();
+    let mut kinds = Vec::new();
+    while !cursor.after_end() {
+        kinds.extend(cursor.lines().iter().map(|line| line.kind()));
+        if !cursor.next() {
+            break;
+        }
+    }
+    assert!(kinds.iter().any(|kind| kind.is_code()));
+    assert!(kinds.iter().any(|kind| kind.is_function_header()));
+}
+
+#[rstest]
+fn test_data_lines_have_no_function(_session: &Session) {
+    let out_dir = env!("OUT_DIR").parse::<PathBuf>().unwrap();
+    let view = binaryninja::load(out_dir.join("atox.obj")).expect("Failed to create view");
+    assert!(!view.data_variables().is_empty());
+    let settings = DisassemblySettings::new();
+    let object = LinearViewObject::disassembly(&view, &settings);
+    let mut cursor = object.create_cursor();
+    cursor.seek_to_start();
+    let mut data_lines = 0;
+    while !cursor.after_end() {
+        for line in &cursor.lines() {
+            if line.kind() == LinearLineKind::DataVariable {
+                assert!(line.function.is_none());
+                assert!(line.basic_block.is_none());
+                data_lines += 1;
+            }
+        }
+        if !cursor.next() {
+            break;
+        }
+    }
+    assert!(data_lines > 0);
+}
@@ -0,0 +1,19 @@
+use binaryninja::headless::Session;
+use binaryninja::logger::{Logger, LoggerError};
+use rstest::*;
+
+#[fixture]
+#[once]
+fn session() -> Session {
+    Session::new().expect("Failed to initialize session")
+}
+
+#[rstest]
+fn test_try_new(_session: &Session) {
+    let logger = Logger::try_new("My Plugin").expect("Failed to create logger");
+    assert_eq!(logger.name().as_str(), "My Plugin");
+    assert_eq!(
+        Logger::try_new("My\0Plugin").err(),
+        Some(LoggerError::InvalidName("My\0Plugin".to_string()))
+    );
+}